@@ -0,0 +1,110 @@
+# bash completion for horme, load it with: source <(horme completions bash)
+
+# The file the command runs on, given with --file
+_horme_file() {
+    local i
+    for ((i = 2; i < COMP_CWORD; i++)); do
+        if [[ ${COMP_WORDS[i]} == --file ]]; then
+            printf '%s\n' "${COMP_WORDS[i + 1]}"
+            return
+        fi
+    done
+    printf 'todo.md\n'
+}
+
+# Task ids starting with $1, shown with their names when there are several
+_horme_ids() {
+    local id name candidates=()
+    while IFS=$'\t' read -r id name; do
+        [[ $id == "$1"* ]] && candidates+=("$id  $name")
+    done < <(horme __complete ids "$(_horme_file)" 2>/dev/null)
+    if ((${#candidates[@]} == 1)); then
+        COMPREPLY=("${candidates[0]%%  *}")
+    else
+        COMPREPLY=("${candidates[@]}")
+    fi
+}
+
+_horme() {
+    local cur=${COMP_WORDS[COMP_CWORD]} prev=${COMP_WORDS[COMP_CWORD - 1]}
+    local command=${COMP_WORDS[1]} words=()
+    # Words are listed one per line, view and file names can have spaces
+    local IFS=$'\n'
+    if ((COMP_CWORD == 1)); then
+        words=(@COMMANDS@)
+        COMPREPLY=($(compgen -W "${words[*]}" -- "$cur"))
+        return
+    fi
+
+    case $prev in
+    --file | --output | -o)
+        COMPREPLY=($(compgen -f -- "$cur"))
+        return
+        ;;
+    --format | -f)
+        case $command in
+        export | import) words=(@FORMATS@) ;;
+        stats) words=(text json) ;;
+        *) words=(@OUTPUT_FORMATS@) ;;
+        esac
+        COMPREPLY=($(compgen -W "${words[*]}" -- "$cur"))
+        return
+        ;;
+    --theme)
+        words=(light tui)
+        COMPREPLY=($(compgen -W "${words[*]}" -- "$cur"))
+        return
+        ;;
+    --older-than | --due | --name | --description | -n | -d)
+        return
+        ;;
+    esac
+
+    case $command in
+    edit)
+        # The id follows the command, or a --file given before it
+        if ((COMP_CWORD == 2)) || ((COMP_CWORD == 4)) && [[ ${COMP_WORDS[2]} == --file ]]; then
+            _horme_ids "$cur"
+            return
+        fi
+        words=(--name --description --editor)
+        ;;
+    edit-list | delete | complete | tag)
+        # Ids, ranges or a query, after the tags to add or remove
+        if [[ $command == tag && $cur == [+-]* && $cur != *:* ]]; then
+            local tag
+            for tag in $(horme __complete tags "$(_horme_file)" 2>/dev/null); do
+                words+=("${cur:0:1}$tag")
+            done
+        elif [[ -z $cur || $cur == [0-9]* ]]; then
+            _horme_ids "$cur"
+            return
+        else
+            words=(--file @QUERY_FIELDS@)
+        fi
+        ;;
+    list | ls) words=(--format --file --all --completed --incomplete --due @QUERY_FIELDS@) ;;
+    add | help) words=(--file) ;;
+    view)
+        if ((COMP_CWORD == 2)); then
+            COMPREPLY=($(compgen -W "$(horme __complete views 2>/dev/null)" -- "$cur"))
+            return
+        fi
+        words=(--format)
+        ;;
+    today | upcoming | overdue | someday) words=(--format) ;;
+    archive) words=(--older-than) ;;
+    stats) words=(--format) ;;
+    export) words=(--format --theme --output) ;;
+    import) words=(--format --dry-run) ;;
+    completions) words=(bash zsh fish) ;;
+    esac
+    COMPREPLY=($(compgen -W "${words[*]}" -- "$cur"))
+    case $command in
+    check | shell | view | today | upcoming | overdue | someday | archive | stats | export | import)
+        COMPREPLY+=($(compgen -f -- "$cur"))
+        ;;
+    esac
+}
+
+complete -F _horme horme
@@ -0,0 +1,61 @@
+# fish completion for horme, load it with: horme completions fish | source
+
+# The file the command runs on, given with --file
+function __horme_file
+    set -l tokens (commandline -opc)
+    set -l i (contains -i -- --file $tokens)
+    and set -q tokens[(math $i + 1)]
+    and echo $tokens[(math $i + 1)]
+    or echo todo.md
+end
+
+# True when a task id is being typed: the one of an edit command, or any of
+# the ids of edit-list, delete, complete and tag
+function __horme_needs_id
+    set -l tokens (commandline -opc)
+    contains -- "$tokens[2]" edit-list delete complete tag
+    and return 0
+    test "$tokens[2]" = edit
+    or return 1
+    # The id follows the command, or a --file given before it
+    test (count $tokens) -eq 2
+    or begin
+        test (count $tokens) -eq 4
+        and test "$tokens[3]" = --file
+    end
+end
+
+# The tags of the list, to add with + or remove with -
+function __horme_tags
+    for tag in (horme __complete tags (__horme_file) 2>/dev/null)
+        echo +$tag
+        echo -$tag
+    end
+end
+
+set -l commands @COMMAND_NAMES@
+set -l views today upcoming overdue someday view
+
+complete -c horme -f
+@COMMANDS@
+complete -c horme -n __horme_needs_id -a "(horme __complete ids (__horme_file) 2>/dev/null)"
+complete -c horme -n "__fish_seen_subcommand_from edit" -s n -l name -x -d "New name"
+complete -c horme -n "__fish_seen_subcommand_from edit" -s d -l description -x -d "New description"
+complete -c horme -n "__fish_seen_subcommand_from edit" -s e -l editor -d "Edit the task in \$EDITOR"
+complete -c horme -n "__fish_seen_subcommand_from tag" -a "(__horme_tags)"
+complete -c horme -n "__fish_seen_subcommand_from add list ls edit edit-list delete complete tag help" -l file -r -F -d "List to run on"
+complete -c horme -n "__fish_seen_subcommand_from list ls" -s a -l all -d "All tasks"
+complete -c horme -n "__fish_seen_subcommand_from list ls" -s c -l completed -d "Done tasks"
+complete -c horme -n "__fish_seen_subcommand_from list ls" -s i -l incomplete -d "Tasks left to do"
+complete -c horme -n "__fish_seen_subcommand_from list ls" -s d -l due -x -d "Tasks due on a date"
+complete -c horme -n "__fish_seen_subcommand_from list ls edit-list delete complete tag" -a "@QUERY_FIELDS@"
+complete -c horme -n "__fish_seen_subcommand_from list ls $views" -s f -l format -x -a "@OUTPUT_FORMATS@" -d "Output format"
+complete -c horme -n "__fish_seen_subcommand_from view; and test (count (commandline -opc)) -eq 2" -a "(horme __complete views 2>/dev/null)"
+complete -c horme -n "__fish_seen_subcommand_from $views check shell archive stats export import" -F
+complete -c horme -n "__fish_seen_subcommand_from archive" -s o -l older-than -x -d "Only tasks done for more days"
+complete -c horme -n "__fish_seen_subcommand_from export import" -s f -l format -x -a "@FORMATS@" -d "File format"
+complete -c horme -n "__fish_seen_subcommand_from export" -l theme -x -a "light tui" -d "HTML theme"
+complete -c horme -n "__fish_seen_subcommand_from export" -s o -l output -r -F -d "File to write"
+complete -c horme -n "__fish_seen_subcommand_from import" -s n -l dry-run -d "Only describe the import"
+complete -c horme -n "__fish_seen_subcommand_from stats" -s f -l format -x -a "text json" -d "Report format"
+complete -c horme -n "__fish_seen_subcommand_from completions" -a "bash zsh fish"
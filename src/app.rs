@@ -1,5 +1,5 @@
 use color_eyre::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent};
 
 use ratatui::{
     backend::Backend,
@@ -11,44 +11,73 @@ use ratatui::{
     text::Line,
     widgets::{
         Block, Borders, HighlightSpacing, List, ListItem, ListState, Padding, Paragraph,
-        StatefulWidget, Widget, Wrap,
+        StatefulWidget, Tabs, Widget, Wrap,
     },
 };
 
-use std::{
-    collections::HashMap,
-    fs,
-    path::{Path, PathBuf},
-};
+use std::{collections::BTreeSet, path::Path, thread};
 
-const TODO_HEADER_STYLE: Style = Style::new()
+pub(crate) const TODO_HEADER_STYLE: Style = Style::new()
     .fg(SLATE.c800)
     .bg(AMBER.c400)
     .add_modifier(Modifier::BOLD);
-const NORMAL_ROW_BG: Color = SLATE.c950;
-const ALT_ROW_BG_COLOR: Color = SLATE.c900;
-const TEXT_FG_COLOR: Color = SLATE.c200;
-const TODO_FG_COLOR: Color = AMBER.c500;
-const DOING_FG_COLOR: Color = GREEN.c500;
-const DONE_FG_COLOR: Color = SLATE.c500;
+pub(crate) const NORMAL_ROW_BG: Color = SLATE.c950;
+pub(crate) const ALT_ROW_BG_COLOR: Color = SLATE.c900;
+pub(crate) const TEXT_FG_COLOR: Color = SLATE.c200;
+pub(crate) const TODO_FG_COLOR: Color = AMBER.c500;
+pub(crate) const DOING_FG_COLOR: Color = GREEN.c500;
+pub(crate) const DONE_FG_COLOR: Color = SLATE.c500;
 const TEXT_FG_EDITING: Color = AMBER.c400;
 const TEXT_FG_ADDING: Color = GREEN.c400;
 const TEXT_FG_DELETING: Color = RED.c400;
-const COMPLETED_TEXT_FG_COLOR: Color = GREEN.c500;
-
-use crate::task::{Status, Subtask, Task};
+pub(crate) const COMPLETED_TEXT_FG_COLOR: Color = GREEN.c500;
+
+use crate::archive::{archive_path, archive_section, load_archive};
+use crate::bulk::{change_status, TagEdit};
+use crate::dashboard::Dashboard;
+use crate::editor::{edit_list_in_editor, edit_task_in_editor, editor_command};
+use crate::events::{AppEvent, Events, Message};
+use crate::markdown::Severity;
+use crate::query::{Query, QueryError};
+use crate::quickadd::parse_quick_add;
+use crate::stats::Stats;
+use crate::storage::storage_for;
+use crate::tab::{FileChange, Tab};
+use crate::task::{new_uid, now, Status, Subtask, Task, DATE_FORMAT, DATE_TIME_FORMAT};
+use crate::tui::{init_terminal, restore_terminal};
+use crate::view::{SavedView, View};
+use crate::watch::FileWatcher;
 
 //#[derive(Debug)]
 pub struct App {
     pub name_input: String,
     pub description_input: String,
-    pub todo_list: TodoList,
+    pub tabs: Vec<Tab>,
+    pub current_tab: usize,
     pub subtask_list: SubtaskList,
-    pub file_path: PathBuf,
-    pub sections_order: Vec<String>,
     pub should_exit: bool,
     pub current_screen: CurrentScreen,
     pub currently_editing: Option<CurrentlyEditing>,
+    pub status_message: Option<String>,
+    // Where the selected task goes when moving or copying it to another tab
+    pub transfer: Option<Transfer>,
+    // Tasks archived from the current tab, while browsing them
+    pub archive_list: TodoList,
+    // Views of the lists shown in the header, the current one filters the tasks
+    pub views: Vec<View>,
+    pub current_view: usize,
+    // Position of the selected task among those of the current view
+    pub view_state: ListState,
+    // Query typed at the filter prompt, the tasks shown also match `filter`
+    pub filter_input: String,
+    pub filter: Option<Query>,
+    pub filter_error: Option<QueryError>,
+    // Ids of the tasks of the current tab marked to act on them together
+    pub marked: BTreeSet<u16>,
+    // What is to be edited in $EDITOR, once the terminal is handed over to it
+    pub pending_editor: Option<EditorTarget>,
+    // Terminal events, ticks and the messages of the work done in the background
+    pub events: Events,
 }
 
 #[derive(Debug, Default)]
@@ -71,6 +100,14 @@ pub enum CurrentScreen {
     AddTask,
     Deleting,
     AddSubtask,
+    Conflict,
+    Warnings,
+    Transfer,
+    Archive,
+    Filter,
+    Select,
+    Tagging,
+    Dashboard,
 }
 
 pub enum CurrentlyEditing {
@@ -78,63 +115,88 @@ pub enum CurrentlyEditing {
     Description,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EditorTarget {
+    // The selected task
+    Task,
+    // The tasks of the current view, all at once
+    List,
+}
+
+pub struct Transfer {
+    pub target: usize,
+    // Keep the task in the current tab too
+    pub copy: bool,
+}
+
 impl Default for App {
     fn default() -> Self {
         Self {
             name_input: String::new(),
             description_input: String::new(),
             should_exit: false,
-            file_path: Path::new("default.md").to_path_buf(),
-            sections_order: vec![
-                "## Todo".to_string(),
-                "## Doing".to_string(),
-                "## Done".to_string(),
-            ],
-            todo_list: TodoList::from_iter([
-                (
-                    "Check synthetic eyes",
-                    "Are you sure this isn't a replicant?",
-                    Status::Doing,
-                ),
-                (
-                    "Investigate Tyrell Corporation",
-                    "The secrets they're hiding...",
-                    Status::Todo,
-                ),
-                (
-                    "Complete the Dragonborn questline",
-                    "You have what it takes to save Tamriel!",
-                    Status::Done,
-                ),
-                (
-                    "Kill as many dragons as possible",
-                    "Those fire-breathers need puttin' down!",
-                    Status::Todo,
-                ),
-                (
-                    "Take a break, dude",
-                    "Life's too short for bowling alleys and White Russians.",
-                    Status::Todo,
-                ),
-                (
-                    "Find the missing rug",
-                    "Man, that rug really tied the room together...",
-                    Status::Done,
-                ),
-                (
-                    "Visit the planet Frogstar World B",
-                    "A great place for a holiday... or so I've heard.",
-                    Status::Todo,
-                ),
-                (
-                    "Don't forget your towel",
-                    "You never know when you might need it!",
-                    Status::Done,
-                ),
-            ]),
+            tabs: vec![Tab::new(
+                TodoList::from_iter([
+                    (
+                        "Check synthetic eyes",
+                        "Are you sure this isn't a replicant?",
+                        Status::Doing,
+                    ),
+                    (
+                        "Investigate Tyrell Corporation",
+                        "The secrets they're hiding...",
+                        Status::Todo,
+                    ),
+                    (
+                        "Complete the Dragonborn questline",
+                        "You have what it takes to save Tamriel!",
+                        Status::Done,
+                    ),
+                    (
+                        "Kill as many dragons as possible",
+                        "Those fire-breathers need puttin' down!",
+                        Status::Todo,
+                    ),
+                    (
+                        "Take a break, dude",
+                        "Life's too short for bowling alleys and White Russians.",
+                        Status::Todo,
+                    ),
+                    (
+                        "Find the missing rug",
+                        "Man, that rug really tied the room together...",
+                        Status::Done,
+                    ),
+                    (
+                        "Visit the planet Frogstar World B",
+                        "A great place for a holiday... or so I've heard.",
+                        Status::Todo,
+                    ),
+                    (
+                        "Don't forget your towel",
+                        "You never know when you might need it!",
+                        Status::Done,
+                    ),
+                ])
+                .items,
+                Path::new("default.md"),
+            )],
+            current_tab: 0,
             subtask_list: SubtaskList::default(),
             current_screen: CurrentScreen::Main,
             currently_editing: None,
+            status_message: None,
+            transfer: None,
+            archive_list: TodoList::default(),
+            views: View::all(&[]),
+            current_view: 0,
+            view_state: ListState::default(),
+            filter_input: String::new(),
+            filter: None,
+            filter_error: None,
+            marked: BTreeSet::new(),
+            pending_editor: None,
+            events: Events::default(),
         }
     }
 }
@@ -165,51 +227,147 @@ impl FromIterator<&'static str> for SubtaskList {
 }
 
 impl App {
-    pub fn new(todo_list: Vec<Task>, file_path: &Path) -> Self {
-        let state = ListState::default();
-
+    pub fn new(tabs: Vec<Tab>) -> Self {
         Self {
             name_input: String::new(),
             description_input: String::new(),
-            todo_list: TodoList {
-                state,
-                items: todo_list,
-            },
+            tabs,
+            current_tab: 0,
             subtask_list: SubtaskList::default(),
-            file_path: file_path.to_path_buf(),
-            sections_order: vec![
-                "## Todo".to_string(),
-                "## Doing".to_string(),
-                "## Done".to_string(),
-            ],
             should_exit: false,
             current_screen: CurrentScreen::Main,
             currently_editing: None,
+            status_message: None,
+            transfer: None,
+            archive_list: TodoList::default(),
+            views: View::all(&[]),
+            current_view: 0,
+            view_state: ListState::default(),
+            filter_input: String::new(),
+            filter: None,
+            filter_error: None,
+            marked: BTreeSet::new(),
+            pending_editor: None,
+            events: Events::default(),
         }
     }
+
+    // Show the views of the configuration after the smart views
+    pub fn add_saved_views(&mut self, saved: &[SavedView]) {
+        self.views.extend(saved.iter().cloned().map(View::Saved));
+    }
+
+    pub fn tab(&self) -> &Tab {
+        &self.tabs[self.current_tab]
+    }
+
+    pub fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.current_tab]
+    }
     // runs the application's main loop until the user quits
     pub fn run(&mut self, mut terminal: Terminal<impl Backend>) -> Result<()> {
         while !self.should_exit {
             terminal.draw(|f| f.render_widget(&mut *self, f.size()))?;
-            if let Event::Key(key) = event::read()? {
-                let _ = self.handle_key_event(key);
-            };
+            match self.events.next()? {
+                AppEvent::Key(key) => {
+                    let _ = self.handle_key_event(key);
+                }
+                // Drawn again at the new size
+                AppEvent::Resize(..) => {}
+                AppEvent::FocusLost => {}
+                // The files may have been changed in another window meanwhile
+                AppEvent::FocusGained | AppEvent::Tick => self.on_tick(),
+                AppEvent::Message(message) => self.handle_message(message),
+            }
+            if let Some(target) = self.pending_editor.take() {
+                // The editor takes over the terminal until it exits, the
+                // lock stays ours meanwhile
+                restore_terminal()?;
+                let heartbeat = self.tab().keep_lock_alive();
+                match target {
+                    EditorTarget::Task => self.edit_in_editor(),
+                    EditorTarget::List => self.edit_list_in_editor(),
+                }
+                drop(heartbeat);
+                init_terminal()?;
+                terminal.clear()?;
+            }
         }
         Ok(())
     }
 
+    // Time-driven work, done on every tick
+    pub fn on_tick(&mut self) {
+        self.reload_changed_files();
+        self.sync_lock();
+    }
+
+    // Run `work` on another thread, the main loop handles its message once
+    // it is over
+    fn spawn(&self, work: impl FnOnce() -> Message + Send + 'static) {
+        let sender = self.events.sender();
+        thread::spawn(move || {
+            // The app may have quit meanwhile
+            let _ = sender.send(work());
+        });
+    }
+
+    // Take in the result of work done in the background
+    pub fn handle_message(&mut self, message: Message) {
+        match message {
+            Message::Loaded {
+                file_path,
+                load,
+                saves,
+                seen,
+                loaded,
+            } => {
+                let Some(i) = self.tabs.iter().position(|tab| tab.file_path == file_path) else {
+                    return;
+                };
+                let tab = &mut self.tabs[i];
+                // Only one conflict is handled at a time, the file stays unseen
+                // and is read again once it is solved
+                if self.current_screen == CurrentScreen::Conflict {
+                    if tab.loads == load {
+                        tab.loading = false;
+                    }
+                    return;
+                }
+                let change = tab.use_background_load(load, saves, seen, loaded);
+                self.handle_file_change(i, change);
+            }
+        }
+    }
+
     pub fn handle_key_event(&mut self, key_event: KeyEvent) -> Result<()> {
         match self.current_screen {
             CurrentScreen::Main => match key_event.code {
                 KeyCode::Char('j') => self.select_next(),
                 KeyCode::Char('k') => self.select_previous(),
                 KeyCode::Char('c') => self.start_editing(),
+                KeyCode::Char('e') => self.start_editing_in_editor(),
+                KeyCode::Char('E') => self.start_editing_list_in_editor(),
+                KeyCode::Char('u') => self.undo(),
                 KeyCode::Char('s') => self.start_subtask(),
                 KeyCode::Char('a') => self.start_adding(),
                 KeyCode::Char('d') => self.start_deleting(),
                 KeyCode::Down => self.select_next(),
                 KeyCode::Up => self.select_previous(),
                 KeyCode::Enter => self.toggle_status(),
+                KeyCode::Char('w') => self.start_showing_warnings(),
+                KeyCode::Char('x') => self.archive_done_tasks(),
+                KeyCode::Char('X') => self.start_browsing_archive(),
+                KeyCode::Char('m') => self.start_transfer(false),
+                KeyCode::Char('y') => self.start_transfer(true),
+                KeyCode::Tab => self.select_next_tab(),
+                KeyCode::BackTab => self.select_previous_tab(),
+                KeyCode::Char(c @ '1'..='9') => self.select_tab(c as usize - '1' as usize),
+                KeyCode::Char('v') => self.select_next_view(),
+                KeyCode::Char('V') => self.select_previous_view(),
+                KeyCode::Char('/') => self.start_filtering(),
+                KeyCode::Char(' ') => self.start_selecting(),
+                KeyCode::Char('D') => self.current_screen = CurrentScreen::Dashboard,
                 KeyCode::Char('q') => self.exit(),
                 _ => {}
             },
@@ -250,20 +408,186 @@ impl App {
                 KeyCode::Backspace => self.handle_backspace(),
                 _ => {}
             },
+            CurrentScreen::Conflict => match key_event.code {
+                KeyCode::Char('r') => self.reload_remote_list(),
+                KeyCode::Char('k') => self.keep_local_list(),
+                KeyCode::Char('m') => self.merge_remote_list(),
+                _ => {}
+            },
+            CurrentScreen::Transfer => match key_event.code {
+                KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => {
+                    self.select_next_transfer_target()
+                }
+                KeyCode::Char('h') | KeyCode::Left | KeyCode::BackTab => {
+                    self.select_previous_transfer_target()
+                }
+                KeyCode::Enter => self.transfer_task(),
+                KeyCode::Esc => self.cancel_transfer(),
+                _ => {}
+            },
+            CurrentScreen::Archive => match key_event.code {
+                KeyCode::Char('j') | KeyCode::Down => self.archive_list.state.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.archive_list.state.select_previous(),
+                KeyCode::Char('r') => self.restore_archived_task(),
+                KeyCode::Esc | KeyCode::Char('X') => self.cancel_browsing_archive(),
+                _ => {}
+            },
+            CurrentScreen::Filter => match key_event.code {
+                KeyCode::Enter => self.apply_filter(),
+                KeyCode::Esc => self.clear_filter(),
+                KeyCode::Char(c) => {
+                    self.filter_input.push(c);
+                    self.update_filter();
+                }
+                KeyCode::Backspace => {
+                    self.filter_input.pop();
+                    self.update_filter();
+                }
+                _ => {}
+            },
+            CurrentScreen::Warnings => match key_event.code {
+                KeyCode::Esc | KeyCode::Enter | KeyCode::Char('w') => {
+                    self.cancel_showing_warnings()
+                }
+                _ => {}
+            },
+            CurrentScreen::Select => match key_event.code {
+                KeyCode::Char('j') | KeyCode::Down => self.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.select_previous(),
+                KeyCode::Char(' ') => self.toggle_mark(),
+                KeyCode::Char('*') => self.toggle_all_marks(),
+                KeyCode::Enter => self.change_marked_status(None),
+                KeyCode::Char('t') => self.change_marked_status(Some(Status::Todo)),
+                KeyCode::Char('p') => self.change_marked_status(Some(Status::Doing)),
+                KeyCode::Char('c') => self.change_marked_status(Some(Status::Done)),
+                KeyCode::Char('d') => self.start_deleting(),
+                KeyCode::Char('m') => self.start_transfer(false),
+                KeyCode::Char('y') => self.start_transfer(true),
+                KeyCode::Char('#') => self.start_tagging(),
+                KeyCode::Esc => self.cancel_selecting(),
+                _ => {}
+            },
+            CurrentScreen::Tagging => match key_event.code {
+                KeyCode::Enter => self.tag_marked_tasks(),
+                KeyCode::Esc => self.cancel_tagging(),
+                KeyCode::Char(c) => self.handle_editing_input(c),
+                KeyCode::Backspace => self.handle_backspace(),
+                _ => {}
+            },
+            CurrentScreen::Dashboard => match key_event.code {
+                KeyCode::Char('D') | KeyCode::Esc => self.current_screen = CurrentScreen::Main,
+                KeyCode::Tab => self.select_next_tab(),
+                KeyCode::BackTab => self.select_previous_tab(),
+                KeyCode::Char(c @ '1'..='9') => self.select_tab(c as usize - '1' as usize),
+                KeyCode::Char('q') => self.exit(),
+                _ => {}
+            },
         }
         Ok(())
     }
 
     fn select_next(&mut self) {
-        self.todo_list.state.select_next();
+        let visible = self.visible_tasks();
+        let next = match self.visible_position(&visible) {
+            Some(position) => visible.get(position + 1).or(visible.last()),
+            None => visible.first(),
+        };
+        self.tab_mut().todo_list.state.select(next.copied());
         self.create_subtask_list();
     }
 
     fn select_previous(&mut self) {
-        self.todo_list.state.select_previous();
+        let visible = self.visible_tasks();
+        let previous = match self.visible_position(&visible) {
+            Some(position) => visible.get(position.saturating_sub(1)),
+            None => visible.last(),
+        };
+        self.tab_mut().todo_list.state.select(previous.copied());
+        self.create_subtask_list();
+    }
+
+    // Indices of the tasks of the current tab shown in the current view
+    fn visible_tasks(&self) -> Vec<usize> {
+        let today = now().date();
+        let items = &self.tab().todo_list.items;
+        let mut visible = self.views[self.current_view].select(items, today);
+        if let Some(filter) = &self.filter {
+            visible.retain(|i| filter.matches(&items[*i], today));
+        }
+        visible
+    }
+
+    fn start_filtering(&mut self) {
+        self.current_screen = CurrentScreen::Filter;
+    }
+
+    // Filter the tasks as the query is typed, keeping the last valid query
+    // while it is being written
+    fn update_filter(&mut self) {
+        match Query::parse(&self.filter_input) {
+            Ok(query) => {
+                self.filter = (!query.is_empty()).then_some(query);
+                self.filter_error = None;
+            }
+            Err(e) => self.filter_error = Some(e),
+        }
+    }
+
+    fn apply_filter(&mut self) {
+        if self.filter_error.is_none() {
+            self.current_screen = CurrentScreen::Main;
+        }
+    }
+
+    fn clear_filter(&mut self) {
+        self.filter_input.clear();
+        self.filter = None;
+        self.filter_error = None;
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    // Position of the selected task among the visible ones
+    fn visible_position(&self, visible: &[usize]) -> Option<usize> {
+        let selected = self.tab().todo_list.state.selected()?;
+        visible.iter().position(|i| *i == selected)
+    }
+
+    // Keep the selection on a visible task, the one after the selected task
+    // when it left the view
+    fn sync_view_selection(&mut self, visible: &[usize]) {
+        let selected = self.tab().todo_list.state.selected();
+        if selected.is_none_or(|selected| visible.contains(&selected)) {
+            return;
+        }
+        let next = selected
+            .and_then(|selected| visible.iter().find(|i| **i > selected))
+            .or(visible.last())
+            .copied();
+        self.tab_mut().todo_list.state.select(next);
         self.create_subtask_list();
     }
 
+    fn select_view(&mut self, index: usize) {
+        self.current_view = index;
+        self.view_state = ListState::default();
+        let visible = self.visible_tasks();
+        if self.visible_position(&visible).is_none() {
+            self.tab_mut()
+                .todo_list
+                .state
+                .select(visible.first().copied());
+            self.create_subtask_list();
+        }
+    }
+
+    fn select_next_view(&mut self) {
+        self.select_view((self.current_view + 1) % self.views.len());
+    }
+
+    fn select_previous_view(&mut self) {
+        self.select_view((self.current_view + self.views.len() - 1) % self.views.len());
+    }
+
     fn create_subtask_list(&mut self) {
         // Clear the subtask_list and populate the subtask_list with the subtasks of the selected item,
         // if there are any. Otherwise, just clear the subtask_list
@@ -272,13 +596,13 @@ impl App {
             items: Vec::new(),
         };
 
-        let i = match self.todo_list.state.selected() {
+        let i = match self.tab().todo_list.state.selected() {
             Some(i) => i,
             None => return,
         };
 
-        if i < self.todo_list.items.len() {
-            let subtasks = self.todo_list.items[i].subtasks.clone();
+        if i < self.tab().todo_list.items.len() {
+            let subtasks = self.tab().todo_list.items[i].subtasks.clone();
             self.subtask_list = SubtaskList {
                 state: ListState::default(),
                 items: subtasks,
@@ -295,71 +619,627 @@ impl App {
     }
 
     fn toggle_status(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            self.todo_list.items[i].update_status();
+        if !self.can_edit() {
+            return;
+        }
+        let todo_list = &mut self.tab_mut().todo_list;
+        let Some(i) = todo_list.state.selected() else {
+            return;
+        };
+        todo_list.items[i].update_status();
+        // A recurring task comes back once it is done
+        let task = &mut todo_list.items[i];
+        if task.status == Status::Done {
+            if let Some(next) = task.next_occurrence(now().date()) {
+                self.status_message = Some(format!(
+                    "\"{}\" is due again on {}",
+                    next.name,
+                    next.due_date.unwrap_or_default().format(DATE_FORMAT)
+                ));
+                self.tab_mut().todo_list.items.push(next);
+            }
+        }
+    }
+
+    // Mark the selected task and start picking the tasks to act on together
+    fn start_selecting(&mut self) {
+        if self.tab().todo_list.state.selected().is_none() {
+            return;
         }
+        self.current_screen = CurrentScreen::Select;
+        self.toggle_mark();
+    }
+
+    // Mark or unmark the selected task, and go to the next one
+    fn toggle_mark(&mut self) {
+        let todo_list = &self.tab().todo_list;
+        let Some(i) = todo_list.state.selected() else {
+            return;
+        };
+        let id = todo_list.items[i].id;
+        if !self.marked.remove(&id) {
+            self.marked.insert(id);
+        }
+        self.select_next();
+    }
+
+    // Mark every task of the view, or none when they are all marked already
+    fn toggle_all_marks(&mut self) {
+        let items = &self.tab().todo_list.items;
+        let ids: Vec<u16> = self
+            .visible_tasks()
+            .into_iter()
+            .map(|i| items[i].id)
+            .collect();
+        if ids.iter().all(|id| self.marked.contains(id)) {
+            self.marked.retain(|id| !ids.contains(id));
+        } else {
+            self.marked.extend(ids);
+        }
+    }
+
+    fn cancel_selecting(&mut self) {
+        self.marked.clear();
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    // The screen to go back to once an action is done or cancelled
+    fn return_screen(&self) -> CurrentScreen {
+        if self.marked.is_empty() {
+            CurrentScreen::Main
+        } else {
+            CurrentScreen::Select
+        }
+    }
+
+    // Indices of the tasks an action applies to: the marked tasks, or the
+    // selected one when none is marked
+    fn target_tasks(&self) -> Vec<usize> {
+        let todo_list = &self.tab().todo_list;
+        if self.marked.is_empty() {
+            return todo_list.state.selected().into_iter().collect();
+        }
+        (0..todo_list.items.len())
+            .filter(|i| self.marked.contains(&todo_list.items[*i].id))
+            .collect()
+    }
+
+    // Move the marked tasks to `status`, or each to its next status
+    fn change_marked_status(&mut self, status: Option<Status>) {
+        if !self.can_edit() {
+            return;
+        }
+        let ids: Vec<u16> = self.marked.iter().copied().collect();
+        let next = change_status(
+            &mut self.tab_mut().todo_list.items,
+            &ids,
+            status,
+            now().date(),
+        );
+        let mut message = match status {
+            Some(status) => format!("Moved {} tasks to {}", ids.len(), status),
+            None => format!("Moved {} tasks to their next status", ids.len()),
+        };
+        // Recurring tasks come back once they are done
+        if !next.is_empty() {
+            message.push_str(&format!(", {} recurring tasks are due again", next.len()));
+        }
+        self.status_message = Some(message);
+        self.create_subtask_list();
+    }
+
+    fn start_tagging(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        self.name_input = String::new();
+        self.current_screen = CurrentScreen::Tagging;
+        self.currently_editing = Some(CurrentlyEditing::Name);
+    }
+
+    // Add the `+tags` and remove the `-tags` typed from the marked tasks
+    fn tag_marked_tasks(&mut self) {
+        let words: Vec<&str> = self.name_input.split_whitespace().collect();
+        let (edit, read) = TagEdit::parse(&words);
+        if edit.is_empty() || read < words.len() {
+            self.status_message = Some("Type +tag to add a tag and -tag to remove it".to_string());
+            return;
+        }
+        let mut changed = 0;
+        for i in self.target_tasks() {
+            if edit.apply(&mut self.tab_mut().todo_list.items[i]) {
+                changed += 1;
+            }
+        }
+        self.status_message = Some(format!("Changed the tags of {} tasks", changed));
+        self.current_screen = self.return_screen();
+        self.currently_editing = None;
+    }
+
+    fn cancel_tagging(&mut self) {
+        self.current_screen = self.return_screen();
+        self.currently_editing = None;
     }
 
     fn exit(&mut self) {
-        self.save_todo_list();
+        // Never overwrite changes made on disk without asking first
+        self.check_external_changes();
+        if self.current_screen == CurrentScreen::Conflict {
+            return;
+        }
+        for tab in &mut self.tabs {
+            if tab.has_unsaved_changes() {
+                if let Err(e) = tab.save() {
+                    // Stay open rather than losing the changes
+                    self.status_message = Some(format!("{:#}", e));
+                    return;
+                }
+            }
+            tab.lock = None;
+        }
         self.should_exit = true;
     }
 
-    fn save_todo_list(&self) {
-        // Generate the data from the list of tasks
-        let mut data = String::new();
-        let mut sections = HashMap::<String, Vec<Task>>::new();
+    // Take the lock on the file before changing the list. Returns false when
+    // another instance is editing it, in which case the list stays read-only.
+    fn can_edit(&mut self) -> bool {
+        match self.tab_mut().lock() {
+            Ok(true) => true,
+            Ok(false) => {
+                self.status_message = Some(format!(
+                    "{} is being edited by another instance, the list is read-only",
+                    self.tab().file_path.display()
+                ));
+                false
+            }
+            Err(e) => {
+                // The lock is only advisory, don't prevent editing because of it
+                self.status_message = Some(format!("Unable to lock the file: {}", e));
+                true
+            }
+        }
+    }
 
-        for section in &self.sections_order {
-            if !sections.contains_key(section) {
-                sections.insert(section.to_string(), vec![]);
+    // Keep the locks only while there is something to save, and notice when
+    // another instance releases one
+    fn sync_lock(&mut self) {
+        let editing = !matches!(
+            self.current_screen,
+            CurrentScreen::Main
+                | CurrentScreen::Subtask
+                | CurrentScreen::Select
+                | CurrentScreen::Dashboard
+        );
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            if tab.sync_lock(editing && i == self.current_tab) {
+                self.status_message = Some(format!(
+                    "{} is no longer locked, editing is enabled",
+                    tab.file_path.display()
+                ));
             }
         }
+    }
 
-        for task in &self.todo_list.items {
-            let section = match task.status {
-                Status::Todo => "## Todo",
-                Status::Doing => "## Doing",
-                Status::Done => "## Done",
-            };
-            sections.get_mut(section).unwrap().push(task.clone());
+    // Look for changes made to the files by other programs. Without local edits
+    // a list is simply reloaded, otherwise the user is asked what to do.
+    pub fn check_external_changes(&mut self) {
+        for i in 0..self.tabs.len() {
+            if self.current_screen == CurrentScreen::Conflict {
+                return;
+            }
+            let change = self.tabs[i].check_external_changes();
+            self.handle_file_change(i, change);
         }
+    }
 
-        for section in &self.sections_order {
-            data.push_str(&format!("{}\n", section));
-            for task in sections.get(section).unwrap() {
-                data.push_str(&format!("- {}\n", task.name));
-                if !task.description.is_empty() {
-                    data.push_str(&format!("    > {}\n", task.description));
+    // Read the files changed by other programs again in the background, so
+    // that large lists don't hold up the input
+    fn reload_changed_files(&mut self) {
+        if self.current_screen == CurrentScreen::Conflict {
+            return;
+        }
+        let changed: Vec<_> = self
+            .tabs
+            .iter_mut()
+            .filter_map(|tab| {
+                let load = tab.poll_file()?;
+                Some((tab.file_path.clone(), load, tab.saves))
+            })
+            .collect();
+        for (file_path, load, saves) in changed {
+            self.spawn(move || {
+                let seen = FileWatcher::new(&file_path);
+                let mut storage = storage_for(&file_path);
+                let loaded = storage
+                    .load()
+                    .map(|(tasks, diagnostics)| (storage, tasks, diagnostics));
+                Message::Loaded {
+                    file_path,
+                    load,
+                    saves,
+                    seen,
+                    loaded,
                 }
-                if !task.subtasks.is_empty() {
-                    for subtask in &task.subtasks {
-                        if subtask.status {
-                            data.push_str(&format!("    * [x] {}\n", subtask.name));
-                        } else {
-                            data.push_str(&format!("    * [ ] {}\n", subtask.name));
-                        }
-                    }
+            });
+        }
+    }
+
+    // Tell what happened to the list of tab `i` after its file changed on disk
+    fn handle_file_change(&mut self, i: usize, change: FileChange) {
+        match change {
+            FileChange::Unchanged => {}
+            FileChange::Reloaded => {
+                let tab = &self.tabs[i];
+                self.status_message = Some(match tab.diagnostics.len() {
+                    0 => format!("Reloaded {} (changed on disk)", tab.file_path.display()),
+                    n => format!(
+                        "Reloaded {} (changed on disk) with {} warnings, press w to show them",
+                        tab.file_path.display(),
+                        n
+                    ),
+                });
+                if i == self.current_tab {
+                    self.create_subtask_list();
+                    self.clear_marks();
                 }
             }
-            data.push_str("\n");
+            FileChange::Conflict => {
+                // Show the conflicting list to ask what to do with it
+                self.current_tab = i;
+                self.create_subtask_list();
+                self.marked.clear();
+                self.current_screen = CurrentScreen::Conflict;
+                self.currently_editing = None;
+            }
         }
+    }
 
-        fs::write(self.file_path.clone(), data).expect("Unable to write file");
+    // The tasks were read again with new ids, forget the marked ones
+    fn clear_marks(&mut self) {
+        self.marked.clear();
+        if matches!(
+            self.current_screen,
+            CurrentScreen::Select | CurrentScreen::Tagging
+        ) {
+            self.current_screen = CurrentScreen::Main;
+            self.currently_editing = None;
+        }
+    }
+
+    // Report the problems found while reading the files, showing them right away
+    pub fn show_diagnostics(&mut self) {
+        if let Some(i) = self.tabs.iter().position(|tab| !tab.diagnostics.is_empty()) {
+            self.current_tab = i;
+            self.current_screen = CurrentScreen::Warnings;
+        }
+    }
+
+    fn start_showing_warnings(&mut self) {
+        if self.tab().diagnostics.is_empty() {
+            self.status_message = Some(format!(
+                "No problems found in {}",
+                self.tab().file_path.display()
+            ));
+        } else {
+            self.current_screen = CurrentScreen::Warnings;
+        }
+    }
+
+    fn cancel_showing_warnings(&mut self) {
+        self.current_screen = CurrentScreen::Main;
+    }
+
+    // Discard the local edits and use the list found on disk
+    fn reload_remote_list(&mut self) {
+        self.tab_mut().reload_remote_list();
+        self.create_subtask_list();
+        self.current_screen = CurrentScreen::Main;
+        self.status_message = Some(format!("Reloaded {}", self.tab().file_path.display()));
+    }
+
+    // Keep the local edits, the file on disk will be overwritten on save
+    fn keep_local_list(&mut self) {
+        self.tab_mut().keep_local_list();
+        self.current_screen = CurrentScreen::Main;
+        self.status_message = Some("Kept local changes, the file will be overwritten".to_string());
+    }
+
+    // Combine the local edits with the changes made on disk
+    fn merge_remote_list(&mut self) {
+        let conflicts = self.tab_mut().merge_remote_list();
+        self.create_subtask_list();
+        self.current_screen = CurrentScreen::Main;
+        self.status_message = Some(if conflicts.is_empty() {
+            "Merged changes from disk".to_string()
+        } else {
+            format!(
+                "Merged changes from disk, kept local version of: {}",
+                conflicts.join(", ")
+            )
+        });
+    }
+
+    // Move the done tasks of the current list to its archive file
+    fn archive_done_tasks(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        self.status_message = Some(match self.tab_mut().archive_done_tasks(None) {
+            Ok(0) => "No done tasks to archive".to_string(),
+            Ok(n) => format!(
+                "Archived {} tasks to {}",
+                n,
+                archive_path(&self.tab().file_path).display()
+            ),
+            Err(e) => format!("Unable to archive tasks: {}", e),
+        });
+        self.create_subtask_list();
+    }
+
+    // Archive the tasks done for more than `days` days in every list, as set
+    // in the configuration
+    pub fn archive_old_tasks(&mut self, days: u32) {
+        let mut archived = 0;
+        for tab in &mut self.tabs {
+            if !matches!(tab.lock(), Ok(true)) {
+                continue;
+            }
+            tab.date_done_tasks();
+            match tab.archive_done_tasks(Some(days)) {
+                Ok(n) => archived += n,
+                Err(e) => {
+                    self.status_message = Some(format!("Unable to archive tasks: {}", e));
+                    return;
+                }
+            }
+        }
+        if archived > 0 {
+            self.status_message = Some(format!(
+                "Archived {} tasks done for more than {} days",
+                archived, days
+            ));
+        }
+        self.create_subtask_list();
+    }
+
+    fn start_browsing_archive(&mut self) {
+        match load_archive(&self.tab().file_path) {
+            Ok(items) => {
+                let mut state = ListState::default();
+                if !items.is_empty() {
+                    state.select(Some(0));
+                }
+                self.archive_list = TodoList { state, items };
+                self.current_screen = CurrentScreen::Archive;
+            }
+            Err(e) => self.status_message = Some(format!("Unable to read the archive: {}", e)),
+        }
+    }
+
+    // Put the selected archived task back in the list
+    fn restore_archived_task(&mut self) {
+        let Some(i) = self.archive_list.state.selected() else {
+            return;
+        };
+        if !self.can_edit() {
+            return;
+        }
+        match self.tab_mut().restore_archived_task(i) {
+            Ok(Some(task)) => {
+                self.archive_list.items.remove(i);
+                if self.archive_list.items.is_empty() {
+                    self.archive_list.state.select(None);
+                } else if i >= self.archive_list.items.len() {
+                    self.archive_list.state.select_previous();
+                }
+                self.status_message = Some(format!("Restored \"{}\"", task.name));
+            }
+            Ok(None) => {}
+            Err(e) => self.status_message = Some(format!("Unable to restore the task: {}", e)),
+        }
+    }
+
+    fn cancel_browsing_archive(&mut self) {
+        self.archive_list = TodoList::default();
+        self.current_screen = CurrentScreen::Main;
+        self.create_subtask_list();
+    }
+
+    // Switch to the tab at `index`, if there is one
+    fn select_tab(&mut self, index: usize) {
+        if index < self.tabs.len() {
+            self.current_tab = index;
+            self.create_subtask_list();
+        }
+    }
+
+    fn select_next_tab(&mut self) {
+        self.select_tab((self.current_tab + 1) % self.tabs.len());
+    }
+
+    fn select_previous_tab(&mut self) {
+        self.select_tab((self.current_tab + self.tabs.len() - 1) % self.tabs.len());
+    }
+
+    fn start_transfer(&mut self, copy: bool) {
+        if self.tabs.len() < 2 {
+            self.status_message = Some("Open another list to move tasks to it".to_string());
+            return;
+        }
+        if self.tab().todo_list.state.selected().is_none() || (!copy && !self.can_edit()) {
+            return;
+        }
+        self.transfer = Some(Transfer {
+            target: (self.current_tab + 1) % self.tabs.len(),
+            copy,
+        });
+        self.current_screen = CurrentScreen::Transfer;
+    }
+
+    fn select_next_transfer_target(&mut self) {
+        let (current_tab, len) = (self.current_tab, self.tabs.len());
+        if let Some(transfer) = &mut self.transfer {
+            transfer.target = (transfer.target + 1) % len;
+            if transfer.target == current_tab {
+                transfer.target = (transfer.target + 1) % len;
+            }
+        }
+    }
+
+    fn select_previous_transfer_target(&mut self) {
+        let (current_tab, len) = (self.current_tab, self.tabs.len());
+        if let Some(transfer) = &mut self.transfer {
+            transfer.target = (transfer.target + len - 1) % len;
+            if transfer.target == current_tab {
+                transfer.target = (transfer.target + len - 1) % len;
+            }
+        }
+    }
+
+    // Move or copy the marked tasks, or the selected one, to the target tab
+    fn transfer_task(&mut self) {
+        let Some(transfer) = self.transfer.take() else {
+            return;
+        };
+        let targets = self.target_tasks();
+        self.current_screen = CurrentScreen::Main;
+        if targets.is_empty() {
+            return;
+        }
+
+        let target = &mut self.tabs[transfer.target];
+        // The lock is only advisory, don't prevent editing if it can't be taken
+        if let Ok(false) = target.lock() {
+            self.status_message = Some(format!(
+                "{} is being edited by another instance, the list is read-only",
+                target.file_path.display()
+            ));
+            return;
+        }
+
+        let tasks: Vec<Task> = if transfer.copy {
+            let items = &self.tab().todo_list.items;
+            targets.iter().map(|i| items[*i].duplicate()).collect()
+        } else {
+            // Remove from the end so the other indices stay valid
+            let mut tasks: Vec<Task> = targets
+                .iter()
+                .rev()
+                .map(|i| self.tab_mut().todo_list.items.remove(*i))
+                .collect();
+            tasks.reverse();
+            self.tab_mut().clamp_selection();
+            self.create_subtask_list();
+            tasks
+        };
+
+        let what = match &tasks[..] {
+            [task] => format!("\"{}\"", task.name),
+            tasks => format!("{} tasks", tasks.len()),
+        };
+        self.status_message = Some(format!(
+            "{} {} to {}",
+            if transfer.copy { "Copied" } else { "Moved" },
+            what,
+            self.tabs[transfer.target].file_path.display()
+        ));
+        self.tabs[transfer.target].todo_list.items.extend(tasks);
+        self.marked.clear();
+    }
+
+    fn cancel_transfer(&mut self) {
+        self.transfer = None;
+        self.current_screen = self.return_screen();
     }
 
     fn start_editing(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            self.name_input = self.todo_list.items[i].name.clone();
-            self.description_input = self.todo_list.items[i].description.clone();
+        if !self.can_edit() {
+            return;
+        }
+        if let Some(i) = self.tab().todo_list.state.selected() {
+            self.name_input = self.tab().todo_list.items[i].name.clone();
+            self.description_input = self.tab().todo_list.items[i].description.clone();
             self.current_screen = CurrentScreen::Editing;
             self.currently_editing = Some(CurrentlyEditing::Name);
         }
     }
 
+    fn start_editing_in_editor(&mut self) {
+        if self.tab().todo_list.state.selected().is_some() && self.can_edit() {
+            self.pending_editor = Some(EditorTarget::Task);
+        }
+    }
+
+    fn start_editing_list_in_editor(&mut self) {
+        if self.can_edit() {
+            self.pending_editor = Some(EditorTarget::List);
+        }
+    }
+
+    // Edit the selected task as markdown in $EDITOR, with its subtasks
+    fn edit_in_editor(&mut self) {
+        let Some(i) = self.tab().todo_list.state.selected() else {
+            return;
+        };
+        match edit_task_in_editor(&self.tab().todo_list.items[i], &editor_command()) {
+            Ok(task) => {
+                self.tab_mut().todo_list.items[i] = task;
+                self.create_subtask_list();
+                self.check_current_file();
+            }
+            Err(e) => self.status_message = Some(format!("{}", e)),
+        }
+    }
+
+    // Edit the tasks of the current view in $EDITOR, applying the changes
+    // made to them as one change set
+    fn edit_list_in_editor(&mut self) {
+        let items = &self.tab().todo_list.items;
+        let ids: Vec<u16> = self
+            .visible_tasks()
+            .into_iter()
+            .map(|i| items[i].id)
+            .collect();
+        match edit_list_in_editor(items, &ids, &editor_command()) {
+            Ok(change_set) if change_set.is_empty() => {
+                self.status_message = Some("The list wasn't changed".to_string());
+            }
+            Ok(change_set) => {
+                self.status_message = Some(format!(
+                    "Applied the changes: {}, press u to undo",
+                    change_set
+                ));
+                self.tab_mut().apply_change_set(change_set);
+                self.create_subtask_list();
+                self.check_current_file();
+            }
+            Err(e) => self.status_message = Some(format!("{}", e)),
+        }
+    }
+
+    // Look at the file of the current tab after the list was changed in
+    // $EDITOR. The file may have changed meanwhile, the edits being applied
+    // already it is a conflict, never a reload over them.
+    fn check_current_file(&mut self) {
+        let change = self.tab_mut().check_external_changes();
+        self.handle_file_change(self.current_tab, change);
+    }
+
+    // Undo the last change set of the current tab
+    fn undo(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
+        self.status_message = Some(match self.tab_mut().undo() {
+            Ok(change_set) => format!("Undid the changes: {}", change_set),
+            Err(e) => e,
+        });
+        self.create_subtask_list();
+    }
+
     fn save_edited_task(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            let task = &mut self.todo_list.items[i];
+        if let Some(i) = self.tabs[self.current_tab].todo_list.state.selected() {
+            let task = &mut self.tabs[self.current_tab].todo_list.items[i];
             task.name = self.name_input.clone();
             task.description = self.description_input.clone();
         }
@@ -401,6 +1281,9 @@ impl App {
     }
 
     fn start_adding(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
         self.name_input = String::new();
         self.description_input = String::new();
         self.current_screen = CurrentScreen::AddTask;
@@ -408,12 +1291,12 @@ impl App {
     }
 
     fn add_new_task(&mut self) {
-        self.todo_list.items.push(Task::new(
-            self.name_input.clone(),
-            self.description_input.clone(),
-            Status::Todo,
-            None,
-        ));
+        // The name can hold the due date, tags, priority and recurrence
+        let mut task = parse_quick_add(&self.name_input, now().date())
+            .into_task(self.description_input.clone());
+        task.created_at = Some(now());
+        task.uid = Some(new_uid());
+        self.tab_mut().todo_list.items.push(task);
         self.current_screen = CurrentScreen::Main;
         self.currently_editing = None;
     }
@@ -424,18 +1307,29 @@ impl App {
     }
 
     fn start_deleting(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
         self.current_screen = CurrentScreen::Deleting;
     }
 
+    // Delete the marked tasks, or the selected one
     fn delete_task(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            self.todo_list.items.remove(i);
+        let targets = self.target_tasks();
+        for i in targets.iter().rev() {
+            self.tab_mut().todo_list.items.remove(*i);
+        }
+        if targets.len() > 1 {
+            self.status_message = Some(format!("Deleted {} tasks", targets.len()));
+            self.tab_mut().clamp_selection();
+            self.create_subtask_list();
         }
+        self.marked.clear();
         self.current_screen = CurrentScreen::Main;
     }
 
     fn cancel_deleting(&mut self) {
-        self.current_screen = CurrentScreen::Main;
+        self.current_screen = self.return_screen();
     }
 
     fn start_subtask(&mut self) {
@@ -447,8 +1341,12 @@ impl App {
     }
 
     fn delete_subtask(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
         if let Some(i) = self.subtask_list.state.selected() {
-            self.todo_list.items[self.todo_list.state.selected().unwrap()]
+            let todo_list = &mut self.tabs[self.current_tab].todo_list;
+            todo_list.items[todo_list.state.selected().unwrap()]
                 .subtasks
                 .remove(i);
 
@@ -457,8 +1355,12 @@ impl App {
     }
 
     fn update_subtask(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
         if let Some(i) = self.subtask_list.state.selected() {
-            self.todo_list.items[self.todo_list.state.selected().unwrap()]
+            let todo_list = &mut self.tabs[self.current_tab].todo_list;
+            todo_list.items[todo_list.state.selected().unwrap()]
                 .subtasks
                 .get_mut(i)
                 .unwrap()
@@ -469,6 +1371,9 @@ impl App {
     }
 
     fn start_adding_subtask(&mut self) {
+        if !self.can_edit() {
+            return;
+        }
         self.name_input = String::new();
         self.description_input = String::new();
         self.current_screen = CurrentScreen::AddSubtask;
@@ -481,8 +1386,9 @@ impl App {
     }
 
     fn add_subtask(&mut self) {
-        if let Some(i) = self.todo_list.state.selected() {
-            self.todo_list
+        if let Some(i) = self.tabs[self.current_tab].todo_list.state.selected() {
+            self.tabs[self.current_tab]
+                .todo_list
                 .items
                 .get_mut(i)
                 .unwrap()
@@ -501,7 +1407,7 @@ impl App {
 impl Widget for &mut App {
     fn render(self, area: Rect, buf: &mut Buffer) {
         let [header_area, main_area, footer_area] = Layout::vertical([
-            Constraint::Length(2),
+            Constraint::Length(3),
             Constraint::Fill(1),
             Constraint::Length(1),
         ])
@@ -513,9 +1419,18 @@ impl Widget for &mut App {
         let [item_area, subtask_area] =
             Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(info_area);
 
-        App::render_header(header_area, buf);
-        App::render_footer(footer_area, buf);
-        self.render_list(list_area, buf);
+        self.render_header(header_area, buf);
+        self.render_footer(footer_area, buf);
+        if self.current_screen == CurrentScreen::Dashboard {
+            let stats = Stats::new(&self.tab().todo_list.items, now());
+            Dashboard { stats: &stats }.render(main_area, buf);
+            return;
+        }
+        if self.current_screen == CurrentScreen::Archive {
+            self.render_archive(list_area, buf);
+        } else {
+            self.render_list(list_area, buf);
+        }
         self.render_selected_item(item_area, buf);
         self.render_subtasks(subtask_area, buf);
     }
@@ -523,71 +1438,129 @@ impl Widget for &mut App {
 
 // Rendering logic for the app
 impl App {
-    fn render_header(area: Rect, buf: &mut Buffer) {
-        Paragraph::new("Horme").bold().centered().render(area, buf);
+    fn render_header(&self, area: Rect, buf: &mut Buffer) {
+        let [title_area, tabs_area, views_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(area);
+
+        let mut title = Line::from("Horme".bold());
+        if let Some(owner) = &self.tab().locked_by {
+            let owner = match owner.pid {
+                Some(pid) => format!("another instance (pid {})", pid),
+                None => "another instance".to_string(),
+            };
+            title.push_span(
+                format!("  [read-only: locked by {}]", owner)
+                    .fg(TEXT_FG_DELETING)
+                    .bold(),
+            );
+        }
+        Paragraph::new(title).centered().render(title_area, buf);
+        // Kept up to date by the ticks of the main loop
+        Paragraph::new(now().format("%H:%M ").to_string())
+            .right_aligned()
+            .fg(DONE_FG_COLOR)
+            .render(title_area, buf);
+
+        // One tab for each open list
+        Tabs::new(self.tabs.iter().map(|tab| tab.title()))
+            .select(self.current_tab)
+            .highlight_style(TODO_HEADER_STYLE)
+            .divider("|")
+            .render(tabs_area, buf);
+
+        // One tab for each view of the lists
+        Tabs::new(self.views.iter().map(|view| view.name().to_string()))
+            .select(self.current_view)
+            .highlight_style(Style::new().fg(TEXT_FG_EDITING).bold())
+            .divider("|")
+            .render(views_area, buf);
     }
 
-    fn render_footer(area: Rect, buf: &mut Buffer) {
-        Paragraph::new(
-            "Press j/k to select, a to add new task, Enter to edit, d to delete, q to quit",
-        )
-        .centered()
-        .render(area, buf);
+    fn render_footer(&self, area: Rect, buf: &mut Buffer) {
+        let text = match (&self.current_screen, &self.status_message) {
+            (CurrentScreen::Conflict, _) => {
+                "Press r to reload from disk, k to keep your changes, m to merge"
+            }
+            (CurrentScreen::Warnings, _) => "Press Esc to close the warnings",
+            (CurrentScreen::Archive, _) => {
+                "Press j/k to select, r to restore the task, Esc to go back"
+            }
+            (CurrentScreen::Transfer, _) => {
+                "Press h/l to choose the list, Enter to confirm, Esc to cancel"
+            }
+            (CurrentScreen::Filter, _) => "Type a query, Enter to apply it, Esc to clear it",
+            (CurrentScreen::Dashboard, _) => {
+                "Press Tab to switch list, D or Esc to go back to the list, q to quit"
+            }
+            (_, Some(message)) => message,
+            _ => "Press j/k to select, a to add new task, e to edit in $EDITOR, d to delete, Space to select several, v to change view, / to filter, q to quit",
+        };
+        Paragraph::new(text).centered().render(area, buf);
     }
 
-    // Iterate through the list of tasks and render them
+    // Iterate through the tasks of the current view and render them
     fn render_list(&mut self, area: Rect, buf: &mut Buffer) {
+        let mut title = self.views[self.current_view].name().to_string();
+        if self.filter.is_some() {
+            title.push_str(&format!(" / {}", self.filter_input.trim()));
+        }
         let block = Block::new()
-            .title(Line::raw("Tasks").centered())
+            .title(Line::raw(title).centered())
             .borders(Borders::TOP)
             .border_set(symbols::border::EMPTY)
             .border_style(TODO_HEADER_STYLE)
             .bg(NORMAL_ROW_BG);
 
-        let items: Vec<ListItem> = self
-            .todo_list
-            .items
+        let visible = self.visible_tasks();
+        self.sync_view_selection(&visible);
+        let position = self.visible_position(&visible);
+
+        let todo_list = &self.tabs[self.current_tab].todo_list;
+        let items: Vec<ListItem> = visible
             .iter()
             .enumerate()
-            .filter_map(|(i, todo_item)| {
-                if i < self.todo_list.items.len() {
-                    let bg_color = alternate_colors(i);
-                    let fg_color = match todo_item.status {
-                        Status::Todo => TODO_FG_COLOR,
-                        Status::Doing => DOING_FG_COLOR,
-                        Status::Done => DONE_FG_COLOR,
+            .map(|(row, i)| {
+                let todo_item = &todo_list.items[*i];
+                let fg_color = match todo_item.status {
+                    Status::Todo => TODO_FG_COLOR,
+                    Status::Doing => DOING_FG_COLOR,
+                    Status::Done => DONE_FG_COLOR,
+                };
+                let mut line = Line::raw(todo_item.name.clone());
+                if !self.marked.is_empty() {
+                    let mark = if self.marked.contains(&todo_item.id) {
+                        "● "
+                    } else {
+                        "  "
                     };
-                    Some(
-                        ListItem::from(todo_item.name.clone())
-                            .bg(bg_color)
-                            .fg(fg_color),
-                    )
-                } else {
-                    None
+                    line.spans.insert(0, mark.fg(TEXT_FG_EDITING));
                 }
+                if let Some(date) = todo_item.due_date {
+                    line.push_span(format!("  {}", date.format("%a %d %b")).fg(DONE_FG_COLOR));
+                }
+                ListItem::from(line).bg(alternate_colors(row)).fg(fg_color)
             })
             .collect();
 
         match self.current_screen {
-            CurrentScreen::Main => {
-                let highlighted_style = if let Some(i) = self.todo_list.state.selected() {
-                    if i < self.todo_list.items.len() {
-                        match self.todo_list.items[i].status {
-                            Status::Todo => Style::default()
-                                .fg(TODO_FG_COLOR)
-                                .add_modifier(Modifier::BOLD),
-                            Status::Doing => Style::default()
-                                .fg(DOING_FG_COLOR)
-                                .add_modifier(Modifier::BOLD),
-                            Status::Done => Style::default()
-                                .fg(DONE_FG_COLOR)
-                                .add_modifier(Modifier::BOLD),
-                        }
-                    } else {
-                        Style::default()
-                    }
-                } else {
-                    Style::default()
+            CurrentScreen::Main | CurrentScreen::Select => {
+                let highlighted_style = match position.map(|position| visible[position]) {
+                    Some(i) => match todo_list.items[i].status {
+                        Status::Todo => Style::default()
+                            .fg(TODO_FG_COLOR)
+                            .add_modifier(Modifier::BOLD),
+                        Status::Doing => Style::default()
+                            .fg(DOING_FG_COLOR)
+                            .add_modifier(Modifier::BOLD),
+                        Status::Done => Style::default()
+                            .fg(DONE_FG_COLOR)
+                            .add_modifier(Modifier::BOLD),
+                    },
+                    None => Style::default(),
                 };
 
                 // Create a list from all list items and highlight the currently selected one
@@ -599,7 +1572,8 @@ impl App {
 
                 // Disambiguate this trait method as both `Widget` and `StatefulWidget`
                 // share the `render` method
-                StatefulWidget::render(list, area, buf, &mut self.todo_list.state);
+                self.view_state.select(position);
+                StatefulWidget::render(list, area, buf, &mut self.view_state);
             }
             _ => {
                 // If on other screens, just render the list of tasks,
@@ -610,6 +1584,40 @@ impl App {
         }
     }
 
+    // Read-only list of the archived tasks, with the month they were done in
+    fn render_archive(&mut self, area: Rect, buf: &mut Buffer) {
+        let block = Block::new()
+            .title(Line::raw("Archive").centered())
+            .borders(Borders::TOP)
+            .border_set(symbols::border::EMPTY)
+            .border_style(TODO_HEADER_STYLE)
+            .bg(NORMAL_ROW_BG);
+
+        let items: Vec<ListItem> = self
+            .archive_list
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, task)| {
+                ListItem::from(format!("{}  {}", archive_section(task), task.name))
+                    .bg(alternate_colors(i))
+                    .fg(DONE_FG_COLOR)
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(
+                Style::default()
+                    .fg(COMPLETED_TEXT_FG_COLOR)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol(">> ")
+            .highlight_spacing(HighlightSpacing::Always);
+
+        StatefulWidget::render(list, area, buf, &mut self.archive_list.state);
+    }
+
     fn render_selected_item(&self, area: Rect, buf: &mut Buffer) {
         // Show the list item's info under the list in this paragraph
         let block = Block::new()
@@ -638,25 +1646,39 @@ impl App {
                     .render(area, buf);
             }
             CurrentScreen::AddTask => {
-                let input = match self.currently_editing {
+                let mut lines = vec![Line::raw(match self.currently_editing {
                     Some(CurrentlyEditing::Name) => format!("Name: {} ", self.name_input),
                     Some(CurrentlyEditing::Description) => {
                         format!("Description: {}", self.description_input)
                     }
                     None => "".to_string(),
-                };
+                })];
+                // Preview what was recognised in the name as it is typed
+                let quick_add = parse_quick_add(&self.name_input, now().date());
+                let fields = quick_add.describe();
+                if !fields.is_empty() {
+                    lines.push(Line::raw(""));
+                    lines.push(Line::raw(format!("Task: {}", quick_add.name)).fg(TEXT_FG_COLOR));
+                    lines.extend(fields.into_iter().map(|(label, value)| {
+                        Line::raw(format!("{}: {}", label, value)).fg(TEXT_FG_COLOR)
+                    }));
+                }
                 // Render the item info
-                Paragraph::new(input)
+                Paragraph::new(lines)
                     .block(block)
                     .fg(TEXT_FG_ADDING)
                     .wrap(Wrap { trim: false })
                     .render(area, buf);
             }
             CurrentScreen::Deleting => {
+                let what = match self.target_tasks()[..] {
+                    [i] => format!("\"{}\"", self.tab().todo_list.items[i].name),
+                    ref targets => format!("the {} marked tasks", targets.len()),
+                };
                 let input = format!(
-                    "Are you sure you want to delete \"{}\"?\n
+                    "Are you sure you want to delete {}?\n
                         Press d to confirm, Esc to cancel",
-                    self.todo_list.items[self.todo_list.state.selected().unwrap()].name
+                    what
                 );
                 // Render the item info
                 Paragraph::new(input)
@@ -665,6 +1687,156 @@ impl App {
                     .wrap(Wrap { trim: false })
                     .render(area, buf);
             }
+            CurrentScreen::Conflict => {
+                let input = format!(
+                    "{} was changed on disk while you have unsaved changes.\n\n\
+                     r: reload it and discard your changes\n\
+                     k: keep your changes and overwrite it on save\n\
+                     m: merge both versions",
+                    self.tab().file_path.display()
+                );
+                // Render the item info
+                Paragraph::new(input)
+                    .block(block)
+                    .fg(TEXT_FG_DELETING)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+            CurrentScreen::Transfer => {
+                let target = self.transfer.as_ref().map_or(0, |transfer| transfer.target);
+                let copy = self.transfer.as_ref().is_some_and(|transfer| transfer.copy);
+                let what = match self.target_tasks()[..] {
+                    [i] => format!("\"{}\"", self.tab().todo_list.items[i].name),
+                    ref targets => format!("{} tasks", targets.len()),
+                };
+                let mut lines = vec![Line::raw(format!(
+                    "{} {} to:",
+                    if copy { "Copy" } else { "Move" },
+                    what
+                ))];
+                lines.extend(
+                    self.tabs
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| *i != self.current_tab)
+                        .map(|(i, tab)| {
+                            if i == target {
+                                Line::raw(format!(">> {}", tab.file_path.display())).bold()
+                            } else {
+                                Line::raw(format!("   {}", tab.file_path.display()))
+                            }
+                        }),
+                );
+                // Render the lists the task can go to
+                Paragraph::new(lines)
+                    .block(block)
+                    .fg(TEXT_FG_ADDING)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+            CurrentScreen::Archive => {
+                let info = match self
+                    .archive_list
+                    .state
+                    .selected()
+                    .and_then(|i| self.archive_list.items.get(i))
+                {
+                    Some(task) => format!(
+                        "✓ DONE: {}\n{}\n\nCompleted: {}",
+                        task.name,
+                        task.description,
+                        task.completed_at
+                            .map(|date| date.format(DATE_TIME_FORMAT).to_string())
+                            .unwrap_or_else(|| "unknown".to_string())
+                    ),
+                    None => "No archived tasks".to_string(),
+                };
+                // Render the archived task info
+                Paragraph::new(info)
+                    .block(block)
+                    .fg(TEXT_FG_COLOR)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+            CurrentScreen::Filter => {
+                let mut lines = vec![Line::raw(format!("Filter: {}", self.filter_input))];
+                if let Some(error) = &self.filter_error {
+                    // Point at the column of the error under the query
+                    let indent = "Filter: ".len() + error.column.saturating_sub(1);
+                    lines.push(Line::raw(format!("{}^", " ".repeat(indent))).fg(TEXT_FG_DELETING));
+                    lines.push(Line::raw(error.message.clone()).fg(TEXT_FG_DELETING));
+                }
+                lines.push(Line::raw(""));
+                lines.push(
+                    Line::raw("e.g. status:todo tag:infra due<7d -tag:someday \"text\"")
+                        .fg(TEXT_FG_COLOR),
+                );
+                // Render the query being typed
+                Paragraph::new(lines)
+                    .block(block)
+                    .fg(TEXT_FG_ADDING)
+                    .render(area, buf);
+            }
+            CurrentScreen::Warnings => {
+                let title = Line::raw(format!(
+                    "{} problems found in {}",
+                    self.tab().diagnostics.len(),
+                    self.tab().file_path.display()
+                ))
+                .bold();
+                let lines: Vec<Line> = std::iter::once(title)
+                    .chain(self.tab().diagnostics.iter().map(|diagnostic| {
+                        let color = match diagnostic.severity {
+                            Severity::Warning => TEXT_FG_EDITING,
+                            Severity::Error => TEXT_FG_DELETING,
+                        };
+                        Line::raw(diagnostic.to_string()).fg(color)
+                    }))
+                    .collect();
+                // Render the problems found in the file
+                Paragraph::new(lines)
+                    .block(block)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+            CurrentScreen::Select => {
+                let items = &self.tab().todo_list.items;
+                let mut lines = vec![
+                    Line::raw(format!("{} tasks marked", self.marked.len())).bold(),
+                    Line::raw("Space: mark, *: mark all, Esc: leave"),
+                    Line::raw("t/p/c: todo/doing/done, Enter: next status"),
+                    Line::raw("d: delete, m: move, y: copy, #: tags"),
+                    Line::raw(""),
+                ];
+                lines.extend(
+                    self.target_tasks()
+                        .into_iter()
+                        .map(|i| Line::raw(format!("● {}", items[i].name)).fg(TEXT_FG_EDITING)),
+                );
+                // Render the marked tasks and what can be done with them
+                Paragraph::new(lines)
+                    .block(block)
+                    .fg(TEXT_FG_COLOR)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
+            CurrentScreen::Tagging => {
+                let lines = vec![
+                    Line::raw(format!("Tags: {}", self.name_input)),
+                    Line::raw(""),
+                    Line::raw(format!(
+                        "e.g. +infra -old, for the {} marked tasks",
+                        self.target_tasks().len()
+                    ))
+                    .fg(TEXT_FG_COLOR),
+                ];
+                // Render the tags being typed
+                Paragraph::new(lines)
+                    .block(block)
+                    .fg(TEXT_FG_EDITING)
+                    .wrap(Wrap { trim: false })
+                    .render(area, buf);
+            }
             CurrentScreen::Subtask => {
                 let info = format!(
                     "Modify subtask: {}",
@@ -686,25 +1858,26 @@ impl App {
                     .render(area, buf);
             }
             _ => {
-                let status_info = if let Some(i) = self.todo_list.state.selected() {
-                    match self.todo_list.items[i].status {
+                let todo_list = &self.tab().todo_list;
+                let status_info = if let Some(i) = todo_list.state.selected() {
+                    match todo_list.items[i].status {
                         Status::Todo => format!(
                             "◇ TODO: {}\n{}",
-                            self.todo_list.items[i].name, self.todo_list.items[i].description
+                            todo_list.items[i].name, todo_list.items[i].description
                         ),
                         Status::Doing => format!(
                             "◎ IN PROGRESS: {}\n{}",
-                            self.todo_list.items[i].name, self.todo_list.items[i].description
+                            todo_list.items[i].name, todo_list.items[i].description
                         ),
                         Status::Done => format!(
                             "✓ DONE: {}\n{}",
-                            self.todo_list.items[i].name, self.todo_list.items[i].description
+                            todo_list.items[i].name, todo_list.items[i].description
                         ),
                     }
                 } else {
                     "No task selected".to_string()
                 };
-                let info = format!("{}", status_info);
+                let info = status_info.to_string();
                 // Render the item info
                 Paragraph::new(info)
                     .block(block)
@@ -724,12 +1897,8 @@ impl App {
 
         match self.current_screen {
             CurrentScreen::Subtask => {
-                let subtasks: Vec<ListItem> = self
-                    .subtask_list
-                    .items
-                    .iter()
-                    .map(|subtask| ListItem::from(subtask))
-                    .collect();
+                let subtasks: Vec<ListItem> =
+                    self.subtask_list.items.iter().map(ListItem::from).collect();
 
                 let list = List::new(subtasks)
                     .block(block)
@@ -749,9 +1918,17 @@ impl App {
                     .render(area, buf);
             }
             _ => {
-                let subtasks: String = self
-                    .subtask_list
-                    .items
+                // While browsing the archive, show the subtasks of the archived task
+                let subtasks = match self.current_screen {
+                    CurrentScreen::Archive => self
+                        .archive_list
+                        .state
+                        .selected()
+                        .and_then(|i| self.archive_list.items.get(i))
+                        .map_or(&[][..], |task| &task.subtasks[..]),
+                    _ => &self.subtask_list.items[..],
+                };
+                let subtasks: String = subtasks
                     .iter()
                     .map(|subtask| {
                         if subtask.status {
@@ -763,7 +1940,7 @@ impl App {
                     .collect::<Vec<_>>()
                     .join("\n");
 
-                let info = format!("{}", subtasks);
+                let info = subtasks.to_string();
                 // Render the item info
                 Paragraph::new(info)
                     .block(block)
@@ -776,7 +1953,7 @@ impl App {
 }
 
 const fn alternate_colors(i: usize) -> Color {
-    if i % 2 == 0 {
+    if i.is_multiple_of(2) {
         NORMAL_ROW_BG
     } else {
         ALT_ROW_BG_COLOR
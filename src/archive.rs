@@ -0,0 +1,93 @@
+use chrono::{Duration, NaiveDateTime};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::markdown::{parse_todo_list, render_task};
+use crate::task::{Status, Task};
+
+// Heading of the archived tasks without a completion date
+const UNDATED_SECTION: &str = "Undated";
+
+// Archive kept next to a task list, `todo.md` is archived to `todo.archive.md`
+pub fn archive_path(file_path: &Path) -> PathBuf {
+    let stem = file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_path.with_file_name(format!("{}.archive.md", stem))
+}
+
+// Read the tasks archived from a task list, newest first
+pub fn load_archive(file_path: &Path) -> io::Result<Vec<Task>> {
+    let contents = match fs::read_to_string(archive_path(file_path)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    // Archived tasks are listed by month rather than by status
+    let mut tasks: Vec<Task> = parse_todo_list(&contents)
+        .into_iter()
+        .map(|task| Task {
+            status: Status::Done,
+            ..task
+        })
+        .collect();
+    sort_archive(&mut tasks);
+    Ok(tasks)
+}
+
+pub fn save_archive(file_path: &Path, tasks: &[Task]) -> io::Result<()> {
+    fs::write(archive_path(file_path), render_archive(tasks))
+}
+
+// Add tasks to the archive of a task list
+pub fn archive_tasks(file_path: &Path, tasks: Vec<Task>) -> io::Result<()> {
+    let mut archive = load_archive(file_path)?;
+    archive.extend(tasks);
+    sort_archive(&mut archive);
+    save_archive(file_path, &archive)
+}
+
+// Generate the archive data, grouping the tasks by completion month
+pub fn render_archive(tasks: &[Task]) -> String {
+    let mut data = String::from("# Archive\n");
+    let mut current_section: Option<String> = None;
+
+    for task in tasks {
+        let section = archive_section(task);
+        if current_section.as_ref() != Some(&section) {
+            data.push_str(&format!("\n## {}\n", section));
+            current_section = Some(section);
+        }
+        render_task(task, &mut data);
+    }
+
+    data
+}
+
+// Heading under which a task is archived
+pub fn archive_section(task: &Task) -> String {
+    match task.completed_at {
+        Some(date) => date.format("%Y-%m").to_string(),
+        None => UNDATED_SECTION.to_string(),
+    }
+}
+
+// Check if a task can be archived, only if it was completed at least
+// `older_than_days` days ago when given
+pub fn is_archivable(task: &Task, older_than_days: Option<u32>, now: NaiveDateTime) -> bool {
+    task.status == Status::Done
+        && match older_than_days {
+            Some(days) => task
+                .completed_at
+                .is_some_and(|date| now - date >= Duration::days(days.into())),
+            None => true,
+        }
+}
+
+// Newest tasks first, undated ones last as `None` sorts before any date
+fn sort_archive(tasks: &mut [Task]) {
+    tasks.sort_by_key(|task| std::cmp::Reverse(task.completed_at));
+}
@@ -0,0 +1,175 @@
+use chrono::NaiveDate;
+
+use crate::query::Query;
+use crate::task::{Status, Task};
+
+// Tags added with `+tag` and removed with `-tag`, for several tasks at once
+#[derive(Debug, Default, PartialEq)]
+pub struct TagEdit {
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+impl TagEdit {
+    // Read the `+tag` and `-tag` words at the start of `words`, returning the
+    // number of words read. `-tag:infra` is a query term, not a tag.
+    pub fn parse(words: &[&str]) -> (Self, usize) {
+        let mut edit = TagEdit::default();
+        let mut read = 0;
+        for word in words {
+            let (add, tag) = match word.split_at_checked(1) {
+                Some(("+", tag)) => (true, tag),
+                Some(("-", tag)) => (false, tag),
+                _ => break,
+            };
+            let tag = tag.trim_start_matches('#');
+            if tag.is_empty() || tag.contains(':') || tag.starts_with('-') {
+                break;
+            }
+            if add {
+                edit.add.push(tag.to_string());
+            } else {
+                edit.remove.push(tag.to_string());
+            }
+            read += 1;
+        }
+        (edit, read)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+
+    // Change the tags of a task, returning whether they changed
+    pub fn apply(&self, task: &mut Task) -> bool {
+        let before = task.tags.clone();
+        task.tags.retain(|tag| !self.remove.contains(tag));
+        for tag in &self.add {
+            if !task.tags.contains(tag) {
+                task.tags.push(tag.clone());
+            }
+        }
+        task.tags != before
+    }
+}
+
+// How the tasks were picked, a query can match more tasks than expected
+#[derive(Debug, PartialEq)]
+pub enum Selection {
+    Ids(Vec<u16>),
+    Query(Vec<u16>),
+}
+
+impl Selection {
+    pub fn ids(&self) -> &[u16] {
+        match self {
+            Selection::Ids(ids) | Selection::Query(ids) => ids,
+        }
+    }
+}
+
+// The tasks picked by `args`: ids and ranges of ids like `3 5 8-12`, or a
+// query with at least one field like `status:todo tag:infra`. Words that
+// look like ids are never read as text, so a typo can't pick other tasks.
+pub fn select_tasks(
+    args: &[&str],
+    todo_list: &[Task],
+    today: NaiveDate,
+) -> Result<Selection, String> {
+    if args.is_empty() {
+        return Err("Please provide task ids, ranges like 8-12 or a query".to_string());
+    }
+
+    let looks_like_id = |arg: &&str| {
+        arg.chars().all(|c| c.is_ascii_digit() || c == '-')
+            && arg.contains(|c: char| c.is_ascii_digit())
+    };
+    if !args.iter().all(looks_like_id) {
+        let input = args.join(" ");
+        let query = Query::parse(&input).map_err(|e| e.pointing_at(&input))?;
+        if !query.has_field_terms() {
+            let arg = args
+                .iter()
+                .find(|arg| !looks_like_id(arg))
+                .unwrap_or(&args[0]);
+            return Err(format!(
+                "Invalid task id or range \"{}\", expected ids like 3, ranges like 8-12 or a query like status:done",
+                arg
+            ));
+        }
+        if let Some(arg) = args.iter().find(|arg| looks_like_id(arg)) {
+            return Err(format!(
+                "\"{}\" is not a query term, use id:<id> to pick ids in a query",
+                arg
+            ));
+        }
+        return Ok(Selection::Query(
+            todo_list
+                .iter()
+                .filter(|task| query.matches(task, today))
+                .map(|task| task.id)
+                .collect(),
+        ));
+    }
+
+    let mut ids = vec![];
+    for arg in args {
+        let (first, last) = parse_range(arg)?;
+        // A single id has to exist, a range takes the ids found in it
+        if first == last && !todo_list.iter().any(|task| task.id == first) {
+            return Err(format!(
+                "No task with id {} (run <list> to see task ids)",
+                first
+            ));
+        }
+        for task in todo_list {
+            if (first..=last).contains(&task.id) && !ids.contains(&task.id) {
+                ids.push(task.id);
+            }
+        }
+    }
+    Ok(Selection::Ids(ids))
+}
+
+// `3` or `8-12`
+fn parse_range(arg: &str) -> Result<(u16, u16), String> {
+    let invalid = || format!("Invalid task id or range \"{}\"", arg);
+    match arg.split_once('-') {
+        Some((first, last)) => {
+            let first: u16 = first.parse().map_err(|_| invalid())?;
+            let last: u16 = last.parse().map_err(|_| invalid())?;
+            if first > last {
+                return Err(format!(
+                    "Invalid range \"{}\", the first id must come first like {}-{}",
+                    arg, last, first
+                ));
+            }
+            Ok((first, last))
+        }
+        None => arg.parse().map(|id| (id, id)).map_err(|_| invalid()),
+    }
+}
+
+// Move the tasks with the given ids to `status`, or to their next status when
+// it isn't given. Recurring tasks that get done come back, the occurrences
+// added to the list are returned.
+pub fn change_status(
+    todo_list: &mut Vec<Task>,
+    ids: &[u16],
+    status: Option<Status>,
+    today: NaiveDate,
+) -> Vec<Task> {
+    let mut next = vec![];
+    for task in todo_list.iter_mut().filter(|task| ids.contains(&task.id)) {
+        let was_done = task.status == Status::Done;
+        match status {
+            Some(status) => task.set_status(status),
+            None => task.update_status(),
+        }
+        if task.status == Status::Done && !was_done {
+            next.extend(task.next_occurrence(today));
+        }
+    }
+    todo_list.extend(next.iter().cloned());
+    next
+}
@@ -0,0 +1,527 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+
+use crate::markdown::{Diagnostic, Severity};
+use crate::task::{Status, Subtask, Task, DATE_FORMAT, DATE_TIME_FORMAT};
+
+// Markdown files written by other tools, where tasks are checklist items:
+//
+//     ## Backlog
+//
+//     - [ ] Write the docs
+//         - [x] Outline
+//     - [x] Fix the build
+//
+// A checked item is done whatever the section it is in, and the sections are
+// kept as they are even when they aren't named after a status. The other
+// fields of a task are written at the end of its item, as the Kanban plugin
+// and Dataview do:
+//
+//     - [ ] Release @{2026-11-01} @@{09:00} #ops [priority:: B] [repeat:: every month]
+//
+// along with `[status:: doing]` when the status doesn't follow from the
+// checkbox and the section.
+//
+// GFM task lists write the due date as `[due:: 2026-11-01 09:00]` and the tags
+// as `[tags:: ops]` instead, `#12` being an issue there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    // GitHub-flavoured task lists
+    Gfm,
+    // Boards of the Obsidian Kanban plugin, task lists with a front matter and
+    // a settings block
+    Kanban,
+}
+
+// What is kept of a checklist file besides its tasks, so that it can be
+// written back the way it was read
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub dialect: Dialect,
+    // Everything between the `---` lines, without them
+    pub front_matter: Option<String>,
+    // Lines before the first section
+    pub preamble: Vec<String>,
+    pub lanes: Vec<Lane>,
+    // The `%% kanban:settings` block, as it was read
+    pub settings: Option<String>,
+}
+
+// A section of the file, a column of a Kanban board
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lane {
+    // Heading markers, `##` for Kanban boards, empty for the items before the
+    // first heading
+    pub level: String,
+    pub name: String,
+    // Set for the sections named after a status, their tasks don't keep the
+    // section since it follows from their status
+    pub status: Option<Status>,
+    // Kanban lanes marked `**Complete**`, which check the tasks moved to them
+    pub complete: bool,
+    // Lines between the heading and the first task
+    pub notes: Vec<String>,
+}
+
+impl Layout {
+    // Layout used when writing tasks that weren't read from a checklist
+    pub fn new(dialect: Dialect) -> Self {
+        let lane = |name: &str, status| Lane {
+            level: "##".to_string(),
+            name: name.to_string(),
+            status: Some(status),
+            complete: dialect == Dialect::Kanban && status == Status::Done,
+            notes: vec![],
+        };
+        let kanban = dialect == Dialect::Kanban;
+        Self {
+            dialect,
+            front_matter: kanban.then(|| "\n\nkanban-plugin: basic\n\n".to_string()),
+            preamble: vec![],
+            lanes: vec![
+                lane("Todo", Status::Todo),
+                lane("Doing", Status::Doing),
+                lane("Done", Status::Done),
+            ],
+            settings: kanban.then(|| {
+                "%% kanban:settings\n```\n{\"kanban-plugin\":\"basic\"}\n```\n%%".to_string()
+            }),
+        }
+    }
+}
+
+// Recognise a checklist file: a Kanban board has `kanban-plugin` in its front
+// matter, a GFM task list has unindented `- [ ]` items
+pub fn detect_dialect(contents: &str) -> Option<Dialect> {
+    if let Some(front_matter) = front_matter(contents) {
+        if front_matter.contains("kanban-plugin") {
+            return Some(Dialect::Kanban);
+        }
+    }
+    let item_pattern = Regex::new(r"^[-*+] \[[ xX]\] ").unwrap();
+    contents
+        .lines()
+        .any(|line| item_pattern.is_match(line))
+        .then_some(Dialect::Gfm)
+}
+
+// Read the tasks of a checklist file, along with its layout
+pub fn parse_checklist(contents: &str, dialect: Dialect) -> (Vec<Task>, Layout, Vec<Diagnostic>) {
+    let heading_pattern = Regex::new(r"^(#{1,6}) (.*)$").unwrap();
+    let item_pattern = Regex::new(r"^[-*+] \[(.)\] ?(.*)$").unwrap();
+
+    let mut layout = Layout {
+        dialect,
+        front_matter: None,
+        preamble: vec![],
+        lanes: vec![],
+        settings: None,
+    };
+    let mut todo_list: Vec<Task> = vec![];
+    let mut diagnostics = vec![];
+
+    let mut lines = contents.lines().enumerate();
+    if let Some(front_matter) = front_matter(contents) {
+        layout.front_matter = Some(front_matter.to_string());
+        // The front matter starts and ends with the line breaks around it
+        let count = front_matter.matches('\n').count() + 1;
+        for _ in 0..count {
+            lines.next();
+        }
+    }
+
+    // Whether the last line read belongs to a task, for its description and subtasks
+    let mut in_task = false;
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let content = line.trim_start();
+
+        if line.starts_with("%% kanban:settings") {
+            let mut settings = vec![line];
+            for (_, line) in lines.by_ref() {
+                settings.push(line);
+                if line.trim() == "%%" {
+                    break;
+                }
+            }
+            layout.settings = Some(settings.join("\n"));
+            in_task = false;
+        } else if let Some(captures) = heading_pattern.captures(line) {
+            let name = captures[2].trim().to_string();
+            layout.lanes.push(Lane {
+                level: captures[1].to_string(),
+                status: lane_status(&name),
+                name,
+                complete: false,
+                notes: vec![],
+            });
+            in_task = false;
+        } else if let Some(captures) = item_pattern.captures(line) {
+            if layout.lanes.is_empty() {
+                // Items before the first heading, all of them in a plain
+                // checklist, are in a section without a heading
+                layout.lanes.push(Lane {
+                    level: String::new(),
+                    name: String::new(),
+                    status: None,
+                    complete: false,
+                    notes: vec![],
+                });
+            }
+            let lane = &layout.lanes[layout.lanes.len() - 1];
+            let done = match &captures[1] {
+                "x" | "X" => true,
+                " " => false,
+                marker => {
+                    diagnostics.push(Diagnostic::new(
+                        line_number,
+                        4,
+                        Severity::Warning,
+                        format!("unknown checkbox marker \"{}\", read as unchecked", marker),
+                    ));
+                    false
+                }
+            };
+            let status = item_status(lane, done);
+            let mut task = Task::new(captures[2].to_string(), String::new(), status, None);
+            let mut read = task.clone();
+            match parse_metadata(&mut read, &captures[2], dialect) {
+                Ok(name) => task = Task { name, ..read },
+                Err((field, message)) => diagnostics.push(Diagnostic::new(
+                    line_number,
+                    line.find(&field).map_or(1, |i| i + 1),
+                    Severity::Warning,
+                    message,
+                )),
+            }
+            // Keep the task where it is when its section doesn't follow from its status
+            if lane.status != Some(task.status) && !lane.level.is_empty() {
+                task.section = Some(lane.name.clone());
+            }
+            todo_list.push(task);
+            in_task = true;
+        } else if content.is_empty() {
+            continue;
+        } else if line == "**Complete**" && layout.lanes.last().is_some() {
+            if let Some(lane) = layout.lanes.last_mut() {
+                lane.complete = true;
+            }
+        } else if in_task && content.len() < line.len() {
+            // Indented lines belong to the task above
+            let Some(task) = todo_list.last_mut() else {
+                continue;
+            };
+            match item_pattern.captures(content) {
+                Some(captures) => task.subtasks.push(Subtask {
+                    name: captures[2].to_string(),
+                    status: !captures[1].trim().is_empty(),
+                }),
+                None if task.description.is_empty() => task.description = content.to_string(),
+                None => {
+                    task.description.push('\n');
+                    task.description.push_str(content);
+                }
+            }
+        } else if layout.lanes.is_empty() {
+            layout.preamble.push(line.to_string());
+        } else if !in_task {
+            if let Some(lane) = layout.lanes.last_mut() {
+                lane.notes.push(line.to_string());
+            }
+        } else {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                1,
+                Severity::Error,
+                "text between tasks is lost on save".to_string(),
+            ));
+        }
+    }
+
+    (todo_list, layout, diagnostics)
+}
+
+// Write the tasks back in the layout they were read from. Tasks in a section
+// that isn't in the layout, or whose status has no section, are written in
+// new sections at the end.
+pub fn render_checklist(todo_list: &[Task], layout: &Layout) -> String {
+    let mut lanes = layout.lanes.clone();
+    for task in todo_list {
+        if lane_index(&lanes, task).is_none() {
+            let name = match &task.section {
+                Some(section) => section.clone(),
+                None => task.status.to_string(),
+            };
+            lanes.push(Lane {
+                level: "##".to_string(),
+                status: task.section.is_none().then_some(task.status),
+                complete: layout.dialect == Dialect::Kanban && task.status == Status::Done,
+                name,
+                notes: vec![],
+            });
+        }
+    }
+
+    let mut data = String::new();
+    if let Some(front_matter) = &layout.front_matter {
+        data.push_str(&format!("---{}---\n\n", front_matter));
+    }
+    for line in &layout.preamble {
+        data.push_str(line);
+        data.push('\n');
+    }
+    if !layout.preamble.is_empty() {
+        data.push('\n');
+    }
+
+    for (i, lane) in lanes.iter().enumerate() {
+        if !lane.level.is_empty() {
+            data.push_str(&format!("{} {}\n\n", lane.level, lane.name));
+        }
+        if lane.complete {
+            data.push_str("**Complete**\n");
+        }
+        for line in &lane.notes {
+            data.push_str(line);
+            data.push('\n');
+        }
+        for task in todo_list
+            .iter()
+            .filter(|task| lane_index(&lanes, task) == Some(i))
+        {
+            render_item(task, lane, layout.dialect, &mut data);
+        }
+        if !lane.level.is_empty() || i + 1 < lanes.len() {
+            data.push('\n');
+        }
+    }
+
+    if let Some(settings) = &layout.settings {
+        data.push_str(&format!("\n\n\n{}\n", settings));
+    }
+    data
+}
+
+fn render_item(task: &Task, lane: &Lane, dialect: Dialect, data: &mut String) {
+    let done = task.status == Status::Done;
+    let mark = if done { 'x' } else { ' ' };
+    data.push_str(&format!("- [{}] {}", mark, task.name));
+    // A task in progress outside of the section for its status, for instance
+    let status = (item_status(lane, done) != task.status).then_some(task.status);
+    render_metadata(task, status, dialect, data);
+    data.push('\n');
+    for line in task.description.lines() {
+        data.push_str(&format!("    {}\n", line));
+    }
+    for subtask in &task.subtasks {
+        let mark = if subtask.status { 'x' } else { ' ' };
+        data.push_str(&format!("    - [{}] {}\n", mark, subtask.name));
+    }
+}
+
+// Write the fields of a task that have no place in a checklist item after its
+// name
+fn render_metadata(task: &Task, status: Option<Status>, dialect: Dialect, data: &mut String) {
+    let mut fields = vec![];
+    fields.extend(status.map(|status| format!("[status:: {}]", status.to_string().to_lowercase())));
+    match dialect {
+        Dialect::Kanban => {
+            fields.extend(
+                task.due_date
+                    .map(|date| format!("@{{{}}}", date.format(DATE_FORMAT))),
+            );
+            fields.extend(
+                task.due_date
+                    .and(task.due_time)
+                    .map(|time| format!("@@{{{}}}", time.format("%H:%M"))),
+            );
+            fields.extend(task.tags.iter().map(|tag| format!("#{}", tag)));
+        }
+        Dialect::Gfm => {
+            if let Some(date) = task.due_date {
+                let mut due = date.format(DATE_FORMAT).to_string();
+                if let Some(time) = task.due_time {
+                    due.push_str(&time.format(" %H:%M").to_string());
+                }
+                fields.push(format!("[due:: {}]", due));
+            }
+            if !task.tags.is_empty() {
+                fields.push(format!("[tags:: {}]", task.tags.join(" ")));
+            }
+        }
+    }
+    fields.extend(
+        task.scheduled
+            .map(|date| format!("[scheduled:: {}]", date.format(DATE_FORMAT))),
+    );
+    fields.extend(
+        task.priority
+            .map(|priority| format!("[priority:: {}]", priority)),
+    );
+    fields.extend(
+        task.recurrence
+            .as_ref()
+            .map(|recurrence| format!("[repeat:: {}]", recurrence)),
+    );
+    for (key, date) in [
+        ("created", task.created_at),
+        ("start", task.started_at),
+        ("completion", task.completed_at),
+    ] {
+        fields.extend(date.map(|date| format!("[{}:: {}]", key, date.format(DATE_TIME_FORMAT))));
+    }
+    for field in fields {
+        data.push(' ');
+        data.push_str(&field);
+    }
+}
+
+// Read the fields written at the end of an item into the task, returning the
+// name they follow. On an invalid field, returns the field and what is wrong
+// with it, the item is then read as a name.
+fn parse_metadata(
+    task: &mut Task,
+    text: &str,
+    dialect: Dialect,
+) -> Result<String, (String, String)> {
+    let field_pattern =
+        Regex::new(r"\s+(?:\[(\w+):: ([^\]]*)\]|@\{([^}]*)\}|@@\{([^}]*)\}|#([^\s#]+))$").unwrap();
+    let invalid = |field: &str, expected: &str| {
+        (
+            field.trim().to_string(),
+            format!("invalid \"{}\", expected {}", field.trim(), expected),
+        )
+    };
+
+    let mut name = text.trim_end();
+    let mut tags = vec![];
+    while let Some(captures) = field_pattern.captures(name) {
+        let field = captures.get(0).unwrap().as_str();
+        if let (Some(key), Some(value)) = (captures.get(1), captures.get(2)) {
+            let value = value.as_str().trim();
+            let date_time = || {
+                NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+                    .map_err(|_| invalid(field, "YYYY-MM-DD HH:MM"))
+            };
+            match key.as_str() {
+                "due" => {
+                    let (date, time) = match value.split_once(' ') {
+                        Some((date, time)) => (date, Some(time)),
+                        None => (value, None),
+                    };
+                    task.due_date = Some(
+                        NaiveDate::parse_from_str(date, DATE_FORMAT)
+                            .map_err(|_| invalid(field, "YYYY-MM-DD [HH:MM]"))?,
+                    );
+                    task.due_time = time
+                        .map(|time| NaiveTime::parse_from_str(time, "%H:%M"))
+                        .transpose()
+                        .map_err(|_| invalid(field, "YYYY-MM-DD [HH:MM]"))?;
+                }
+                "scheduled" => {
+                    task.scheduled = Some(
+                        NaiveDate::parse_from_str(value, DATE_FORMAT)
+                            .map_err(|_| invalid(field, "YYYY-MM-DD"))?,
+                    )
+                }
+                "status" => {
+                    task.status = match value {
+                        "todo" => Status::Todo,
+                        "doing" => Status::Doing,
+                        "done" => Status::Done,
+                        _ => return Err(invalid(field, "todo, doing or done")),
+                    }
+                }
+                "tags" => tags.extend(value.split_whitespace().rev().map(str::to_string)),
+                "priority" => match value.chars().next() {
+                    Some(priority) if value.len() == 1 && priority.is_ascii_uppercase() => {
+                        task.priority = Some(priority)
+                    }
+                    _ => return Err(invalid(field, "a priority from A to Z")),
+                },
+                "repeat" => {
+                    task.recurrence = Some(
+                        value
+                            .parse()
+                            .map_err(|message| (field.trim().to_string(), message))?,
+                    )
+                }
+                "created" => task.created_at = Some(date_time()?),
+                "start" => task.started_at = Some(date_time()?),
+                "completion" => task.completed_at = Some(date_time()?),
+                // A field of another tool is part of the name
+                _ => break,
+            }
+        } else if let Some(date) = captures.get(3) {
+            task.due_date = Some(
+                NaiveDate::parse_from_str(date.as_str(), DATE_FORMAT)
+                    .map_err(|_| invalid(field, "@{YYYY-MM-DD}"))?,
+            );
+        } else if let Some(time) = captures.get(4) {
+            task.due_time = Some(
+                NaiveTime::parse_from_str(time.as_str(), "%H:%M")
+                    .map_err(|_| invalid(field, "@@{HH:MM}"))?,
+            );
+        } else if let Some(tag) = captures.get(5) {
+            // `#12` is an issue in a GFM task list
+            if dialect != Dialect::Kanban {
+                break;
+            }
+            tags.push(tag.as_str().to_string());
+        }
+        name = &name[..name.len() - field.len()];
+    }
+    // A time is only kept along with a date
+    if task.due_date.is_none() {
+        task.due_time = None;
+    }
+    tags.reverse();
+    task.tags = tags;
+    Ok(name.to_string())
+}
+
+// Status of an item from its checkbox and the section it is in
+fn item_status(lane: &Lane, done: bool) -> Status {
+    match lane.status {
+        _ if done || lane.complete => Status::Done,
+        // An unchecked item in the Done section is still to do
+        Some(Status::Done) | None => Status::Todo,
+        Some(status) => status,
+    }
+}
+
+// Section a task is written in: the one it was kept in, or else the first one
+// for its status
+fn lane_index(lanes: &[Lane], task: &Task) -> Option<usize> {
+    match &task.section {
+        Some(section) => lanes.iter().position(|lane| lane.name == *section),
+        None => lanes
+            .iter()
+            .position(|lane| lane.status == Some(task.status))
+            .or_else(|| {
+                // Done tasks go to a Kanban lane that completes tasks
+                (task.status == Status::Done)
+                    .then(|| lanes.iter().position(|lane| lane.complete))
+                    .flatten()
+            })
+            // or else to the items before the first heading
+            .or_else(|| lanes.iter().position(|lane| lane.level.is_empty())),
+    }
+}
+
+// Status of the tasks in a section, for the usual names of Kanban columns
+pub fn lane_status(name: &str) -> Option<Status> {
+    match name.to_lowercase().as_str() {
+        "todo" | "to do" | "to-do" => Some(Status::Todo),
+        "doing" | "in progress" | "in-progress" => Some(Status::Doing),
+        "done" | "complete" | "completed" => Some(Status::Done),
+        _ => None,
+    }
+}
+
+// The text between the `---` lines opening a file
+fn front_matter(contents: &str) -> Option<&str> {
+    let rest = contents.strip_prefix("---")?;
+    let end = rest.find("\n---")?;
+    Some(&rest[..end + 1])
+}
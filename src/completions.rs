@@ -0,0 +1,84 @@
+use crate::convert::Format;
+use crate::output::OutputFormat;
+
+// Commands of `horme` and what they do, offered as the first word
+const COMMANDS: [(&str, &str); 21] = [
+    ("add", "Add a task"),
+    ("list", "List the tasks matching a query"),
+    ("ls", "List the tasks matching a query"),
+    ("edit", "Edit a task"),
+    ("edit-list", "Edit tasks at once in $EDITOR"),
+    ("delete", "Delete tasks"),
+    ("complete", "Move tasks to their next status"),
+    ("tag", "Add or remove tags of tasks"),
+    ("help", "Show the commands"),
+    ("today", "Tasks due today or earlier"),
+    ("upcoming", "Tasks due in the next 7 days"),
+    ("overdue", "Tasks due before today"),
+    ("someday", "Tasks without a due date"),
+    ("view", "Tasks of a view"),
+    ("shell", "Run commands from a prompt"),
+    ("stats", "Report the throughput of a list"),
+    ("check", "Validate a file"),
+    ("archive", "Archive the done tasks"),
+    ("export", "Write a list in another format"),
+    ("import", "Add the tasks of a file to a list"),
+    ("completions", "Print a completion script"),
+];
+
+// Fields of the query language, completed in list commands
+pub(crate) const QUERY_FIELDS: [&str; 9] = [
+    "status:",
+    "tag:",
+    "priority:",
+    "section:",
+    "due:",
+    "created:",
+    "done:",
+    "id:",
+    "is:",
+];
+
+pub const SHELLS: [&str; 3] = ["bash", "zsh", "fish"];
+
+// The completion script for a shell, `horme completions <shell>`. Task ids and
+// tags are completed by calling `horme __complete ids|tags <file>`.
+pub fn completion_script(shell: &str) -> Option<String> {
+    let names: Vec<&str> = COMMANDS.iter().map(|(name, _)| *name).collect();
+    let (template, commands) = match shell {
+        "bash" => (
+            include_str!("../completions/horme.bash"),
+            names.join(" "),
+        ),
+        "zsh" => (
+            include_str!("../completions/_horme"),
+            COMMANDS
+                .iter()
+                .map(|(name, about)| format!("'{}:{}'", name, about))
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        "fish" => (
+            include_str!("../completions/horme.fish"),
+            COMMANDS
+                .iter()
+                .map(|(name, about)| {
+                    format!(
+                        "complete -c horme -n \"not __fish_seen_subcommand_from $commands\" -a {} -d \"{}\"",
+                        name, about
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+        _ => return None,
+    };
+    Some(
+        template
+            .replace("@COMMAND_NAMES@", &names.join(" "))
+            .replace("@COMMANDS@", &commands)
+            .replace("@FORMATS@", &Format::NAMES.join(" "))
+            .replace("@OUTPUT_FORMATS@", &OutputFormat::NAMES.join(" "))
+            .replace("@QUERY_FIELDS@", &QUERY_FIELDS.join(" ")),
+    )
+}
@@ -0,0 +1,54 @@
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Deserialize;
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::view::SavedView;
+
+// Name of the configuration file looked up in the current directory
+const LOCAL_CONFIG_FILE: &str = "horme.toml";
+
+// User settings, read from `horme.toml` in the current directory or from
+// `$XDG_CONFIG_HOME/horme/config.toml` (`~/.config/horme/config.toml`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub archive: ArchiveConfig,
+    // Views shown after the smart ones, `[[views]]` tables
+    pub views: Vec<SavedView>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    // Archive the tasks that have been done for more than this many days
+    pub after_days: Option<u32>,
+}
+
+impl Config {
+    // Load the first configuration file found, or the defaults if there is none
+    pub fn load() -> Result<Self> {
+        match config_paths().into_iter().find(|path| path.exists()) {
+            Some(path) => Self::load_from(&path),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .wrap_err_with(|| format!("Unable to read {}", path.display()))?;
+        toml::from_str(&contents).wrap_err_with(|| format!("Invalid {}", path.display()))
+    }
+}
+
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(LOCAL_CONFIG_FILE)];
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME") {
+        paths.push(Path::new(&dir).join("horme").join("config.toml"));
+    } else if let Some(home) = env::var_os("HOME") {
+        paths.push(Path::new(&home).join(".config/horme/config.toml"));
+    }
+    paths
+}
@@ -0,0 +1,181 @@
+use color_eyre::{eyre::bail, Result};
+use std::path::Path;
+
+use crate::checklist::{parse_checklist, render_checklist, Dialect, Layout};
+use crate::html::{render_html, Theme};
+use crate::ical::{parse_ical, render_ical};
+use crate::json::{parse_json_document, render_json_document};
+use crate::markdown::{
+    default_sections_order, parse_todo_list_checked, render_todo_list, Diagnostic,
+};
+use crate::org::{parse_org, render_org};
+use crate::task::{next_id, Status, Task};
+use crate::taskwarrior::parse_taskwarrior;
+use crate::todoist::parse_todoist;
+use crate::todotxt::{parse_todo_txt, render_todo_txt};
+use crate::trello::parse_trello;
+
+// Formats task lists can be imported from and exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Json,
+    TodoTxt,
+    ICalendar,
+    Gfm,
+    Kanban,
+    Org,
+    // Export files of other task managers, which can only be imported
+    Taskwarrior,
+    Todoist,
+    Trello,
+    // A report to share with people who don't use horme, which can only be
+    // exported
+    Html(Theme),
+}
+
+impl Format {
+    // Names accepted by `--format`
+    pub const NAMES: &'static [&'static str] = &[
+        "markdown",
+        "json",
+        "todotxt",
+        "ical",
+        "gfm",
+        "kanban",
+        "org",
+        "taskwarrior",
+        "todoist",
+        "trello",
+        "html",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "markdown" | "md" => Some(Format::Markdown),
+            "json" => Some(Format::Json),
+            "todotxt" | "todo.txt" | "txt" => Some(Format::TodoTxt),
+            "ical" | "icalendar" | "ics" => Some(Format::ICalendar),
+            "gfm" => Some(Format::Gfm),
+            "kanban" => Some(Format::Kanban),
+            "org" => Some(Format::Org),
+            "taskwarrior" => Some(Format::Taskwarrior),
+            "todoist" => Some(Format::Todoist),
+            "trello" => Some(Format::Trello),
+            "html" | "htm" => Some(Format::Html(Theme::Light)),
+            _ => None,
+        }
+    }
+
+    // Guess the format of a file from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy();
+        Self::from_name(&extension)
+    }
+
+    // Read the tasks written in this format, along with the problems found
+    pub fn parse(self, contents: &str) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+        match self {
+            Format::Markdown => Ok(parse_todo_list_checked(contents)),
+            Format::Json => Ok((parse_json_document(contents)?, vec![])),
+            Format::TodoTxt => Ok(parse_todo_txt(contents)),
+            Format::ICalendar => Ok(parse_ical(contents)),
+            Format::Org => Ok(parse_org(contents)),
+            Format::Taskwarrior => parse_taskwarrior(contents),
+            Format::Todoist => parse_todoist(contents),
+            Format::Trello => parse_trello(contents),
+            Format::Html(_) => bail!("HTML reports can only be exported"),
+            Format::Gfm | Format::Kanban => {
+                let dialect = if self == Format::Kanban {
+                    Dialect::Kanban
+                } else {
+                    Dialect::Gfm
+                };
+                let (tasks, _, diagnostics) = parse_checklist(contents, dialect);
+                Ok((tasks, diagnostics))
+            }
+        }
+    }
+
+    // Write the tasks in this format
+    pub fn render(self, tasks: &[Task]) -> Result<String> {
+        match self {
+            Format::Markdown => Ok(render_todo_list(tasks, &default_sections_order())),
+            Format::Json => render_json_document(tasks),
+            Format::TodoTxt => Ok(render_todo_txt(tasks)),
+            Format::ICalendar => Ok(render_ical(tasks)),
+            Format::Gfm => Ok(render_checklist(tasks, &Layout::new(Dialect::Gfm))),
+            Format::Kanban => Ok(render_checklist(tasks, &Layout::new(Dialect::Kanban))),
+            Format::Org => Ok(render_org(tasks)),
+            Format::Html(theme) => Ok(render_html(tasks, theme)),
+            Format::Taskwarrior | Format::Todoist | Format::Trello => {
+                bail!("{:?} files can only be imported", self)
+            }
+        }
+    }
+}
+
+// Add imported tasks to a list. Tasks keep their id unless it is already
+// used in the list, in which case they get a new one.
+pub fn import_tasks(todo_list: &mut Vec<Task>, imported: Vec<Task>) -> usize {
+    let count = imported.len();
+    for task in imported {
+        if todo_list.iter().any(|other| other.id == task.id) {
+            // Still the same task for the program it comes from
+            todo_list.push(Task {
+                id: next_id(),
+                ..task
+            });
+        } else {
+            todo_list.push(task);
+        }
+    }
+    count
+}
+
+// Describe a list of tasks before importing it: how many there are for each
+// status, section and tag
+pub fn summarize(tasks: &[Task]) -> Vec<String> {
+    let count = |status| tasks.iter().filter(|task| task.status == status).count();
+    let mut lines = vec![format!(
+        "{} tasks: {} todo, {} doing, {} done",
+        tasks.len(),
+        count(Status::Todo),
+        count(Status::Doing),
+        count(Status::Done)
+    )];
+
+    let subtasks: usize = tasks.iter().map(|task| task.subtasks.len()).sum();
+    if subtasks > 0 {
+        lines.push(format!("{} subtasks", subtasks));
+    }
+    let sections = count_names(tasks.iter().filter_map(|task| task.section.as_deref()));
+    if !sections.is_empty() {
+        lines.push(format!("sections: {}", sections));
+    }
+    let tags = count_names(tasks.iter().flat_map(|task| &task.tags).map(String::as_str));
+    if !tags.is_empty() {
+        lines.push(format!("tags: {}", tags));
+    }
+    let due = tasks.iter().filter(|task| task.due_date.is_some()).count();
+    if due > 0 {
+        lines.push(format!("{} with a due date", due));
+    }
+    lines
+}
+
+// `name (count), other (count)`, in the order the names first appear
+fn count_names<'a>(names: impl Iterator<Item = &'a str>) -> String {
+    let mut counts: Vec<(&str, usize)> = vec![];
+    for name in names {
+        match counts.iter_mut().find(|(other, _)| *other == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    counts
+        .iter()
+        .map(|(name, count)| format!("{} ({})", name, count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,142 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{palette::tailwind::*, Color, Style, Stylize},
+    symbols,
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Borders, Gauge, Sparkline, Widget},
+};
+
+use crate::app::{DOING_FG_COLOR, NORMAL_ROW_BG, TEXT_FG_COLOR, TODO_FG_COLOR, TODO_HEADER_STYLE};
+use crate::stats::{Count, Stats};
+
+const OVERDUE_FG_COLOR: Color = RED.c400;
+
+// Charts of the throughput of a list, computed again on every draw so that
+// they follow the changes made to the tasks
+pub struct Dashboard<'a> {
+    pub stats: &'a Stats,
+}
+
+impl Widget for Dashboard<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [gauges_area, completions_area, open_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        let [done_area, overdue_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(gauges_area);
+        let [sections_area, tags_area, burndown_area] = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .areas(open_area);
+
+        self.render_gauges(done_area, overdue_area, buf);
+        self.render_completions(completions_area, buf);
+        render_open_tasks("Open by section", &self.stats.sections, sections_area, buf);
+        render_open_tasks("Open by tag", &self.stats.tags, tags_area, buf);
+        self.render_burndown(burndown_area, buf);
+    }
+}
+
+impl Dashboard<'_> {
+    fn render_gauges(&self, done_area: Rect, overdue_area: Rect, buf: &mut Buffer) {
+        let burndown = &self.stats.burndown;
+        let dated = burndown.open + burndown.done;
+        Gauge::default()
+            .block(block("Tasks with a due date done"))
+            .gauge_style(Style::new().fg(DOING_FG_COLOR).bg(NORMAL_ROW_BG))
+            .ratio(ratio(burndown.done, dated))
+            .label(format!("{}/{}", burndown.done, dated))
+            .render(done_area, buf);
+        Gauge::default()
+            .block(block("Open tasks overdue"))
+            .gauge_style(Style::new().fg(OVERDUE_FG_COLOR).bg(NORMAL_ROW_BG))
+            .ratio(ratio(self.stats.overdue, self.stats.open))
+            .label(format!("{}/{}", self.stats.overdue, self.stats.open))
+            .render(overdue_area, buf);
+    }
+
+    fn render_completions(&self, area: Rect, buf: &mut Buffer) {
+        let bars: Vec<Bar> = self
+            .stats
+            .completed_per_day
+            .iter()
+            .map(|day| {
+                Bar::default()
+                    .value(day.tasks as u64)
+                    .label(Line::raw(day.date.format("%d").to_string()))
+            })
+            .collect();
+        BarChart::default()
+            .block(block("Completed per day"))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::new().fg(DOING_FG_COLOR))
+            .value_style(Style::new().fg(NORMAL_ROW_BG).bg(DOING_FG_COLOR))
+            .label_style(Style::new().fg(TEXT_FG_COLOR))
+            .render(area, buf);
+    }
+
+    fn render_burndown(&self, area: Rect, buf: &mut Buffer) {
+        let burndown = &self.stats.burndown;
+        let remaining: Vec<u64> = burndown
+            .remaining
+            .iter()
+            .map(|day| day.tasks as u64)
+            .collect();
+        Sparkline::default()
+            .block(block(&format!(
+                "Burndown, {} due tasks left",
+                burndown.open
+            )))
+            .data(&remaining)
+            .style(Style::new().fg(TODO_FG_COLOR))
+            .render(area, buf);
+    }
+}
+
+// Horizontal bars of the open tasks of each section or tag
+fn render_open_tasks(title: &str, counts: &[Count], area: Rect, buf: &mut Buffer) {
+    let bars: Vec<Bar> = counts
+        .iter()
+        .filter(|count| count.open > 0)
+        .map(|count| {
+            Bar::default()
+                .value(count.open as u64)
+                .label(Line::raw(count.name.clone()))
+        })
+        .collect();
+    BarChart::default()
+        .block(block(title))
+        .direction(Direction::Horizontal)
+        .data(BarGroup::default().bars(&bars))
+        .bar_width(1)
+        .bar_gap(0)
+        .bar_style(Style::new().fg(TODO_FG_COLOR))
+        .value_style(Style::new().fg(NORMAL_ROW_BG).bg(TODO_FG_COLOR))
+        .label_style(Style::new().fg(TEXT_FG_COLOR))
+        .render(area, buf);
+}
+
+fn block(title: &str) -> Block<'static> {
+    Block::new()
+        .title(Line::raw(title.to_string()).centered())
+        .borders(Borders::TOP)
+        .border_set(symbols::border::EMPTY)
+        .border_style(TODO_HEADER_STYLE)
+        .bg(NORMAL_ROW_BG)
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
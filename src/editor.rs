@@ -0,0 +1,407 @@
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use regex::Regex;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::{
+    collections::HashSet,
+    env,
+    fmt::{self, Display},
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::markdown::{
+    default_sections_order, parse_todo_list_checked, render_todo_list, section_for,
+};
+use crate::task::{now, Status, Task};
+
+// Used when neither $VISUAL nor $EDITOR is set
+const DEFAULT_EDITOR: &str = "vi";
+
+// Numbers the temporary files of this process
+static EDIT_COUNT: AtomicU32 = AtomicU32::new(0);
+// Names tried before giving up on creating a temporary file
+const MAX_TEMP_FILES: u32 = 100;
+
+// The editor of the user, from $VISUAL or $EDITOR. It can have arguments,
+// like `code --wait`.
+pub fn editor_command() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string())
+}
+
+// Open `contents` in `editor`, given the file as its last argument, and
+// return the text saved
+pub fn edit_text(editor: &str, contents: &str) -> Result<String> {
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or(DEFAULT_EDITOR);
+
+    let path = create_temp_file(contents)?;
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(&path)
+        .status()
+        .wrap_err_with(|| format!("Unable to run the editor \"{}\"", editor));
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+
+    let status = status?;
+    if !status.success() {
+        bail!("The editor \"{}\" exited with {}", editor, status);
+    }
+    edited.wrap_err_with(|| format!("Unable to read {}", path.display()))
+}
+
+// Write `contents` to a new markdown file only the user can read, for the
+// syntax highlighting of the editor. The file must not exist yet, so that a
+// file or a link left in the shared temporary directory is never written to.
+fn create_temp_file(contents: &str) -> Result<PathBuf> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.subsec_nanos())
+            .unwrap_or_default();
+        let count = EDIT_COUNT.fetch_add(1, Ordering::SeqCst);
+        let path = env::temp_dir().join(format!("horme-{}-{}-{}.md", process::id(), nanos, count));
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())
+                    .wrap_err_with(|| format!("Unable to write {}", path.display()))?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < MAX_TEMP_FILES => {}
+            Err(e) => {
+                return Err(e).wrap_err_with(|| format!("Unable to create {}", path.display()))
+            }
+        }
+    }
+}
+
+// A task as markdown, under the heading of its status so that moving it to
+// another section changes its status
+pub fn task_snippet(task: &Task) -> String {
+    render_todo_list(
+        std::slice::from_ref(task),
+        &[section_for(task.status).to_string()],
+    )
+}
+
+// Read back the snippet of `task` once edited. The task keeps its id, and is
+// dated as done if it was moved to the Done section.
+pub fn parse_task_snippet(contents: &str, task: &Task) -> Result<Task> {
+    // Lines that can't be read would be lost, as would properties with
+    // invalid values
+    let (tasks, diagnostics) = parse_todo_list_checked(contents);
+    if let Some(diagnostic) = diagnostics.first() {
+        bail!("Line {}, the task wasn't changed", diagnostic);
+    }
+    let mut edited = match <[Task; 1]>::try_from(tasks) {
+        Ok([edited]) => edited,
+        Err(tasks) if tasks.is_empty() => bail!("No task left, the task wasn't changed"),
+        Err(tasks) => bail!(
+            "Found {} tasks instead of one, the task wasn't changed",
+            tasks.len()
+        ),
+    };
+    edited.id = task.id;
+    let status = edited.status;
+    edited.status = task.status;
+    edited.set_status(status);
+    Ok(edited)
+}
+
+// Edit a task, with its description and subtasks, in `editor`
+pub fn edit_task_in_editor(task: &Task, editor: &str) -> Result<Task> {
+    let snippet = task_snippet(task);
+    let edited = edit_text(editor, &snippet)?;
+    if edited == snippet {
+        return Ok(task.clone());
+    }
+    parse_task_snippet(&edited, task)
+}
+
+// Explains the list opened in the editor, it is ignored when reading it back
+const LIST_HELP: &str = "<!--
+Edit, add or delete tasks, reorder them, or move them under another heading
+to change their status. The marker at the end of the line of a task keeps
+track of it, a task without one is added to the list.
+Save and quit to apply the changes, nothing is changed if the file has
+problems. Empty the file to cancel.
+-->
+";
+
+// The changes made to a list in one go, undone together
+#[derive(Debug, Default)]
+pub struct ChangeSet {
+    pub before: Vec<Task>,
+    pub after: Vec<Task>,
+    pub added: usize,
+    pub deleted: usize,
+    // Tasks whose status changed
+    pub moved: usize,
+    pub renamed: usize,
+    // Tasks whose description, subtasks or properties changed
+    pub edited: usize,
+    pub reordered: bool,
+}
+
+impl ChangeSet {
+    pub fn new(before: Vec<Task>, after: Vec<Task>) -> Self {
+        let mut change_set = ChangeSet::default();
+        for task in &after {
+            match before.iter().find(|old| old.id == task.id) {
+                None => change_set.added += 1,
+                Some(old) if old.status != task.status => change_set.moved += 1,
+                Some(old) if old.name != task.name => change_set.renamed += 1,
+                Some(old) if !old.same_content(task) => change_set.edited += 1,
+                Some(_) => {}
+            }
+        }
+        change_set.deleted = before
+            .iter()
+            .filter(|old| !after.iter().any(|task| task.id == old.id))
+            .count();
+        // The tasks kept, in their order before and after
+        let kept_before = before
+            .iter()
+            .map(|task| task.id)
+            .filter(|id| after.iter().any(|task| task.id == *id));
+        let kept_after = after
+            .iter()
+            .map(|task| task.id)
+            .filter(|id| before.iter().any(|task| task.id == *id));
+        change_set.reordered = !kept_before.eq(kept_after);
+        change_set.before = before;
+        change_set.after = after;
+        change_set
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added + self.deleted + self.moved + self.renamed + self.edited == 0 && !self.reordered
+    }
+}
+
+impl Display for ChangeSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no changes");
+        }
+        let mut parts: Vec<String> = [
+            (self.added, "added"),
+            (self.deleted, "deleted"),
+            (self.moved, "moved"),
+            (self.renamed, "renamed"),
+            (self.edited, "edited"),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, what)| format!("{} {}", count, what))
+        .collect();
+        if self.reordered {
+            parts.push("reordered".to_string());
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+// The tasks of `todo_list` with the given ids as markdown, each one marked
+// with its id like `- Deploy nginx <!-- #12 -->`
+pub fn list_document(todo_list: &[Task], ids: &[u16]) -> String {
+    let marked: Vec<Task> = todo_list
+        .iter()
+        .filter(|task| ids.contains(&task.id))
+        .map(|task| Task {
+            name: format!("{} <!-- #{} -->", task.name, task.id),
+            ..task.clone()
+        })
+        .collect();
+    format!(
+        "{}\n{}",
+        LIST_HELP,
+        render_todo_list(&marked, &default_sections_order())
+    )
+}
+
+// Read back the document of the tasks of `todo_list` with the given ids once
+// edited, and work out the list it gives
+pub fn parse_list_document(contents: &str, todo_list: &[Task], ids: &[u16]) -> Result<ChangeSet> {
+    let marker = Regex::new(r"\s*<!--\s*#(\d+)\s*-->\s*$").unwrap();
+    let (tasks, diagnostics) = parse_todo_list_checked(&strip_comments(contents));
+    if let Some(diagnostic) = diagnostics.first() {
+        bail!("Line {}, the list wasn't changed", diagnostic);
+    }
+
+    // Tasks keep their id as long as their marker is found once, copies of a
+    // task are added as new tasks
+    let mut seen = HashSet::new();
+    let mut edited = vec![];
+    for mut task in tasks {
+        let id = marker
+            .captures(&task.name)
+            .and_then(|captures| captures[1].parse::<u16>().ok());
+        task.name = marker.replace(&task.name, "").to_string();
+        let original = id
+            .filter(|id| ids.contains(id) && seen.insert(*id))
+            .and_then(|id| todo_list.iter().find(|task| task.id == id));
+        let status = task.status;
+        match original {
+            Some(original) => {
+                task.id = original.id;
+                task.status = original.status;
+            }
+            // New tasks have an id of their own already
+            None => {
+                task.created_at.get_or_insert_with(now);
+                // Dated as done now, unless the date was written
+                if task.completed_at.is_none() {
+                    task.status = Status::Todo;
+                }
+            }
+        }
+        task.set_status(status);
+        edited.push(task);
+    }
+
+    // Recurring tasks that get done come back
+    let today = now().date();
+    let next: Vec<Task> = edited
+        .iter_mut()
+        .filter(|task| {
+            task.status == Status::Done
+                && todo_list
+                    .iter()
+                    .any(|old| old.id == task.id && old.status != Status::Done)
+        })
+        .filter_map(|task| task.next_occurrence(today))
+        .collect();
+
+    let mut after = arrange(todo_list, ids, &edited);
+    after.extend(next);
+    Ok(ChangeSet::new(todo_list.to_vec(), after))
+}
+
+// Blank out the comments, keeping the lines where they were so that problems
+// are reported on the right line
+fn strip_comments(contents: &str) -> String {
+    let mut in_comment = false;
+    let mut stripped = String::new();
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if !in_comment && trimmed.starts_with("<!--") {
+            in_comment = true;
+        }
+        if !in_comment {
+            stripped.push_str(line);
+        }
+        if in_comment && trimmed.ends_with("-->") {
+            in_comment = false;
+        }
+        stripped.push('\n');
+    }
+    stripped
+}
+
+// Put the edited tasks in the list in place of the tasks with the given ids.
+// Tasks stay where they were unless they were reordered in the editor, the
+// document showing the tasks grouped by section.
+fn arrange(todo_list: &[Task], ids: &[u16], edited: &[Task]) -> Vec<Task> {
+    let in_place = arrange_in_place(todo_list, ids, edited);
+    let section = |task: &Task| {
+        default_sections_order()
+            .iter()
+            .position(|heading| heading == section_for(task.status))
+    };
+    let mut shown: Vec<&Task> = in_place.iter().collect();
+    shown.sort_by_key(|task| section(task));
+    let kept = |task: &&Task| todo_list.iter().any(|old| old.id == task.id);
+    let shown_ids = shown.into_iter().filter(kept).map(|task| task.id);
+    let edited_ids = edited.iter().filter(kept).map(|task| task.id);
+    if shown_ids.eq(edited_ids) {
+        return in_place;
+    }
+
+    // The edited tasks take the places of the tasks of the document, in their
+    // new order, and the extra ones follow the last of them
+    let mut arranged = vec![];
+    let mut edited_tasks = edited.iter().cloned();
+    let last = todo_list.iter().rposition(|task| ids.contains(&task.id));
+    for (i, task) in todo_list.iter().enumerate() {
+        if ids.contains(&task.id) {
+            arranged.extend(edited_tasks.next());
+        } else {
+            arranged.push(task.clone());
+        }
+        if Some(i) == last {
+            arranged.extend(edited_tasks.by_ref());
+        }
+    }
+    arranged.extend(edited_tasks);
+    arranged
+}
+
+// The edited tasks replacing the tasks they come from, new tasks following
+// the task before them in the document
+fn arrange_in_place(todo_list: &[Task], ids: &[u16], edited: &[Task]) -> Vec<Task> {
+    let mut arranged: Vec<Task> = todo_list
+        .iter()
+        .filter(|task| !ids.contains(&task.id) || edited.iter().any(|e| e.id == task.id))
+        .map(|task| {
+            edited
+                .iter()
+                .find(|e| e.id == task.id)
+                .unwrap_or(task)
+                .clone()
+        })
+        .collect();
+    let first = todo_list
+        .iter()
+        .position(|task| ids.contains(&task.id))
+        .unwrap_or(todo_list.len());
+    // Where the tasks before the first one of the document end up
+    let start = todo_list[..first]
+        .iter()
+        .filter(|task| !ids.contains(&task.id))
+        .count();
+    for (i, task) in edited.iter().enumerate() {
+        if arranged.iter().any(|kept| kept.id == task.id) {
+            continue;
+        }
+        let position = match i.checked_sub(1) {
+            Some(previous) => {
+                arranged
+                    .iter()
+                    .position(|kept| kept.id == edited[previous].id)
+                    .unwrap_or(arranged.len())
+                    + 1
+            }
+            None => start,
+        };
+        arranged.insert(position, task.clone());
+    }
+    arranged
+}
+
+// Edit the tasks of `todo_list` with the given ids in `editor`, all at once.
+// An empty file leaves the list as it is.
+pub fn edit_list_in_editor(todo_list: &[Task], ids: &[u16], editor: &str) -> Result<ChangeSet> {
+    let document = list_document(todo_list, ids);
+    let edited = edit_text(editor, &document)?;
+    if edited == document || edited.trim().is_empty() {
+        return Ok(ChangeSet::new(todo_list.to_vec(), todo_list.to_vec()));
+    }
+    parse_list_document(&edited, todo_list, ids)
+}
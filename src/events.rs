@@ -0,0 +1,113 @@
+use color_eyre::Result;
+use crossterm::event::{self, Event, KeyEvent, KeyEventKind};
+use std::{
+    io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use crate::markdown::Diagnostic;
+use crate::storage::Storage;
+use crate::task::Task;
+use crate::watch::FileWatcher;
+
+// How often time-driven work runs: looking at the files on disk, keeping the
+// locks fresh and redrawing the clock and the dates
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+// Longest wait for a terminal event before looking for messages again
+const MESSAGE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+// What the main loop of the app reacts to
+pub enum AppEvent {
+    Key(KeyEvent),
+    // The terminal was resized to these columns and rows
+    Resize(u16, u16),
+    FocusGained,
+    FocusLost,
+    // Sent every `TICK_RATE`
+    Tick,
+    Message(Message),
+}
+
+// A list read from its file, with the storage that read it
+pub type LoadedList = (Box<dyn Storage>, Vec<Task>, Vec<Diagnostic>);
+
+// Sent by the work done in the background once it is over
+pub enum Message {
+    // The file of a tab was read again after it changed on disk. `load`
+    // numbers the read among those of the tab, `saves` is the number of times
+    // the tab had been saved when it started and `seen` the file that was read.
+    Loaded {
+        file_path: PathBuf,
+        load: u64,
+        saves: u64,
+        seen: FileWatcher,
+        loaded: Result<LoadedList>,
+    },
+}
+
+// Terminal events, ticks and messages, in the order they come
+pub struct Events {
+    tick_rate: Duration,
+    last_tick: Instant,
+    sender: Sender<Message>,
+    receiver: Receiver<Message>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new(TICK_RATE)
+    }
+}
+
+impl Events {
+    pub fn new(tick_rate: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            tick_rate,
+            last_tick: Instant::now(),
+            sender,
+            receiver,
+        }
+    }
+
+    // Where background work sends its message
+    pub fn sender(&self) -> Sender<Message> {
+        self.sender.clone()
+    }
+
+    // Wait for a message from background work, for at most `timeout`
+    pub fn recv_message(&self, timeout: Duration) -> Option<Message> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    // Wait for the next event. Messages come first, then terminal events, and
+    // the tick once it is due, so that input never waits for a tick.
+    pub fn next(&mut self) -> io::Result<AppEvent> {
+        loop {
+            if let Ok(message) = self.receiver.try_recv() {
+                return Ok(AppEvent::Message(message));
+            }
+            let until_tick = self.tick_rate.saturating_sub(self.last_tick.elapsed());
+            if until_tick.is_zero() {
+                self.last_tick = Instant::now();
+                return Ok(AppEvent::Tick);
+            }
+            if !event::poll(until_tick.min(MESSAGE_POLL_INTERVAL))? {
+                continue;
+            }
+            match event::read()? {
+                // Key releases are only reported by some terminals
+                Event::Key(key) if key.kind != KeyEventKind::Release => {
+                    return Ok(AppEvent::Key(key))
+                }
+                Event::Resize(columns, rows) => return Ok(AppEvent::Resize(columns, rows)),
+                Event::FocusGained => return Ok(AppEvent::FocusGained),
+                Event::FocusLost => return Ok(AppEvent::FocusLost),
+                _ => {}
+            }
+        }
+    }
+}
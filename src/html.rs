@@ -0,0 +1,310 @@
+use pulldown_cmark::{html, Event, Parser, Tag};
+use ratatui::style::Color;
+
+use crate::app::{
+    ALT_ROW_BG_COLOR, COMPLETED_TEXT_FG_COLOR, DOING_FG_COLOR, DONE_FG_COLOR, NORMAL_ROW_BG,
+    TEXT_FG_COLOR, TODO_FG_COLOR, TODO_HEADER_STYLE,
+};
+use crate::task::{now, Status, Task, DATE_FORMAT, DATE_TIME_FORMAT};
+
+// Colours of the report
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    // Dark text on a white page, for printing and sharing
+    Light,
+    // The colours of the TUI
+    Tui,
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "light" => Some(Theme::Light),
+            "tui" | "dark" => Some(Theme::Tui),
+            _ => None,
+        }
+    }
+
+    // Values of the CSS variables used by the stylesheet
+    fn variables(self) -> Vec<(&'static str, String)> {
+        match self {
+            Theme::Light => vec![
+                ("page", "#ffffff".to_string()),
+                ("card", "#f8fafc".to_string()),
+                ("column", "#f1f5f9".to_string()),
+                ("text", "#0f172a".to_string()),
+                ("muted", "#64748b".to_string()),
+                ("header-fg", "#0f172a".to_string()),
+                ("header-bg", "#fbbf24".to_string()),
+                ("todo", "#d97706".to_string()),
+                ("doing", "#16a34a".to_string()),
+                ("done", "#64748b".to_string()),
+                ("progress", "#16a34a".to_string()),
+            ],
+            Theme::Tui => vec![
+                ("page", css_color(NORMAL_ROW_BG)),
+                ("card", css_color(ALT_ROW_BG_COLOR)),
+                ("column", css_color(NORMAL_ROW_BG)),
+                ("text", css_color(TEXT_FG_COLOR)),
+                ("muted", css_color(DONE_FG_COLOR)),
+                (
+                    "header-fg",
+                    css_color(TODO_HEADER_STYLE.fg.unwrap_or(Color::Black)),
+                ),
+                (
+                    "header-bg",
+                    css_color(TODO_HEADER_STYLE.bg.unwrap_or(Color::Yellow)),
+                ),
+                ("todo", css_color(TODO_FG_COLOR)),
+                ("doing", css_color(DOING_FG_COLOR)),
+                ("done", css_color(DONE_FG_COLOR)),
+                ("progress", css_color(COMPLETED_TEXT_FG_COLOR)),
+            ],
+        }
+    }
+}
+
+const STYLESHEET: &str = "
+* { box-sizing: border-box; }
+body { margin: 0; padding: 2rem; background: var(--page); color: var(--text);
+       font-family: system-ui, -apple-system, 'Segoe UI', sans-serif; line-height: 1.4; }
+header { display: flex; align-items: baseline; gap: 1rem; flex-wrap: wrap; margin-bottom: 1.5rem; }
+h1 { margin: 0; padding: 0.2rem 0.8rem; background: var(--header-bg); color: var(--header-fg); font-size: 1.4rem; }
+.summary { color: var(--muted); }
+.board { display: grid; grid-template-columns: repeat(auto-fit, minmax(18rem, 1fr)); gap: 1rem; align-items: start; }
+.column { background: var(--column); border: 1px solid var(--muted); border-radius: 6px; padding: 0.8rem; }
+.column h2 { margin: 0 0 0.8rem; font-size: 1.1rem; }
+.card { background: var(--card); border-left: 4px solid var(--muted); border-radius: 4px; padding: 0.6rem 0.8rem; margin-bottom: 0.6rem; }
+.card.todo { border-color: var(--todo); } .card.doing { border-color: var(--doing); } .card.done { border-color: var(--done); }
+.card.done .name { text-decoration: line-through; color: var(--muted); }
+.glyph { margin-right: 0.4rem; }
+.todo .glyph { color: var(--todo); } .doing .glyph { color: var(--doing); } .done .glyph { color: var(--done); }
+.meta { font-size: 0.85rem; color: var(--muted); margin-top: 0.2rem; }
+.tag, .priority { display: inline-block; border: 1px solid var(--muted); border-radius: 3px; padding: 0 0.3rem; margin-right: 0.3rem; }
+.description { font-size: 0.9rem; }
+.description p { margin: 0.4rem 0; }
+.subtasks { list-style: none; padding: 0; margin: 0.4rem 0 0; font-size: 0.9rem; }
+.progress { height: 0.4rem; background: var(--column); border-radius: 2px; margin-top: 0.4rem; overflow: hidden; }
+.progress div { height: 100%; background: var(--progress); }
+.empty { color: var(--muted); font-style: italic; }
+footer { margin-top: 2rem; color: var(--muted); font-size: 0.8rem; }
+";
+
+// Write the tasks as a self-contained HTML page, with a column for each
+// section of the list
+pub fn render_html(todo_list: &[Task], theme: Theme) -> String {
+    let mut data =
+        String::from("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    data.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    data.push_str("<title>Horme</title>\n<style>\n:root {");
+    for (name, value) in theme.variables() {
+        data.push_str(&format!(" --{}: {};", name, value));
+    }
+    data.push_str(" }");
+    data.push_str(STYLESHEET);
+    data.push_str("</style>\n</head>\n<body>\n");
+
+    let done = todo_list
+        .iter()
+        .filter(|task| task.status == Status::Done)
+        .count();
+    data.push_str(&format!(
+        "<header><h1>Horme</h1><span class=\"summary\">{} of {} tasks done</span></header>\n",
+        done,
+        todo_list.len()
+    ));
+    data.push_str(&progress_bar(done, todo_list.len()));
+
+    data.push_str("<main class=\"board\">\n");
+    for (title, tasks) in columns(todo_list) {
+        data.push_str(&format!(
+            "<section class=\"column\">\n<h2>{} <span class=\"summary\">{}</span></h2>\n",
+            escape(&title),
+            tasks.len()
+        ));
+        if tasks.is_empty() {
+            data.push_str("<p class=\"empty\">No tasks</p>\n");
+        }
+        for task in tasks {
+            render_card(task, &mut data);
+        }
+        data.push_str("</section>\n");
+    }
+    data.push_str("</main>\n");
+
+    data.push_str(&format!(
+        "<footer>Generated by horme on {}</footer>\n</body>\n</html>\n",
+        now().format(DATE_TIME_FORMAT)
+    ));
+    data
+}
+
+// The columns of the page: one for each status, followed by one for each
+// section that doesn't follow from the status of its tasks
+fn columns(todo_list: &[Task]) -> Vec<(String, Vec<&Task>)> {
+    let mut columns: Vec<(String, Vec<&Task>)> = [Status::Todo, Status::Doing, Status::Done]
+        .iter()
+        .map(|status| {
+            let tasks = todo_list
+                .iter()
+                .filter(|task| task.section.is_none() && task.status == *status)
+                .collect();
+            (status.to_string(), tasks)
+        })
+        .collect();
+    for task in todo_list {
+        let Some(section) = &task.section else {
+            continue;
+        };
+        match columns
+            .iter_mut()
+            .skip(3)
+            .find(|(title, _)| title == section)
+        {
+            Some((_, tasks)) => tasks.push(task),
+            None => columns.push((section.clone(), vec![task])),
+        }
+    }
+    columns
+}
+
+fn render_card(task: &Task, data: &mut String) {
+    let (class, glyph) = match task.status {
+        Status::Todo => ("todo", "○"),
+        Status::Doing => ("doing", "◐"),
+        Status::Done => ("done", "●"),
+    };
+    data.push_str(&format!(
+        "<article class=\"card {}\">\n<div><span class=\"glyph\" title=\"{}\">{}</span><span class=\"name\">{}</span></div>\n",
+        class,
+        task.status,
+        glyph,
+        escape(&task.name)
+    ));
+
+    let mut meta = vec![];
+    if let Some(priority) = task.priority {
+        meta.push(format!("<span class=\"priority\">{}</span>", priority));
+    }
+    if let Some(date) = task.due_date {
+        meta.push(format!("due {}", date.format(DATE_FORMAT)));
+    }
+    if let Some(date) = task.completed_at {
+        meta.push(format!("done {}", date.format(DATE_TIME_FORMAT)));
+    }
+    for tag in &task.tags {
+        meta.push(format!("<span class=\"tag\">{}</span>", escape(tag)));
+    }
+    if !meta.is_empty() {
+        data.push_str(&format!("<div class=\"meta\">{}</div>\n", meta.join(" ")));
+    }
+
+    if !task.description.is_empty() {
+        data.push_str(&format!(
+            "<div class=\"description\">{}</div>\n",
+            markdown_to_html(&task.description)
+        ));
+    }
+
+    if !task.subtasks.is_empty() {
+        data.push_str("<ul class=\"subtasks\">\n");
+        for subtask in &task.subtasks {
+            let checked = if subtask.status { " checked" } else { "" };
+            data.push_str(&format!(
+                "<li><label><input type=\"checkbox\" disabled{}> {}</label></li>\n",
+                checked,
+                escape(&subtask.name)
+            ));
+        }
+        data.push_str("</ul>\n");
+        let done = task
+            .subtasks
+            .iter()
+            .filter(|subtask| subtask.status)
+            .count();
+        data.push_str(&format!(
+            "<div class=\"meta\">{}/{} subtasks</div>\n",
+            done,
+            task.subtasks.len()
+        ));
+        data.push_str(&progress_bar(done, task.subtasks.len()));
+    }
+    data.push_str("</article>\n");
+}
+
+fn progress_bar(done: usize, total: usize) -> String {
+    let percent = (done * 100).checked_div(total).unwrap_or(0);
+    format!(
+        "<div class=\"progress\" title=\"{}%\"><div style=\"width: {}%\"></div></div>\n",
+        percent, percent
+    )
+}
+
+// Render a description written in markdown. Raw HTML is escaped and links
+// only go to web pages, mail addresses or relative paths, so that the report
+// can't run anything.
+fn markdown_to_html(text: &str) -> String {
+    let parser = Parser::new(text).map(|event| match event {
+        Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+        Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Link {
+            link_type,
+            dest_url: "#".into(),
+            title,
+            id,
+        }),
+        Event::Start(Tag::Image {
+            link_type,
+            dest_url,
+            title,
+            id,
+        }) if !is_safe_url(&dest_url) => Event::Start(Tag::Image {
+            link_type,
+            dest_url: "".into(),
+            title,
+            id,
+        }),
+        event => event,
+    });
+    let mut output = String::new();
+    html::push_html(&mut output, parser);
+    output
+}
+
+// http(s) and mailto URLs, or URLs without a scheme. Browsers ignore blanks
+// and control characters in a scheme, so they are ignored here too.
+fn is_safe_url(url: &str) -> bool {
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(i) if url[i..].starts_with(':') => {
+            let scheme = url[..i].to_ascii_lowercase();
+            matches!(scheme.as_str(), "http" | "https" | "mailto")
+        }
+        _ => true,
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// CSS colour for a colour of the TUI, its palette colours are all RGB
+fn css_color(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        Color::Black => "black".to_string(),
+        Color::White => "white".to_string(),
+        Color::Yellow => "gold".to_string(),
+        _ => "gray".to_string(),
+    }
+}
@@ -0,0 +1,594 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+use crate::markdown::{Diagnostic, Severity};
+use crate::recurrence::{Recurrence, Unit};
+use crate::task::{Status, Subtask, Task};
+
+const DATE_FORMAT: &str = "%Y%m%d";
+const DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+
+// Lines longer than this many bytes are folded (RFC 5545, 3.1)
+const MAX_LINE_LENGTH: usize = 75;
+
+// Days of the week as they are written in a recurrence rule
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+// A VTODO component, as a list of properties
+struct Component {
+    line: usize,
+    properties: Vec<Property>,
+}
+
+struct Property {
+    line: usize,
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Component {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+}
+
+// Read the VTODO components of an iCalendar file (RFC 5545). Components
+// related to another one by `RELATED-TO` become subtasks of that task, the
+// other components are ignored.
+pub fn parse_ical(contents: &str) -> (Vec<Task>, Vec<Diagnostic>) {
+    let mut diagnostics = vec![];
+    let components = parse_components(contents, &mut diagnostics);
+
+    let uids: HashMap<&str, usize> = components
+        .iter()
+        .enumerate()
+        .filter_map(|(i, component)| Some((component.get("UID")?.value.as_str(), i)))
+        .collect();
+    // The top-level task of each component, following RELATED-TO to the root
+    let root_of = |mut i: usize| {
+        for _ in 0..components.len() {
+            match parent_uid(&components[i]).and_then(|uid| uids.get(uid)) {
+                Some(&parent) if parent != i => i = parent,
+                _ => break,
+            }
+        }
+        i
+    };
+
+    let mut todo_list = vec![];
+    let mut task_index = HashMap::new();
+    for (i, component) in components.iter().enumerate() {
+        if root_of(i) != i {
+            continue;
+        }
+        let Some(summary) = component.get("SUMMARY").filter(|p| !p.value.is_empty()) else {
+            diagnostics.push(Diagnostic::new(
+                component.line,
+                1,
+                Severity::Error,
+                "VTODO without a SUMMARY".to_string(),
+            ));
+            continue;
+        };
+        let mut task = Task::new(summary.value.clone(), String::new(), Status::Todo, None);
+        read_properties(&mut task, component, &mut diagnostics);
+        task_index.insert(i, todo_list.len());
+        todo_list.push(task);
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        let root = root_of(i);
+        let Some(&index) = task_index.get(&root).filter(|_| root != i) else {
+            continue;
+        };
+        let name = component
+            .get("SUMMARY")
+            .map(|property| unescape(&property.value))
+            .unwrap_or_default();
+        let done = component
+            .get("STATUS")
+            .is_some_and(|property| property.value == "COMPLETED");
+        todo_list[index]
+            .subtasks
+            .push(Subtask { name, status: done });
+    }
+
+    (todo_list, diagnostics)
+}
+
+// Write the tasks as an iCalendar file with one VTODO per task, and one per
+// subtask related to its task. The start and section of a task, which have
+// no iCalendar property, are written in properties of our own.
+pub fn render_ical(todo_list: &[Task]) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//horme//horme//EN".to_string(),
+    ];
+    let stamp = format!("{}Z", Utc::now().format(DATE_TIME_FORMAT));
+    let mut fallback_uids = HashMap::new();
+
+    for task in todo_list {
+        let uid = match &task.uid {
+            Some(uid) => uid.clone(),
+            None => fallback_uid(task, &mut fallback_uids),
+        };
+        lines.push("BEGIN:VTODO".to_string());
+        lines.push(format!("UID:{}", uid));
+        lines.push(format!("DTSTAMP:{}", stamp));
+        lines.push(format!("SUMMARY:{}", escape(&task.name)));
+        if !task.description.is_empty() {
+            lines.push(format!("DESCRIPTION:{}", escape(&task.description)));
+        }
+        let status = match task.status {
+            Status::Todo => "NEEDS-ACTION",
+            Status::Doing => "IN-PROCESS",
+            Status::Done => "COMPLETED",
+        };
+        lines.push(format!("STATUS:{}", status));
+        match (task.due_date, task.due_time) {
+            (Some(date), Some(time)) => {
+                lines.push(format!("DUE:{}", format_date_time(date.and_time(time))))
+            }
+            (Some(date), None) => {
+                lines.push(format!("DUE;VALUE=DATE:{}", date.format(DATE_FORMAT)))
+            }
+            _ => {}
+        }
+        if let Some(date) = task.scheduled {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", date.format(DATE_FORMAT)));
+        }
+        if let Some(recurrence) = &task.recurrence {
+            lines.push(format!("RRULE:{}", format_rule(recurrence)));
+        }
+        if let Some(date) = task.created_at {
+            lines.push(format!("CREATED:{}", format_utc(date)));
+        }
+        if let Some(date) = task.started_at {
+            lines.push(format!("X-HORME-STARTED:{}", format_utc(date)));
+        }
+        if let Some(date) = task.completed_at {
+            lines.push(format!("COMPLETED:{}", format_utc(date)));
+        }
+        if let Some(priority) = task.priority {
+            // 1 is the highest priority and 9 the lowest, the priorities
+            // past I are all 9 and the letter is kept in a property of ours
+            let level = (priority as u8).saturating_sub(b'A').saturating_add(1);
+            lines.push(format!("PRIORITY:{}", level.min(9)));
+            if level > 9 {
+                lines.push(format!("X-HORME-PRIORITY:{}", priority));
+            }
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|tag| escape(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        if let Some(section) = &task.section {
+            lines.push(format!("X-HORME-SECTION:{}", escape(section)));
+        }
+        lines.push("END:VTODO".to_string());
+
+        for (i, subtask) in task.subtasks.iter().enumerate() {
+            lines.push("BEGIN:VTODO".to_string());
+            lines.push(format!("UID:{}-{}", uid, i + 1));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("SUMMARY:{}", escape(&subtask.name)));
+            let status = if subtask.status {
+                "COMPLETED"
+            } else {
+                "NEEDS-ACTION"
+            };
+            lines.push(format!("STATUS:{}", status));
+            lines.push(format!("RELATED-TO;RELTYPE=PARENT:{}", uid));
+            lines.push("END:VTODO".to_string());
+        }
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut data = String::new();
+    for line in lines {
+        fold_line(&line, &mut data);
+    }
+    data
+}
+
+// Split the file into the VTODO components it contains, leaving out the
+// components nested in them
+fn parse_components(contents: &str, diagnostics: &mut Vec<Diagnostic>) -> Vec<Component> {
+    let mut components = vec![];
+    let mut current: Option<Component> = None;
+    // Components nested in the current one, alarms for instance, whose
+    // properties are not those of the task
+    let mut depth = 0;
+
+    for (line_number, line) in unfold_lines(contents) {
+        let Some(property) = parse_property(line_number, &line) else {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                1,
+                Severity::Warning,
+                "line without a property name is ignored".to_string(),
+            ));
+            continue;
+        };
+        let Some(component) = &mut current else {
+            if property.name == "BEGIN" && property.value == "VTODO" {
+                current = Some(Component {
+                    line: line_number,
+                    properties: vec![],
+                });
+                depth = 0;
+            }
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => depth += 1,
+            "END" if depth > 0 => depth -= 1,
+            "END" => components.extend(current.take()),
+            _ if depth == 0 => component.properties.push(property),
+            _ => {}
+        }
+    }
+
+    components
+}
+
+// Join the folded lines, keeping the number of the line each one starts on
+fn unfold_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = vec![];
+    for (i, line) in contents.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ if line.is_empty() => {}
+            _ => lines.push((i + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+// Read `NAME;PARAM=value:value`, the parameter values may be quoted
+fn parse_property(line_number: usize, line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = parts
+        .filter_map(|param| param.split_once('='))
+        .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+    Some(Property {
+        line: line_number,
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+// Fill the fields of a task from the properties of its component
+fn read_properties(task: &mut Task, component: &Component, diagnostics: &mut Vec<Diagnostic>) {
+    let mut warn = |property: &Property, message: String| {
+        diagnostics.push(Diagnostic::new(
+            property.line,
+            property.name.len() + 2,
+            Severity::Warning,
+            message,
+        ));
+    };
+    let invalid = |property: &Property, expected: &str| {
+        format!(
+            "invalid {} \"{}\", expected {}",
+            property.name, property.value, expected
+        )
+    };
+
+    for property in &component.properties {
+        let value = property.value.as_str();
+        match property.name.as_str() {
+            "UID" => task.uid = Some(value.to_string()),
+            "SUMMARY" => task.name = unescape(value),
+            "DESCRIPTION" => task.description = unescape(value),
+            "STATUS" => match value {
+                "NEEDS-ACTION" => task.status = Status::Todo,
+                "IN-PROCESS" => task.status = Status::Doing,
+                "COMPLETED" | "CANCELLED" => task.status = Status::Done,
+                _ => warn(
+                    property,
+                    invalid(property, "NEEDS-ACTION, IN-PROCESS or COMPLETED"),
+                ),
+            },
+            "DUE" | "DTSTART" | "CREATED" | "X-HORME-STARTED" | "COMPLETED" => {
+                let Some(date) = parse_date_time(value) else {
+                    warn(property, invalid(property, "a date"));
+                    continue;
+                };
+                let date = match time_zone(property) {
+                    Some(Ok(zone)) => zone
+                        .from_local_datetime(&date)
+                        .earliest()
+                        .map_or(date, |date| date.with_timezone(&Local).naive_local()),
+                    Some(Err(tzid)) => {
+                        warn(
+                            property,
+                            format!(
+                                "unknown time zone \"{}\", the time is read as local time",
+                                tzid
+                            ),
+                        );
+                        date
+                    }
+                    None => date,
+                };
+                match property.name.as_str() {
+                    "DUE" => {
+                        task.due_date = Some(date.date());
+                        // Dates without a time are due at some point of the day
+                        task.due_time = value.contains('T').then(|| date.time());
+                    }
+                    "DTSTART" => task.scheduled = Some(date.date()),
+                    "CREATED" => task.created_at = Some(date),
+                    "X-HORME-STARTED" => task.started_at = Some(date),
+                    _ => task.completed_at = Some(date),
+                }
+            }
+            "PRIORITY" => match value.parse::<u8>() {
+                // 0 means the priority is not set
+                Ok(0) => task.priority = None,
+                Ok(priority @ 1..=9) => task.priority = Some((b'A' + priority - 1) as char),
+                _ => warn(property, invalid(property, "a number from 0 to 9")),
+            },
+            "X-HORME-PRIORITY" => match value.chars().next() {
+                Some(priority @ 'A'..='Z') if value.len() == 1 => task.priority = Some(priority),
+                _ => warn(property, invalid(property, "a letter from A to Z")),
+            },
+            "RRULE" => match parse_rule(value) {
+                Some(recurrence) => task.recurrence = Some(recurrence),
+                None => warn(
+                    property,
+                    format!(
+                        "recurrence rule \"{}\" is not supported and is ignored",
+                        value
+                    ),
+                ),
+            },
+            "X-HORME-SECTION" if !value.is_empty() => task.section = Some(unescape(value)),
+            "CATEGORIES" => task.tags.extend(
+                split_list(value)
+                    .iter()
+                    .map(|tag| unescape(tag))
+                    .filter(|tag| !tag.is_empty()),
+            ),
+            _ => {}
+        }
+    }
+}
+
+// The time zone a local time is given in with a TZID parameter, the TZID
+// itself when it is not a time zone of the tz database
+fn time_zone(property: &Property) -> Option<Result<Tz, &str>> {
+    // UTC times and dates without a time don't depend on the time zone
+    if property.value.ends_with('Z') || !property.value.contains('T') {
+        return None;
+    }
+    let (_, tzid) = property.params.iter().find(|(key, _)| key == "TZID")?;
+    // Some programs prefix the TZID with a slash
+    let name = tzid.trim_start_matches('/');
+    Some(name.parse::<Tz>().map_err(|_| tzid.as_str()))
+}
+
+// The RRULE value of a recurrence, `FREQ=WEEKLY;INTERVAL=2` or
+// `FREQ=WEEKLY;BYDAY=MO,TH`
+fn format_rule(recurrence: &Recurrence) -> String {
+    match recurrence {
+        Recurrence::Every(count, unit) => {
+            let frequency = match unit {
+                Unit::Day => "DAILY",
+                Unit::Week => "WEEKLY",
+                Unit::Month => "MONTHLY",
+                Unit::Year => "YEARLY",
+            };
+            match count {
+                1 => format!("FREQ={}", frequency),
+                count => format!("FREQ={};INTERVAL={}", frequency, count),
+            }
+        }
+        Recurrence::On(days) => {
+            let days: Vec<&str> = days
+                .iter()
+                .filter_map(|day| WEEKDAYS.iter().find(|(weekday, _)| weekday == day))
+                .map(|(_, code)| *code)
+                .collect();
+            format!("FREQ=WEEKLY;BYDAY={}", days.join(","))
+        }
+    }
+}
+
+// Read the rules a recurrence can be written as, the others (a count, an
+// end date, days of the month...) have no recurrence to be read as
+fn parse_rule(value: &str) -> Option<Recurrence> {
+    let mut unit = None;
+    let mut count = 1;
+    let mut days = vec![];
+    for part in value.split(';') {
+        let (key, value) = part.split_once('=')?;
+        match key.to_uppercase().as_str() {
+            "FREQ" => {
+                unit = Some(match value.to_uppercase().as_str() {
+                    "DAILY" => Unit::Day,
+                    "WEEKLY" => Unit::Week,
+                    "MONTHLY" => Unit::Month,
+                    "YEARLY" => Unit::Year,
+                    _ => return None,
+                })
+            }
+            "INTERVAL" => count = value.parse().ok().filter(|count| *count > 0)?,
+            "BYDAY" => {
+                for code in value.split(',') {
+                    let (day, _) = WEEKDAYS
+                        .iter()
+                        .find(|(_, name)| name.eq_ignore_ascii_case(code))?;
+                    days.push(*day);
+                }
+            }
+            // Only changes the days of rules with both an interval and days,
+            // which are not read
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    match unit? {
+        Unit::Week if !days.is_empty() && count == 1 => Some(Recurrence::On(days)),
+        _ if !days.is_empty() => None,
+        unit => Some(Recurrence::Every(count, unit)),
+    }
+}
+
+// A UID for a task that has none, the same each time the task is written so
+// that the programs the file is read by don't take it for a new task
+fn fallback_uid(task: &Task, used: &mut HashMap<u64, usize>) -> String {
+    // FNV-1a, unlike the hasher of the standard library it doesn't change
+    // from one version of Rust to the next
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let created = task.created_at.map(|date| date.to_string());
+    for byte in task.name.bytes().chain(created.unwrap_or_default().bytes()) {
+        hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+    }
+    // Tasks with the same name created at the same time are told apart by
+    // their order
+    let count = used.entry(hash).or_insert(0);
+    *count += 1;
+    match *count {
+        1 => format!("{:016x}@horme", hash),
+        count => format!("{:016x}-{}@horme", hash, count),
+    }
+}
+
+fn parent_uid(component: &Component) -> Option<&str> {
+    component
+        .properties
+        .iter()
+        .filter(|property| property.name == "RELATED-TO")
+        .find(|property| {
+            // PARENT is the default relationship type
+            property
+                .params
+                .iter()
+                .all(|(key, value)| key != "RELTYPE" || value.eq_ignore_ascii_case("PARENT"))
+        })
+        .map(|property| property.value.as_str())
+}
+
+// A DATE or DATE-TIME value. UTC times are converted to local time, others
+// are read as local time.
+pub fn parse_date_time(value: &str) -> Option<NaiveDateTime> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let date = NaiveDateTime::parse_from_str(utc, DATE_TIME_FORMAT).ok()?;
+        return Some(
+            Utc.from_utc_datetime(&date)
+                .with_timezone(&Local)
+                .naive_local(),
+        );
+    }
+    NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, DATE_FORMAT)
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+}
+
+fn format_date_time(date: NaiveDateTime) -> String {
+    date.format(DATE_TIME_FORMAT).to_string()
+}
+
+// A local time as a UTC time, which CREATED, COMPLETED and DTSTAMP must be
+fn format_utc(date: NaiveDateTime) -> String {
+    match Local.from_local_datetime(&date).earliest() {
+        Some(date) => format!("{}Z", date.with_timezone(&Utc).format(DATE_TIME_FORMAT)),
+        // A time skipped when the clocks went forward
+        None => format_date_time(date),
+    }
+}
+
+// Split a list of values on the commas that are not escaped
+fn split_list(value: &str) -> Vec<&str> {
+    let mut values = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                values.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    values.push(&value[start..]);
+    values
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+// Write a content line, folding it so that no line is longer than
+// MAX_LINE_LENGTH bytes, without splitting a character
+fn fold_line(line: &str, data: &mut String) {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            data.push_str("\r\n ");
+            length = 1;
+        }
+        data.push(c);
+        length += c.len_utf8();
+    }
+    data.push_str("\r\n");
+}
@@ -0,0 +1,51 @@
+use color_eyre::{eyre::bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::task::{reserve_id, Task, MAX_FILE_ID};
+
+// Version of the JSON format written by horme, bumped on incompatible changes
+pub const JSON_FORMAT_VERSION: u32 = 1;
+
+// A task list as a JSON document: `{ "version": 1, "tasks": [...] }`
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonDocument {
+    version: u32,
+    tasks: Vec<Task>,
+}
+
+// Read the tasks of a JSON document
+pub fn parse_json_document(contents: &str) -> Result<Vec<Task>> {
+    let document: JsonDocument = serde_json::from_str(contents)?;
+    if document.version > JSON_FORMAT_VERSION {
+        bail!(
+            "format version {} is newer than the supported version {}",
+            document.version,
+            JSON_FORMAT_VERSION
+        );
+    }
+    for (i, task) in document.tasks.iter().enumerate() {
+        if document.tasks[..i].iter().any(|other| other.id == task.id) {
+            bail!("task id {} is used more than once", task.id);
+        }
+        if task.id > MAX_FILE_ID {
+            bail!(
+                "task id {} is too large, ids go up to {}",
+                task.id,
+                MAX_FILE_ID
+            );
+        }
+        reserve_id(task.id);
+    }
+    Ok(document.tasks)
+}
+
+// Write the tasks as a JSON document
+pub fn render_json_document(tasks: &[Task]) -> Result<String> {
+    let document = JsonDocument {
+        version: JSON_FORMAT_VERSION,
+        tasks: tasks.to_vec(),
+    };
+    let mut data = serde_json::to_string_pretty(&document)?;
+    data.push('\n');
+    Ok(data)
+}
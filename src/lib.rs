@@ -1,17 +1,66 @@
 mod app;
+mod archive;
+mod bulk;
+mod checklist;
+mod completions;
+mod config;
+mod convert;
+mod dashboard;
+mod editor;
+mod events;
+mod html;
+mod ical;
+mod json;
+mod lock;
+mod markdown;
+mod merge;
+mod org;
+mod output;
+mod query;
+mod quickadd;
+mod recurrence;
 mod run;
+mod shell;
+mod sqlite;
+mod stats;
+mod storage;
+mod tab;
 mod task;
+mod taskwarrior;
+mod todoist;
+mod todotxt;
+mod trello;
 mod tui;
 mod user_interactions;
 mod utils;
+mod view;
+mod watch;
 
 pub use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
 pub use ratatui::prelude::*;
 
 pub use app::App;
+pub use archive::{archive_path, load_archive};
+pub use completions::{completion_script, SHELLS};
+pub use config::Config;
+pub use convert::{import_tasks, summarize, Format};
+pub use editor::ChangeSet;
+pub use html::Theme;
+pub use markdown::{
+    parse_todo_list, parse_todo_list_checked, render_todo_list, Diagnostic, Severity,
+};
+pub use merge::merge_todo_lists;
+pub use output::{print_tasks, OutputFormat};
+pub use query::{Query, QueryError};
 pub use run::run;
+pub use shell::run_shell;
+pub use stats::Stats;
+pub use storage::{diff_todo_lists, storage_for, Change, Storage};
+pub use tab::{FileChange, Tab};
 pub use task::Status;
 pub use task::Task;
+pub use tui::{init_error_hooks, init_terminal, restore_terminal};
+pub use view::{SavedView, SmartView, View};
 
 // ---------------------------------- TESTS ----------------------------------
 #[cfg(test)]
@@ -99,4 +148,1163 @@ mod tests {
             &mut todo_list,
         );
     }
+
+    #[test]
+    fn markdown_round_trip() {
+        let contents = "## Todo\n- task\n    > description\n    * [ ] subtask\n\n## Doing\n\n## Done\n- done\n    * [x] finished\n\n";
+        let todo_list = parse_todo_list(contents);
+        assert_eq!(todo_list.len(), 2);
+        assert_eq!(todo_list[0].description, "description");
+        assert_eq!(todo_list[1].status, Status::Done);
+        assert!(todo_list[1].subtasks[0].status);
+        let sections = ["## Todo", "## Doing", "## Done"].map(String::from);
+        assert_eq!(render_todo_list(&todo_list, &sections), contents);
+    }
+
+    #[test]
+    fn merge_takes_changes_from_both_sides() {
+        let base = parse_todo_list("## Todo\n- one\n- two\n- three\n");
+        let local = parse_todo_list("## Todo\n- one\n    > edited locally\n- two\n- four\n");
+        let remote = parse_todo_list("## Todo\n- one\n- three\n## Done\n- two\n- five\n");
+        let merged = merge_todo_lists(&base, &local, &remote);
+        let names: Vec<&str> = merged.tasks.iter().map(|task| task.name.as_str()).collect();
+        assert_eq!(names, ["one", "two", "four", "five"]);
+        assert_eq!(merged.tasks[0].description, "edited locally");
+        assert_eq!(merged.tasks[1].status, Status::Done);
+        assert!(merged.conflicts.is_empty());
+
+        // Tasks of the same name are told apart
+        let base = parse_todo_list("## Todo\n- review\n- review\n- review\n");
+        let local = parse_todo_list("## Todo\n- review\n    > local\n- review\n- review\n");
+        let remote = parse_todo_list("## Todo\n- review\n- review\n## Done\n- review\n");
+        let merged = merge_todo_lists(&base, &local, &remote);
+        let statuses: Vec<Status> = merged.tasks.iter().map(|task| task.status).collect();
+        assert_eq!(statuses, [Status::Todo, Status::Todo, Status::Done]);
+        assert_eq!(merged.tasks[0].description, "local");
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn merge_reports_conflicts() {
+        let base = parse_todo_list("## Todo\n- one\n");
+        let local = parse_todo_list("## Todo\n- one\n    > local\n");
+        let remote = parse_todo_list("## Todo\n- one\n    > remote\n");
+        let merged = merge_todo_lists(&base, &local, &remote);
+        assert_eq!(merged.tasks[0].description, "local");
+        assert_eq!(merged.conflicts, ["one"]);
+    }
+
+    #[test]
+    fn lock_replaces_stale_lock() {
+        use lock::{FileLock, LockStatus};
+        let dir = std::env::temp_dir().join(format!("horme-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("todo.md");
+        let lock_path = dir.join(".todo.md.lock");
+
+        // Left behind by an instance that stopped long ago
+        std::fs::write(&lock_path, "4194305\n0\n").unwrap();
+        assert!(FileLock::owner(&file_path).is_none());
+        let lock = FileLock::acquire(&file_path).unwrap();
+        assert!(matches!(lock, LockStatus::Acquired(_)));
+        assert!(lock_path.exists());
+
+        // Another lock of this process is not ours, and taking over a lock
+        // keeps the previous owner from removing it
+        assert!(matches!(
+            FileLock::acquire(&file_path).unwrap(),
+            LockStatus::HeldBy(_)
+        ));
+        let contents = std::fs::read_to_string(&lock_path).unwrap();
+        let heartbeat = contents.lines().nth(1).unwrap();
+        std::fs::write(&lock_path, contents.replacen(heartbeat, "0", 1)).unwrap();
+        let LockStatus::Acquired(other) = FileLock::acquire(&file_path).unwrap() else {
+            panic!("the stale lock wasn't replaced");
+        };
+        let LockStatus::Acquired(lock) = lock else {
+            unreachable!()
+        };
+        assert!(!lock.is_held() && other.is_held());
+        drop(lock);
+        assert!(other.is_held());
+        drop(other);
+        assert!(!lock_path.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_reports_diagnostics() {
+        let contents = "    > early\n## Todo\n- task\n  > two spaces\n  - nested\n";
+        let (todo_list, diagnostics) = parse_todo_list_checked(contents);
+        assert_eq!(todo_list.len(), 1);
+        assert_eq!(todo_list[0].description, "two spaces");
+        let positions: Vec<(usize, usize, Severity)> = diagnostics
+            .iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.column, diagnostic.severity))
+            .collect();
+        assert_eq!(
+            positions,
+            [
+                (1, 5, Severity::Error),
+                (4, 3, Severity::Warning),
+                (5, 3, Severity::Error)
+            ]
+        );
+    }
+
+    #[test]
+    fn move_task_between_tabs() {
+        let dir = std::env::temp_dir().join(format!("horme-tabs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let personal = parse_todo_list("## Todo\n- first\n- second\n");
+        let mut app = App::new(vec![
+            Tab::new(personal, &dir.join("personal.md")),
+            Tab::new(vec![], &dir.join("team.md")),
+        ]);
+
+        for code in [KeyCode::Down, KeyCode::Char('m'), KeyCode::Enter] {
+            app.handle_key_event(KeyEvent::from(code)).unwrap();
+        }
+        assert_eq!(app.tabs[0].todo_list.items.len(), 1);
+        assert_eq!(app.tabs[1].todo_list.items[0].name, "first");
+
+        // Switch tabs and render them
+        let mut terminal = Terminal::new(backend::TestBackend::new(80, 20)).unwrap();
+        app.handle_key_event(KeyEvent::from(KeyCode::Tab)).unwrap();
+        assert_eq!(app.current_tab, 1);
+        terminal
+            .draw(|f| f.render_widget(&mut app, f.size()))
+            .unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archive_old_done_tasks() {
+        let dir = std::env::temp_dir().join(format!("horme-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("todo.md");
+        let todo_list = parse_todo_list(
+            "## Todo\n- open\n## Done\n- old\n    @completed 2020-01-02 10:00\n- recent\n",
+        );
+        let mut tab = Tab::new(todo_list, &file_path);
+
+        tab.date_done_tasks();
+        assert_eq!(tab.archive_done_tasks(Some(30)).unwrap(), 1);
+        let names: Vec<&str> = tab
+            .todo_list
+            .items
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(names, ["open", "recent"]);
+        // The list is saved along with the archive
+        assert!(!tab.has_unsaved_changes());
+        assert!(!std::fs::read_to_string(&file_path)
+            .unwrap()
+            .contains("- old\n"));
+
+        let archive = load_archive(&file_path).unwrap();
+        assert_eq!(archive.len(), 1);
+        assert_eq!(archive[0].status, Status::Done);
+        assert!(std::fs::read_to_string(archive_path(&file_path))
+            .unwrap()
+            .contains("## 2020-01\n- old\n    @completed 2020-01-02 10:00\n"));
+
+        assert_eq!(tab.restore_archived_task(0).unwrap().unwrap().name, "old");
+        assert!(load_archive(&file_path).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn storage_round_trip() {
+        let dir = std::env::temp_dir().join(format!("horme-storage-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let todo_list = parse_todo_list(
+            "## Todo\n- first\n    > with a description\n    * [ ] sub\n    @due 2026-11-02 09:30\n    @scheduled 2026-10-30\n    @every 2 weeks\n    @tags infra\n    @section Later\n## Doing\n- third\n    @every monday, thursday\n    @started 2026-10-01 10:00\n## Done\n- second\n    @created 2026-09-01 00:00\n",
+        );
+
+        // Markdown doesn't keep the ids, so only the content is compared
+        let same = |a: &[Task], b: &[Task]| {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.same_content(b))
+        };
+
+        for file_name in ["todo.md", "todo.json", "todo.db", "todo.ics", "todo.txt"] {
+            let file_path = dir.join(file_name);
+            let mut storage = storage_for(&file_path);
+            storage.save(&todo_list).unwrap();
+            let loaded = storage.load().unwrap().0;
+            assert!(same(&loaded, &todo_list), "{}", file_name);
+
+            // Only the changes are written back
+            let mut changed = loaded.clone();
+            changed.remove(0);
+            changed[0].name = "renamed".to_string();
+            let changes = diff_todo_lists(&loaded, &changed);
+            assert_eq!(changes.len(), 3);
+            storage.save_changes(&changes, &changed).unwrap();
+            let loaded = storage_for(&file_path).load().unwrap().0;
+            assert!(same(&loaded, &changed), "{}", file_name);
+        }
+
+        // Database rows don't depend on task ids, a task merged in with the
+        // id of another one doesn't replace it
+        let mut storage = storage_for(&dir.join("todo.db"));
+        let loaded = storage.load().unwrap().0;
+        let mut merged = loaded.clone();
+        merged.push(Task {
+            name: "merged".to_string(),
+            ..loaded[0].clone()
+        });
+        storage
+            .save_changes(&diff_todo_lists(&loaded, &merged), &merged)
+            .unwrap();
+        let reloaded = storage_for(&dir.join("todo.db")).load().unwrap().0;
+        assert!(same(&reloaded, &merged));
+        assert_ne!(reloaded[0].id, reloaded[1].id);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_export_keeps_every_field() {
+        let todo_list = parse_todo_list(
+            "## Doing\n- release\n    > notes\n    * [x] write\n    @due 2026-11-01\n## Done\n- old\n    @completed 2026-10-01 10:00\n",
+        );
+        let data = Format::Json.render(&todo_list).unwrap();
+        assert!(data.contains("\"version\": 1"));
+        assert!(data.contains("\"due_date\": \"2026-11-01\""));
+        let (imported, _) = Format::Json.parse(&data).unwrap();
+        assert_eq!(imported, todo_list);
+
+        let mut merged = todo_list.clone();
+        assert_eq!(import_tasks(&mut merged, imported), 2);
+        assert_eq!(merged.len(), 4);
+        assert!(merged[2].same_content(&todo_list[0]) && merged[2].id != todo_list[0].id);
+
+        let newer = data.replace("\"version\": 1", "\"version\": 2");
+        assert!(Format::Json.parse(&newer).is_err());
+        // Ids that would leave no room for new tasks are refused
+        let id = format!("\"id\": {},", todo_list[0].id);
+        let too_large = data.replace(&id, "\"id\": 65535,");
+        assert!(Format::Json.parse(&too_large).is_err());
+    }
+
+    #[test]
+    fn todo_txt_round_trip() {
+        let contents = "x 2026-10-02 2026-09-30 Call the plumber +house @phone pri:B\n\
+                        (A) 2026-09-30 Fix the build +horme due:2026-10-20 status:doing\n\
+                        Read url:http://example.com\n";
+        let (todo_list, diagnostics) = Format::TodoTxt.parse(contents).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(todo_list[0].status, Status::Done);
+        assert_eq!(todo_list[0].priority, Some('B'));
+        assert_eq!(todo_list[0].tags, ["house", "@phone"]);
+        assert_eq!(todo_list[1].status, Status::Doing);
+        assert_eq!(todo_list[1].name, "Fix the build");
+        assert_eq!(todo_list[1].due_date.unwrap().to_string(), "2026-10-20");
+        assert_eq!(todo_list[2].name, "Read url:http://example.com");
+        assert_eq!(Format::TodoTxt.render(&todo_list).unwrap(), contents);
+
+        // Descriptions and subtasks are kept as percent-encoded values
+        let todo_list = parse_todo_list(
+            "## Todo\n- Plan\n    > first line, 100%\n    > second line\n    * [x] book the hall\n    * [ ] send invites\n",
+        );
+        let data = Format::TodoTxt.render(&todo_list).unwrap();
+        assert_eq!(data.lines().count(), 1);
+        assert!(data.contains("note:first%20line,%20100%25%0Asecond%20line"));
+        let (imported, _) = Format::TodoTxt.parse(&data).unwrap();
+        assert!(imported[0].same_content(&todo_list[0]));
+
+        // The fields todo.txt has no place for are written as `key:value`
+        let mut task = todo_list[0].clone();
+        task.due_time = chrono::NaiveTime::from_hms_opt(9, 30, 0);
+        task.due_date = chrono::NaiveDate::from_ymd_opt(2026, 11, 2);
+        task.recurrence = Some("every 2 weeks".parse().unwrap());
+        task.section = Some("Next week".to_string());
+        let data = Format::TodoTxt.render(&[task.clone()]).unwrap();
+        assert!(data.contains(" due:2026-11-02T09:30 rec:2w "));
+        assert!(data.contains(" section:Next%20week"));
+        let (imported, _) = Format::TodoTxt.parse(&data).unwrap();
+        assert!(imported[0].same_content(&task));
+
+        let (_, diagnostics) = Format::TodoTxt.parse("Pay due:tomorrow\n+tag\n").unwrap();
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].column, 5);
+    }
+
+    #[test]
+    fn ical_round_trip() {
+        let todo_list = parse_todo_list(
+            "## Doing\n- Ship, then rest\n    > a long description that needs to be folded because it is longer than the limit\n    * [x] build\n    * [ ] deploy\n    @due 2026-11-01\n    @priority B\n    @tags infra ops\n## Done\n- old\n    @completed 2026-10-01 10:00\n",
+        );
+        let data = Format::ICalendar.render(&todo_list).unwrap();
+        assert!(data.contains("SUMMARY:Ship\\, then rest\r\n"));
+        assert!(data.contains("DUE;VALUE=DATE:20261101\r\n"));
+        assert!(data.lines().all(|line| line.len() <= 75));
+
+        let (imported, diagnostics) = Format::ICalendar.parse(&data).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(imported.len(), 2);
+        assert!(imported[0].same_content(&todo_list[0]));
+        assert!(imported[1].same_content(&todo_list[1]));
+
+        // The UID of a task is the same from one export to the next, and kept
+        // when the task is imported, as are the priorities past I
+        let uid = imported[0].uid.clone();
+        assert!(uid.is_some());
+        assert!(Format::ICalendar
+            .render(&todo_list)
+            .unwrap()
+            .contains(&format!("UID:{}\r\n", uid.as_ref().unwrap())));
+        let mut changed = imported.clone();
+        changed[0].priority = Some('J');
+        let data = Format::ICalendar.render(&changed).unwrap();
+        let (reimported, _) = Format::ICalendar.parse(&data).unwrap();
+        assert_eq!(reimported[0].uid, uid);
+        assert_ne!(reimported[1].uid, uid);
+        assert_eq!(reimported[0].priority, Some('J'));
+
+        // The properties of an alarm are not those of its task
+        let data = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nSUMMARY:Call\r\nDESCRIPTION:notes\r\n\
+                    BEGIN:VALARM\r\nACTION:DISPLAY\r\nDESCRIPTION:Reminder\r\nTRIGGER:-PT15M\r\n\
+                    END:VALARM\r\nSTATUS:IN-PROCESS\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+        let (imported, _) = Format::ICalendar.parse(data).unwrap();
+        assert_eq!(imported.len(), 1);
+        assert_eq!(imported[0].description, "notes");
+        assert_eq!(imported[0].status, Status::Doing);
+
+        // Times are written in UTC, and read in the time zone they are given in
+        assert!(Format::ICalendar
+            .render(&todo_list)
+            .unwrap()
+            .lines()
+            .any(|line| line.starts_with("COMPLETED:") && line.ends_with('Z')));
+        let data = "BEGIN:VTODO\r\nSUMMARY:Call\r\nDUE;TZID=America/New_York:20261020T090000\r\nEND:VTODO\r\n";
+        let (imported, diagnostics) = Format::ICalendar.parse(data).unwrap();
+        assert!(diagnostics.is_empty());
+        use chrono::TimeZone;
+        let due = chrono::Utc
+            .with_ymd_and_hms(2026, 10, 20, 13, 0, 0)
+            .unwrap()
+            .with_timezone(&chrono::Local)
+            .naive_local();
+        assert_eq!(imported[0].due_date, Some(due.date()));
+        assert_eq!(imported[0].due_time, Some(due.time()));
+    }
+
+    #[test]
+    fn kanban_board_round_trip() {
+        let dir = std::env::temp_dir().join(format!("horme-kanban-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("board.md");
+        let contents = "---\n\nkanban-plugin: basic\n\n---\n\n\
+                        ## Backlog\n\n- [ ] Write the docs\n- [x] Early bird\n\n\
+                        ## In Progress\n\n- [ ] Fix the build\n    with notes\n    - [x] find the bug\n\n\
+                        ## Done\n\n**Complete**\n- [x] Release\n\n\n\n\n\
+                        %% kanban:settings\n```\n{\"kanban-plugin\":\"basic\"}\n```\n%%\n";
+        std::fs::write(&file_path, contents).unwrap();
+
+        let mut storage = storage_for(&file_path);
+        let (mut todo_list, diagnostics) = storage.load().unwrap();
+        assert!(diagnostics.is_empty());
+        let statuses: Vec<Status> = todo_list.iter().map(|task| task.status).collect();
+        assert_eq!(
+            statuses,
+            [Status::Todo, Status::Done, Status::Doing, Status::Done]
+        );
+        assert_eq!(todo_list[1].section.as_deref(), Some("Backlog"));
+        assert_eq!(todo_list[2].description, "with notes");
+        storage.save(&todo_list).unwrap();
+        assert_eq!(std::fs::read_to_string(&file_path).unwrap(), contents);
+
+        // Tasks follow their status, unless they were kept in their own section
+        todo_list[2].status = Status::Done;
+        storage.save(&todo_list).unwrap();
+        let saved = std::fs::read_to_string(&file_path).unwrap();
+        assert!(saved.contains("**Complete**\n- [x] Fix the build\n"));
+
+        // The other fields are written after the name of the item
+        let task = &mut todo_list[0];
+        task.due_date = chrono::NaiveDate::from_ymd_opt(2026, 11, 1);
+        task.due_time = chrono::NaiveTime::from_hms_opt(9, 0, 0);
+        task.tags = vec!["docs".to_string()];
+        task.scheduled = chrono::NaiveDate::from_ymd_opt(2026, 10, 30);
+        task.priority = Some('B');
+        task.recurrence = Some("every month".parse().unwrap());
+        task.created_at = task.due_date.unwrap().and_hms_opt(8, 30, 0);
+        storage.save(&todo_list).unwrap();
+        let saved = std::fs::read_to_string(&file_path).unwrap();
+        assert!(saved.contains("- [ ] Write the docs @{2026-11-01} @@{09:00} #docs [scheduled:: 2026-10-30] [priority:: B] [repeat:: every month] [created:: 2026-11-01 08:30]\n"));
+        let (loaded, diagnostics) = storage_for(&file_path).load().unwrap();
+        assert!(diagnostics.is_empty());
+        assert!(loaded[0].same_content(&todo_list[0]));
+
+        // A status the section can't show is written along with the item
+        let mut moved = loaded.clone();
+        moved[0].set_status(Status::Doing);
+        storage.save(&moved).unwrap();
+        let (loaded, _) = storage_for(&file_path).load().unwrap();
+        assert_eq!(loaded[0].status, Status::Doing);
+        assert_eq!(loaded[0].section.as_deref(), Some("Backlog"));
+        assert!(loaded[0].same_content(&moved[0]));
+
+        // `#12` is an issue in a GFM task list, not a tag
+        let file_path = dir.join("tasks.md");
+        std::fs::write(
+            &file_path,
+            "## Todo\n\n- [ ] Fix issue #12 [due:: 2026-11-01] [tags:: bug ui]\n",
+        )
+        .unwrap();
+        let (loaded, _) = storage_for(&file_path).load().unwrap();
+        assert_eq!(loaded[0].name, "Fix issue #12");
+        assert_eq!(loaded[0].tags, ["bug", "ui"]);
+        assert_eq!(loaded[0].due_date.unwrap().to_string(), "2026-11-01");
+
+        // A checklist without headings
+        let file_path = dir.join("note.md");
+        std::fs::write(&file_path, "- [ ] Buy milk\n- [x] Call mom\n").unwrap();
+        let mut storage = storage_for(&file_path);
+        let (loaded, diagnostics) = storage.load().unwrap();
+        assert!(diagnostics.is_empty());
+        let statuses: Vec<Status> = loaded.iter().map(|task| task.status).collect();
+        assert_eq!(statuses, [Status::Todo, Status::Done]);
+        assert_eq!(loaded[0].section, None);
+        storage.save(&loaded).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&file_path).unwrap(),
+            "- [ ] Buy milk\n- [x] Call mom\n"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn org_round_trip() {
+        let contents = "#+TODO: TODO NEXT | DONE KILLED\n\
+                        * Work\n\
+                        ** NEXT [#A] Deploy nginx :infra:ops:\n\
+                        \x20  DEADLINE: <2026-11-01 Sun>\n\
+                        \x20  :PROPERTIES:\n\
+                        \x20  :ID: 42\n\
+                        \x20  :END:\n\
+                        \x20  On the new servers\n\
+                        *** DONE Write the config\n\
+                        \x20  - [ ] Reload\n\
+                        * KILLED Old plan\n\
+                        \x20 CLOSED: [2026-10-01 Wed 10:00] SCHEDULED: <2026-09-30 Tue>\n";
+        let (todo_list, diagnostics) = Format::Org.parse(contents).unwrap();
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(todo_list.len(), 2);
+        let task = &todo_list[0];
+        assert_eq!(task.name, "Deploy nginx");
+        assert_eq!(task.status, Status::Doing);
+        assert_eq!(task.priority, Some('A'));
+        assert_eq!(task.tags, ["infra", "ops"]);
+        assert_eq!(task.section.as_deref(), Some("Work"));
+        assert_eq!(task.description, "On the new servers");
+        assert_eq!(task.due_date.unwrap().to_string(), "2026-11-01");
+        assert_eq!(task.subtasks.len(), 1);
+        assert!(task.subtasks[0].status);
+        assert_eq!(todo_list[1].status, Status::Done);
+        assert_eq!(todo_list[1].due_date.unwrap().to_string(), "2026-09-30");
+
+        let data = Format::Org.render(&todo_list).unwrap();
+        assert!(data.contains("\n* Work\n** DOING [#A] Deploy nginx :infra:ops:\n"));
+        let (again, _) = Format::Org.parse(&data).unwrap();
+        // Tasks without a section are written first
+        assert!(again[0].same_content(&todo_list[1]));
+        assert!(again[1].same_content(&todo_list[0]));
+
+        // Every field is written, and descriptions can't turn into headlines
+        let mut task = todo_list[0].clone();
+        task.due_time = chrono::NaiveTime::from_hms_opt(9, 0, 0);
+        task.scheduled = chrono::NaiveDate::from_ymd_opt(2026, 10, 25);
+        task.recurrence = Some("every 2 weeks".parse().unwrap());
+        task.created_at = task.scheduled.unwrap().and_hms_opt(8, 30, 0);
+        task.started_at = task.created_at;
+        task.description =
+            "* not a headline\n- [ ] not a subtask\nDEADLINE: <2026-12-01>".to_string();
+        let data = Format::Org.render(&[task.clone()]).unwrap();
+        assert!(data.contains("DEADLINE: <2026-11-01 Sun 09:00 +2w> SCHEDULED: <2026-10-25 Sun>\n"));
+        let (again, diagnostics) = Format::Org.parse(&data).unwrap();
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+        assert!(again[0].same_content(&task));
+    }
+
+    #[test]
+    fn import_from_other_task_managers() {
+        let taskwarrior = r#"[
+            {"uuid":"a","description":"Deploy nginx","status":"pending","project":"infra","tags":["ops"],"priority":"H","due":"20261101T090000Z","start":"20261018T080000Z","entry":"20261001T100000Z"},
+            {"uuid":"b","description":"Old","status":"deleted","entry":"20261001T100000Z"},
+            {"uuid":"c","description":"Renew","status":"completed","end":"20261002T100000Z","entry":"20261001T100000Z","annotations":[{"entry":"20261001T100000Z","description":"done early"}]}
+        ]"#;
+        let (tasks, diagnostics) = Format::Taskwarrior.parse(taskwarrior).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].status, Status::Doing);
+        assert_eq!(tasks[0].section.as_deref(), Some("infra"));
+        assert_eq!(tasks[0].priority, Some('A'));
+        assert_eq!(tasks[1].status, Status::Done);
+        assert_eq!(tasks[1].description, "done early");
+
+        let todoist = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE\n\
+                       section,Servers,,,,,,,,\n\
+                       task,\"Deploy nginx, again @infra\",On the new host,4,1,me,,2026-11-01,en,Europe/Rome\n\
+                       task,Write config,,1,2,me,,,en,Europe/Rome\n\
+                       task,Call,,1,1,me,,every monday,en,Europe/Rome\n";
+        let (tasks, diagnostics) = Format::Todoist.parse(todoist).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "Deploy nginx, again");
+        assert_eq!(tasks[0].tags, ["infra"]);
+        assert_eq!(tasks[0].section.as_deref(), Some("Servers"));
+        assert_eq!(tasks[0].subtasks[0].name, "Write config");
+        assert_eq!(tasks[0].due_date.unwrap().to_string(), "2026-11-01");
+
+        let trello = r#"{
+            "name": "Board",
+            "lists": [{"id":"l1","name":"Backlog"},{"id":"l2","name":"Doing"},{"id":"l3","name":"Old","closed":true}],
+            "cards": [
+                {"id":"c1","name":"Deploy","desc":"nginx","idList":"l2","labels":[{"name":"infra"},{"name":"","color":"red"}]},
+                {"id":"c2","name":"Plan","idList":"l1","dueComplete":true},
+                {"id":"c3","name":"Gone","idList":"l3"}
+            ],
+            "checklists": [{"id":"k1","idCard":"c1","checkItems":[{"name":"second","state":"incomplete","pos":2},{"name":"first","state":"complete","pos":1}]}]
+        }"#;
+        let (tasks, diagnostics) = Format::Trello.parse(trello).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].status, Status::Doing);
+        assert_eq!(tasks[0].tags, ["infra", "red"]);
+        assert_eq!(tasks[0].subtasks[0].name, "first");
+        assert!(tasks[0].subtasks[0].status);
+        assert_eq!(tasks[1].status, Status::Done);
+        assert_eq!(tasks[1].section.as_deref(), Some("Backlog"));
+        assert_eq!(
+            summarize(&tasks),
+            [
+                "2 tasks: 0 todo, 1 doing, 1 done",
+                "2 subtasks",
+                "sections: Backlog (1)",
+                "tags: infra (1), red (1)"
+            ]
+        );
+    }
+
+    #[test]
+    fn html_report() {
+        let todo_list = parse_todo_list(
+            "## Doing\n- Ship <it>\n    > **bold** <script>alert(1)</script>\n    > [click](javascript:alert(document.cookie)) [docs](https://example.com) ![x](JavaScript:alert(1))\n    * [x] build\n    * [ ] deploy\n    @section Release\n## Done\n- old\n",
+        );
+        let data = Format::Html(Theme::Light).render(&todo_list).unwrap();
+        assert!(data.starts_with("<!DOCTYPE html>"));
+        assert!(data.contains("1 of 2 tasks done"));
+        assert!(data.contains("<h2>Release <span class=\"summary\">1</span></h2>"));
+        assert!(data.contains("<span class=\"name\">Ship &lt;it&gt;</span>"));
+        assert!(data.contains("<strong>bold</strong>"));
+        assert!(!data.contains("<script>"));
+        assert!(!data.to_lowercase().contains("javascript:"));
+        assert!(data.contains("<a href=\"#\">click</a>"));
+        assert!(data.contains("<a href=\"https://example.com\">docs</a>"));
+        assert!(data.contains("1/2 subtasks"));
+        assert!(Format::Html(Theme::Tui).parse(&data).is_err());
+    }
+
+    #[test]
+    fn quick_add() {
+        use crate::quickadd::parse_quick_add;
+        use chrono::NaiveDate;
+
+        // A Monday
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let quick_add =
+            parse_quick_add("Deploy nginx tomorrow 9am #infra !high every monday", today);
+        assert_eq!(quick_add.name, "Deploy nginx");
+        assert_eq!(quick_add.due_date.unwrap().to_string(), "2026-10-20");
+        assert_eq!(quick_add.due_time.unwrap().to_string(), "09:00:00");
+        assert_eq!(quick_add.tags, ["infra"]);
+        assert_eq!(quick_add.priority, Some('A'));
+        assert_eq!(quick_add.recurrence.unwrap().to_string(), "every monday");
+
+        let quick_add = parse_quick_add("Read \"next friday\" on nov 3 at 2:30pm", today);
+        assert_eq!(quick_add.name, "Read next friday");
+        assert_eq!(quick_add.due_date.unwrap().to_string(), "2026-11-03");
+        assert_eq!(quick_add.due_time.unwrap().to_string(), "14:30:00");
+        let quick_add = parse_quick_add("Water plants every 2 weeks", today);
+        assert_eq!(quick_add.due_date, Some(today));
+
+        // Short weekdays need a word before them, whole ones don't
+        for name in ["Fix the sat solver", "Wed the mon", "Thu sun"] {
+            let quick_add = parse_quick_add(name, today);
+            assert_eq!((quick_add.name.as_str(), quick_add.due_date), (name, None));
+        }
+        let quick_add = parse_quick_add("Call Ann on sat", today);
+        assert_eq!(quick_add.name, "Call Ann");
+        assert_eq!(quick_add.due_date.unwrap().to_string(), "2026-10-24");
+        let quick_add = parse_quick_add("Call Ann saturday", today);
+        assert_eq!(quick_add.due_date.unwrap().to_string(), "2026-10-24");
+
+        // Done recurring tasks come back, written with their recurrence
+        let mut task =
+            parse_quick_add("Standup every weekday 9:30", today).into_task(String::new());
+        task.add_subtask("notes".to_string(), true);
+        task.update_status();
+        task.update_status();
+        let recurrence = task.recurrence.clone();
+        let next = task.next_occurrence(today).unwrap();
+        assert_eq!(next.status, Status::Todo);
+        assert_eq!(next.due_date.unwrap().to_string(), "2026-10-20");
+        assert!(!next.subtasks[0].status);
+        assert!(task.recurrence.is_none());
+        let data = render_todo_list(&[next], &["## Todo".to_string()]);
+        assert!(data.contains("    @due 2026-10-20 09:30\n    @every weekday\n"));
+        assert_eq!(parse_todo_list(&data)[0].recurrence, recurrence);
+
+        // Reopening a done occurrence and completing it again adds no other one
+        let mut todo_list =
+            vec![parse_quick_add("Water plants every monday", today).into_task(String::new())];
+        let id = [todo_list[0].id];
+        for status in [Status::Done, Status::Todo, Status::Done] {
+            bulk::change_status(&mut todo_list, &id, Some(status), today);
+        }
+        assert_eq!(todo_list.len(), 2);
+        assert!(todo_list[0].recurrence.is_none());
+        assert!(todo_list[1].recurrence.is_some());
+    }
+
+    #[test]
+    fn smart_and_saved_views() {
+        use chrono::NaiveDate;
+
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let todo_list = parse_todo_list(
+            "## Todo\n- later\n    @due 2026-10-23\n- late\n    @due 2026-10-12\n    @tags infra\n- now\n    @due 2026-10-19 08:00\n- someday\n- far\n    @due 2026-12-01\n## Done\n- old\n    @due 2026-10-01\n",
+        );
+        let names = |view: &View| -> Vec<&str> {
+            view.select(&todo_list, today)
+                .into_iter()
+                .map(|i| todo_list[i].name.as_str())
+                .collect()
+        };
+        assert_eq!(names(&View::Smart(SmartView::All)).len(), 6);
+        assert_eq!(names(&View::Smart(SmartView::Today)), ["late", "now"]);
+        assert_eq!(names(&View::Smart(SmartView::Upcoming)), ["later"]);
+        assert_eq!(names(&View::Smart(SmartView::Overdue)), ["late"]);
+        assert_eq!(names(&View::Smart(SmartView::Someday)), ["someday"]);
+
+        let config: Config =
+            toml::from_str("[[views]]\nname = \"Infra\"\nquery = \"is:today tag:infra\"\n")
+                .unwrap();
+        let view = View::from_name("infra", &config.views).unwrap();
+        assert_eq!(names(&view), ["late"]);
+        assert!(
+            toml::from_str::<Config>("[[views]]\nname = \"x\"\nquery = \"is:never\"\n").is_err()
+        );
+    }
+
+    #[test]
+    fn query_language() {
+        use chrono::NaiveDate;
+
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        let todo_list = parse_todo_list(
+            "## Todo\n- Deploy nginx\n    @due 2026-10-22\n    @priority A\n    @tags infra\n- Write the release notes\n    @tags docs someday\n## Doing\n- Rotate keys\n    @due 2026-11-30\n    @tags infra\n## Done\n- old\n    @completed 2026-10-18 10:00\n",
+        );
+        let names = |query: &str| -> Vec<&str> {
+            let query = Query::parse(query).unwrap();
+            todo_list
+                .iter()
+                .filter(|task| query.matches(task, today))
+                .map(|task| task.name.as_str())
+                .collect()
+        };
+        assert_eq!(
+            names(""),
+            [
+                "Deploy nginx",
+                "Write the release notes",
+                "Rotate keys",
+                "old"
+            ]
+        );
+        assert_eq!(
+            names("status:todo tag:infra due<7d -tag:someday"),
+            ["Deploy nginx"]
+        );
+        assert_eq!(names("\"release NOTES\""), ["Write the release notes"]);
+        assert_eq!(
+            names("status:todo,doing -tag:infra"),
+            ["Write the release notes"]
+        );
+        assert_eq!(
+            names("due:none priority:none"),
+            ["Write the release notes", "old"]
+        );
+        assert_eq!(names("priority:high due>=today"), ["Deploy nginx"]);
+        assert_eq!(names("done:yesterday"), ["old"]);
+        assert_eq!(names("is:someday"), ["Write the release notes"]);
+
+        let error = Query::parse("status:todo due<soon").unwrap_err();
+        assert_eq!(error.column, 17);
+        assert!(error.message.starts_with("invalid date \"soon\""));
+        let error = Query::parse("due<2000000000000000000w").unwrap_err();
+        assert!(error.message.contains("too far away"));
+        assert!(Query::parse("due>-9999999999y").is_err());
+        assert_eq!(Query::parse("tag:a state:x").unwrap_err().column, 7);
+        assert_eq!(Query::parse("status:todo,nope").unwrap_err().column, 13);
+        assert_eq!(Query::parse("tag<2").unwrap_err().column, 4);
+        assert_eq!(Query::parse("\"open").unwrap_err().column, 1);
+    }
+
+    #[test]
+    fn output_formats() {
+        let mut todo_list = parse_todo_list(
+            "## Todo\n- Deploy nginx to the production cluster\n    * [x] build\n    * [ ] push\n    @due 2026-10-22\n    @tags infra\n## Done\n- Tabs\tand, \"quotes\"\n",
+        );
+        // The width of the id column depends on the ids, which depend on the
+        // tests run before
+        for (i, task) in todo_list.iter_mut().enumerate() {
+            task.id = i as u16 + 1;
+        }
+        let table = OutputFormat::Table.render(&todo_list, None).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("ID  STATUS  DUE         TAGS   SUBTASKS  NAME"));
+        assert!(lines[1]
+            .ends_with("2026-10-22  infra  1/2       Deploy nginx to the production cluster"));
+
+        // Long names are cut to fit the terminal
+        let table = OutputFormat::Table.render(&todo_list, Some(60)).unwrap();
+        assert!(table.lines().all(|line| line.chars().count() <= 60));
+        assert!(table.contains("Deploy nginx to th…"));
+
+        let plain = OutputFormat::Plain.render(&todo_list, None).unwrap();
+        assert_eq!(plain.lines().nth(1).unwrap().split('\t').count(), 7);
+        let csv = OutputFormat::Csv.render(&todo_list, None).unwrap();
+        assert!(csv.starts_with("id,status,name,description,"));
+        assert!(csv.contains(",done,\"Tabs\tand, \"\"quotes\"\"\","));
+        let json = OutputFormat::Json.render(&todo_list, None).unwrap();
+        assert_eq!(Format::Json.parse(&json).unwrap().0, todo_list);
+        assert!(OutputFormat::from_name("yaml").is_none());
+    }
+
+    #[test]
+    fn shell_words() {
+        use shell::{complete_word, split_args};
+
+        assert_eq!(
+            split_args(r#"add "Deploy \"nginx\" #infra" 'C:\tmp' a\ b"#).unwrap(),
+            ["add", "Deploy \"nginx\" #infra", "C:\\tmp", "a b"]
+        );
+        assert_eq!(split_args("edit 3 -n ''").unwrap(), ["edit", "3", "-n", ""]);
+        assert!(split_args("add \"open").is_err());
+
+        let todo_list = parse_todo_list(
+            "## Todo\n- Deploy nginx\n    @tags infra\n- Write notes\n    @tags docs\n",
+        );
+        let words = |line: &str| {
+            let mut before = split_args(line).unwrap();
+            let word = if line.ends_with(' ') {
+                String::new()
+            } else {
+                before.pop().unwrap()
+            };
+            complete_word(&before, &word, &todo_list)
+        };
+        let replacements =
+            |line: &str| -> Vec<String> { words(line).into_iter().map(|(word, _)| word).collect() };
+        assert_eq!(replacements("comp"), ["complete"]);
+        // Ids are shown with the name of their task
+        let id = todo_list[0].id.to_string();
+        assert_eq!(
+            words(&format!("delete {}", id)),
+            [(id.clone(), format!("{}  Deploy nginx", id))]
+        );
+        assert_eq!(replacements("edit 1 --d"), ["--description"]);
+        assert_eq!(replacements("add Ship it #i"), ["#infra"]);
+        assert_eq!(replacements("ls -tag:d"), ["-tag:docs"]);
+        assert_eq!(replacements("ls status:d"), ["status:doing", "status:done"]);
+        assert_eq!(replacements("ls --format j"), ["json"]);
+    }
+
+    #[test]
+    fn completion_scripts() {
+        let placeholder = regex::Regex::new("@[A-Z_]+@").unwrap();
+        for shell in SHELLS {
+            let script = completion_script(shell).unwrap();
+            assert!(!placeholder.is_match(&script), "{} script", shell);
+            assert!(script.contains("horme __complete ids"));
+            assert!(script.contains("complete") && script.contains("upcoming"));
+        }
+        assert!(completion_script("zsh")
+            .unwrap()
+            .contains("'delete:Delete tasks'"));
+        assert!(completion_script("powershell").is_none());
+    }
+
+    #[test]
+    fn bulk_operations() {
+        let mut todo_list = parse_todo_list(
+            "## Todo\n- one\n    @tags old\n- two\n    @tags old\n- three\n- four\n",
+        );
+        let ids: Vec<String> = todo_list.iter().map(|task| task.id.to_string()).collect();
+        let range = format!("{}-{}", ids[0], ids[1]);
+        run(vec!["complete", &range, &ids[3]], &mut todo_list);
+        let statuses: Vec<Status> = todo_list.iter().map(|task| task.status).collect();
+        assert_eq!(
+            statuses,
+            [Status::Doing, Status::Doing, Status::Todo, Status::Doing]
+        );
+        run(
+            vec!["tag", "+infra", "-old", "status:doing"],
+            &mut todo_list,
+        );
+        assert_eq!(todo_list[0].tags, ["infra"]);
+        assert!(todo_list[2].tags.is_empty());
+        // Tasks picked by a query are only deleted once confirmed, or with --yes
+        let today = task::now().date();
+        assert!(matches!(
+            bulk::select_tasks(&["-tag:infra"], &todo_list, today),
+            Ok(bulk::Selection::Query(_))
+        ));
+        run(vec!["delete", "--yes", "-tag:infra"], &mut todo_list);
+        assert_eq!(todo_list.len(), 3);
+        for args in [
+            &["65000"][..],
+            &["-3"],
+            &["70000"],
+            &["12-8"],
+            &[&ids[0], "4x"],
+            &["three"],
+            &[&ids[0], "status:done"],
+        ] {
+            assert!(bulk::select_tasks(args, &todo_list, today).is_err());
+        }
+        run(vec!["delete", "three"], &mut todo_list);
+        assert_eq!(todo_list.len(), 3);
+
+        // Mark the first two tasks in the TUI, then act on both
+        let dir = std::env::temp_dir().join(format!("horme-bulk-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut app = App::new(vec![Tab::new(todo_list, &dir.join("todo.md"))]);
+        for code in [
+            KeyCode::Down,
+            KeyCode::Char(' '),
+            KeyCode::Char(' '),
+            KeyCode::Char('c'),
+            KeyCode::Char('#'),
+            KeyCode::Char('+'),
+            KeyCode::Char('x'),
+            KeyCode::Enter,
+        ] {
+            app.handle_key_event(KeyEvent::from(code)).unwrap();
+        }
+        let mut terminal = Terminal::new(backend::TestBackend::new(80, 20)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(&mut app, f.size()))
+            .unwrap();
+        let items = &app.tabs[0].todo_list.items;
+        assert_eq!(items[0].status, Status::Done);
+        assert_eq!(items[1].tags, ["infra", "x"]);
+        assert_eq!(items[2].status, Status::Doing);
+        for code in [KeyCode::Char('d'), KeyCode::Char('d')] {
+            app.handle_key_event(KeyEvent::from(code)).unwrap();
+        }
+        assert_eq!(app.tabs[0].todo_list.items.len(), 1);
+        assert!(app.marked.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn edit_task_as_markdown() {
+        use editor::{edit_task_in_editor, parse_task_snippet, task_snippet};
+
+        let todo_list = parse_todo_list(
+            "## Todo\n- Write docs\n    > first line\n    >\n    > second\n    * [ ] draft\n    @tags docs\n",
+        );
+        let task = &todo_list[0];
+        assert_eq!(task.description, "first line\n\nsecond");
+        let snippet = task_snippet(task);
+        assert!(snippet.starts_with("## Todo\n- Write docs\n    > first line\n    >\n"));
+
+        // Moving the task to another section changes its status
+        let snippet = snippet
+            .replace("## Todo", "## Done")
+            .replace("[ ] draft", "[x] draft\n    * [ ] review");
+        let edited = parse_task_snippet(&snippet, task).unwrap();
+        assert_eq!(edited.id, task.id);
+        assert_eq!(edited.status, Status::Done);
+        assert!(edited.completed_at.is_some());
+        assert_eq!(edited.subtasks.len(), 2);
+        assert!(parse_task_snippet("## Todo\n- one\n- two\n", task).is_err());
+        assert!(parse_task_snippet("## Todo\n- one\n    @due soon\n", task).is_err());
+
+        // The editor is given the file to edit as its last argument
+        let edited = edit_task_in_editor(task, "sed -i s/docs/documentation/").unwrap();
+        assert_eq!(edited.name, "Write documentation");
+        assert_eq!(edited.tags, ["documentation"]);
+    }
+
+    #[test]
+    fn edit_list_as_markdown() {
+        use editor::{list_document, parse_list_document};
+
+        let todo_list = parse_todo_list(
+            "## Todo\n- Write docs\n- Deploy nginx\n    @tags infra\n## Doing\n- Fix bug\n## Done\n- Release\n",
+        );
+        let ids: Vec<u16> = todo_list.iter().map(|task| task.id).collect();
+        let [docs, nginx, bug, release] = [0, 1, 2, 3].map(|i| todo_list[i].id);
+        let document = list_document(&todo_list, &ids);
+        assert!(document.contains(&format!("- Deploy nginx <!-- #{} -->\n", nginx)));
+
+        // Left as it is, the document gives the same list
+        let change_set = parse_list_document(&document, &todo_list, &ids).unwrap();
+        assert!(change_set.is_empty());
+        assert_eq!(change_set.after, todo_list);
+
+        // Rename, delete, add and move tasks
+        let edited = document
+            .replace("Write docs", "Write the docs")
+            .replace(
+                &format!("- Fix bug <!-- #{} -->\n", bug),
+                "- Triage issues\n",
+            )
+            .replace(
+                "## Done\n",
+                &format!("## Done\n- Deploy nginx <!-- #{} -->\n", nginx),
+            )
+            .replacen(
+                &format!("- Deploy nginx <!-- #{} -->\n    @tags infra\n", nginx),
+                "",
+                1,
+            );
+        let change_set = parse_list_document(&edited, &todo_list, &ids).unwrap();
+        assert_eq!(
+            change_set.to_string(),
+            "1 added, 1 deleted, 1 moved, 1 renamed"
+        );
+        let names: Vec<&str> = change_set
+            .after
+            .iter()
+            .map(|task| task.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["Write the docs", "Triage issues", "Deploy nginx", "Release"]
+        );
+        assert_eq!(change_set.after[1].status, Status::Doing);
+        assert_eq!(change_set.after[2].status, Status::Done);
+        assert!(change_set.after[2].tags.is_empty());
+
+        // Reordering tasks of a section reorders the list, tasks not shown
+        // in the document stay where they are
+        let subset = [docs, nginx, release];
+        let document = list_document(&todo_list, &subset);
+        let (first, second) = (
+            format!("- Write docs <!-- #{} -->\n", docs),
+            format!("- Deploy nginx <!-- #{} -->\n    @tags infra\n", nginx),
+        );
+        let edited = document.replace(&(first.clone() + &second), &(second + &first));
+        let change_set = parse_list_document(&edited, &todo_list, &subset).unwrap();
+        assert_eq!(change_set.to_string(), "reordered");
+        let order: Vec<u16> = change_set.after.iter().map(|task| task.id).collect();
+        assert_eq!(order, [nginx, docs, bug, release]);
+
+        // Problems leave the list as it is
+        assert!(parse_list_document("## Todo\n- x\n    @due soon\n", &todo_list, &ids).is_err());
+
+        // The change set is undone in one go, unless the list changed since
+        let mut tab = Tab::new(todo_list.clone(), std::path::Path::new("todo.md"));
+        tab.apply_change_set(change_set);
+        assert_eq!(tab.todo_list.items[0].id, nginx);
+        assert!(tab.undo().is_ok());
+        assert_eq!(tab.todo_list.items, todo_list);
+        assert!(tab.undo().is_err());
+    }
+
+    #[test]
+    fn throughput_stats() {
+        use chrono::NaiveDate;
+
+        let todo_list = parse_todo_list(
+            "## Todo\n- Write docs\n    @tags docs\n    @created 2026-10-01 09:00\n\
+             ## Doing\n- Deploy nginx\n    @tags infra\n    @started 2026-10-17 09:00\n\
+             - Fix bug\n\
+             ## Done\n- Release\n    @tags infra\n    @created 2026-10-10 09:00\n    @started 2026-10-16 09:00\n    @completed 2026-10-18 21:00\n\
+             - Triage\n    @created 2026-10-12 09:00\n    @completed 2026-10-13 09:00\n",
+        );
+        let now = NaiveDate::from_ymd_opt(2026, 10, 19)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap();
+        let stats = Stats::new(&todo_list, now);
+        assert_eq!((stats.tasks, stats.open, stats.done), (5, 3, 2));
+        let sections: Vec<(&str, usize, usize)> = stats
+            .sections
+            .iter()
+            .map(|count| (count.name.as_str(), count.open, count.done))
+            .collect();
+        assert_eq!(sections, [("Todo", 1, 0), ("Doing", 2, 0), ("Done", 0, 2)]);
+        assert_eq!(
+            (
+                stats.tags[0].name.as_str(),
+                stats.tags[0].open,
+                stats.tags[0].done
+            ),
+            ("infra", 1, 1)
+        );
+
+        // Per day up to today, per week from Monday
+        assert_eq!(stats.completed_per_day.len(), 14);
+        assert_eq!(stats.completed_per_day[13].tasks, 0);
+        assert_eq!(stats.completed_per_day[12].tasks, 1);
+        assert_eq!(stats.completed_per_week[7].tasks, 0);
+        assert_eq!(stats.completed_per_week[6].tasks, 2);
+
+        // Lead time from creation (8.5 and 1 days), cycle time from start
+        assert_eq!(
+            stats
+                .lead_time
+                .as_ref()
+                .map(|average| (average.days, average.tasks)),
+            Some((4.75, 2))
+        );
+        assert_eq!(
+            stats
+                .cycle_time
+                .as_ref()
+                .map(|average| (average.days, average.tasks)),
+            Some((2.5, 1))
+        );
+        assert_eq!(stats.doing[0].days, Some(2.0));
+        assert_eq!(stats.doing[1].days, None);
+        assert!(stats
+            .render_text()
+            .contains("Cycle time  2.5 days on average"));
+        let json: serde_json::Value = serde_json::from_str(&stats.render_json().unwrap()).unwrap();
+        assert_eq!(json["doing"][0]["name"], "Deploy nginx");
+
+        // Moving a task out of Todo dates its start
+        let mut task = todo_list[0].clone();
+        task.update_status();
+        assert!(task.started_at.is_some());
+        task.set_status(Status::Todo);
+        assert!(task.started_at.is_none());
+        // but not moving it straight to Done, which would make a 0 day cycle
+        task.set_status(Status::Done);
+        assert!(task.started_at.is_none() && task.completed_at.is_some());
+    }
+
+    #[test]
+    fn dashboard_screen() {
+        let todo_list = parse_todo_list(
+            "## Todo\n- Write docs\n    @due 2020-01-01\n- Deploy nginx\n    @due 2099-01-01\n    @tags infra\n## Done\n- Release\n",
+        );
+        let stats = Stats::new(&todo_list, task::now());
+        assert_eq!(stats.overdue, 1);
+        assert_eq!((stats.burndown.open, stats.burndown.done), (2, 0));
+        assert_eq!(
+            stats.burndown.remaining.last().map(|day| day.tasks),
+            Some(2)
+        );
+
+        let mut app = App::new(vec![Tab::new(todo_list, std::path::Path::new("todo.md"))]);
+        app.handle_key_event(KeyEvent::from(KeyCode::Char('D')))
+            .unwrap();
+        let mut terminal = Terminal::new(backend::TestBackend::new(100, 30)).unwrap();
+        let mut screen = |app: &mut App| {
+            let buffer = terminal
+                .draw(|f| f.render_widget(&mut *app, f.size()))
+                .unwrap()
+                .buffer
+                .clone();
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+        let before = screen(&mut app);
+        assert!(before.contains("Completed per day"));
+        assert!(before.contains("Burndown, 2 due tasks left"));
+
+        // The charts follow the changes made to the list
+        app.tabs[0].todo_list.items[1].set_status(Status::Done);
+        assert!(screen(&mut app).contains("Burndown, 1 due tasks left"));
+        app.handle_key_event(KeyEvent::from(KeyCode::Esc)).unwrap();
+        assert!(!screen(&mut app).contains("Completed per day"));
+    }
+
+    #[test]
+    fn reload_in_background() {
+        use std::time::Duration;
+
+        let dir = std::env::temp_dir().join(format!("horme-events-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("todo.md");
+        std::fs::write(&file_path, "## Todo\n- first\n").unwrap();
+        let mut app = App::new(vec![Tab::open(&file_path).unwrap()]);
+
+        // The file is read on another thread, then taken in by the main loop
+        std::fs::write(&file_path, "## Todo\n- first\n- second\n").unwrap();
+        app.on_tick();
+        let message = app.events.recv_message(Duration::from_secs(5)).unwrap();
+        app.handle_message(message);
+        assert_eq!(app.tabs[0].todo_list.items.len(), 2);
+        assert!(app.status_message.as_ref().unwrap().starts_with("Reloaded"));
+
+        // Edits made while the file is read are never saved over it, the
+        // list read is a conflict
+        std::fs::write(&file_path, "## Todo\n- third\n").unwrap();
+        app.on_tick();
+        app.tabs[0].todo_list.items[0].name = "first!".to_string();
+        assert!(app.tabs[0].save().is_err());
+        let message = app.events.recv_message(Duration::from_secs(5)).unwrap();
+        app.handle_message(message);
+        assert!(app.current_screen == app::CurrentScreen::Conflict);
+        assert_eq!(app.tabs[0].remote_list.as_ref().unwrap()[0].name, "third");
+        assert_eq!(app.tabs[0].todo_list.items[0].name, "first!");
+
+        // A read superseded by another one is dropped
+        app.tabs[0].keep_local_list();
+        app.current_screen = app::CurrentScreen::Main;
+        std::fs::write(&file_path, "## Todo\n- fourth\n").unwrap();
+        app.on_tick();
+        assert_eq!(app.tabs[0].check_external_changes(), FileChange::Conflict);
+        app.tabs[0].reload_remote_list();
+        app.tabs[0].todo_list.items[0].name = "fourth!".to_string();
+        app.tabs[0].save().unwrap();
+        let message = app.events.recv_message(Duration::from_secs(5)).unwrap();
+        app.handle_message(message);
+        assert!(app.tabs[0].remote_list.is_none());
+        assert!(std::fs::read_to_string(&file_path)
+            .unwrap()
+            .starts_with("## Todo\n- fourth!\n"));
+
+        // A read that started before a save and found other changes is a
+        // conflict, once it is solved the list can be saved over the file
+        std::fs::write(&file_path, "## Todo\n- fifth\n").unwrap();
+        let load = app.tabs[0].poll_file().unwrap();
+        let seen = watch::FileWatcher::new(&file_path);
+        let mut storage = storage_for(&file_path);
+        let loaded = storage
+            .load()
+            .map(|(list, diagnostics)| (storage, list, diagnostics));
+        let saves = app.tabs[0].saves - 1;
+        assert_eq!(
+            app.tabs[0].use_background_load(load, saves, seen, loaded),
+            FileChange::Conflict
+        );
+        assert!(app.tabs[0].save().is_err());
+        app.tabs[0].keep_local_list();
+        app.tabs[0].save().unwrap();
+        assert!(std::fs::read_to_string(&file_path)
+            .unwrap()
+            .starts_with("## Todo\n- fourth!\n"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,274 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// A lock whose heartbeat is older than this is considered abandoned
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+// How often the owner of a lock refreshes its heartbeat
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+// Advisory lock on a todo file, stored next to it as `.<file name>.lock`.
+// The lock file holds the pid of the owner, the time of its last heartbeat
+// and a token telling this lock apart from any other, even of the same process.
+#[derive(Debug)]
+pub struct FileLock {
+    path: PathBuf,
+    token: String,
+    last_heartbeat: Instant,
+}
+
+// Process currently holding the lock on a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockOwner {
+    // Unknown while the other instance is still writing the lock file
+    pub pid: Option<u32>,
+}
+
+// Keeps a lock alive from a thread, see `FileLock::keep_alive`
+pub struct Heartbeat {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+pub enum LockStatus {
+    Acquired(FileLock),
+    HeldBy(LockOwner),
+}
+
+// What a lock file holds
+struct LockFile {
+    pid: u32,
+    heartbeat: u64,
+    // Missing in the lock files of older versions
+    token: Option<String>,
+}
+
+impl FileLock {
+    // Try to take the lock on `file_path`, replacing it if it was left behind
+    // by an instance that is no longer running
+    pub fn acquire(file_path: &Path) -> io::Result<LockStatus> {
+        let path = lock_path(file_path);
+        let token = new_token();
+        loop {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+            {
+                Ok(mut file) => {
+                    file.write_all(lock_contents(&token).as_bytes())?;
+                    return Ok(LockStatus::Acquired(Self {
+                        path,
+                        token,
+                        last_heartbeat: Instant::now(),
+                    }));
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    let contents = fs::read_to_string(&path).unwrap_or_default();
+                    match parse_lock(&contents) {
+                        Some(lock) if !is_stale(lock.pid, lock.heartbeat) => {
+                            return Ok(LockStatus::HeldBy(LockOwner {
+                                pid: Some(lock.pid),
+                            }));
+                        }
+                        None if !is_abandoned(&path) => {
+                            return Ok(LockStatus::HeldBy(LockOwner { pid: None }));
+                        }
+                        // Stale lock, remove it unless another instance
+                        // replaced it meanwhile, and try again
+                        _ => remove_lock_if(&path, &token, |current| current == contents)?,
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Check who is holding the lock on `file_path`, if anyone
+    pub fn owner(file_path: &Path) -> Option<LockOwner> {
+        let path = lock_path(file_path);
+        let contents = fs::read_to_string(&path).ok()?;
+        match parse_lock(&contents) {
+            Some(lock) if !is_stale(lock.pid, lock.heartbeat) => Some(LockOwner {
+                pid: Some(lock.pid),
+            }),
+            None if !is_abandoned(&path) => Some(LockOwner { pid: None }),
+            _ => None,
+        }
+    }
+
+    // Tell other instances that the lock is still in use. Returns false when
+    // the lock was lost to another instance that found it stale.
+    pub fn refresh(&mut self) -> bool {
+        if self.last_heartbeat.elapsed() < HEARTBEAT_INTERVAL {
+            return true;
+        }
+        self.last_heartbeat = Instant::now();
+        write_heartbeat(&self.path, &self.token)
+    }
+
+    // Refresh the heartbeat from a thread while this one is blocked, running
+    // $EDITOR for instance, until the returned value is dropped
+    pub fn keep_alive(&self) -> Heartbeat {
+        let (stop, stopped) = mpsc::channel::<()>();
+        let path = self.path.clone();
+        let token = self.token.clone();
+        let thread = thread::spawn(move || {
+            while stopped.recv_timeout(HEARTBEAT_INTERVAL) == Err(RecvTimeoutError::Timeout) {
+                if !write_heartbeat(&path, &token) {
+                    break;
+                }
+            }
+        });
+        Heartbeat {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    // Whether the lock file is still this lock
+    pub fn is_held(&self) -> bool {
+        fs::read_to_string(&self.path).is_ok_and(|contents| holds_token(&contents, &self.token))
+    }
+}
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        // The thread stops once the channel is closed
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Leave the lock of another instance alone
+        let _ = remove_lock_if(&self.path, &self.token, |contents| {
+            holds_token(contents, &self.token)
+        });
+    }
+}
+
+fn lock_path(file_path: &Path) -> PathBuf {
+    let file_name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_path.with_file_name(format!(".{}.lock", file_name))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+// Unique to a lock, the pid alone doesn't tell apart two locks of a process
+fn new_token() -> String {
+    static LOCKS: AtomicU32 = AtomicU32::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.subsec_nanos())
+        .unwrap_or_default();
+    format!(
+        "{}-{}-{}-{}",
+        process::id(),
+        now(),
+        nanos,
+        LOCKS.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+fn lock_contents(token: &str) -> String {
+    format!("{}\n{}\n{}\n", process::id(), now(), token)
+}
+
+// Read the pid, heartbeat and token stored in a lock file
+fn parse_lock(contents: &str) -> Option<LockFile> {
+    let mut lines = contents.lines();
+    let pid = lines.next()?.trim().parse().ok()?;
+    let heartbeat = lines.next()?.trim().parse().ok()?;
+    let token = lines.next().map(|token| token.trim().to_string());
+    Some(LockFile {
+        pid,
+        heartbeat,
+        token,
+    })
+}
+
+fn holds_token(contents: &str, token: &str) -> bool {
+    parse_lock(contents).is_some_and(|lock| lock.token.as_deref() == Some(token))
+}
+
+// Write a new heartbeat in the lock file as long as it is still the lock of
+// `token`, never creating the file again once it was removed
+fn write_heartbeat(path: &Path, token: &str) -> bool {
+    if !fs::read_to_string(path).is_ok_and(|contents| holds_token(&contents, token)) {
+        return false;
+    }
+    let _ = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(lock_contents(token).as_bytes()));
+    true
+}
+
+// Remove the lock file if `expected` holds for its contents. Removing it
+// straight away could remove a lock another instance just took, so the file
+// is moved aside first, which only one instance can do, and put back if it
+// turns out to be another lock.
+fn remove_lock_if(path: &Path, token: &str, expected: impl Fn(&str) -> bool) -> io::Result<()> {
+    let aside = path.with_file_name(format!(
+        "{}.{}",
+        path.file_name().unwrap_or_default().to_string_lossy(),
+        token
+    ));
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    }
+    let contents = fs::read_to_string(&aside).unwrap_or_default();
+    if !expected(&contents) {
+        // Fails when yet another instance took the lock, which is then theirs
+        let _ = fs::hard_link(&aside, path);
+    }
+    fs::remove_file(&aside)
+}
+
+fn is_stale(pid: u32, heartbeat: u64) -> bool {
+    now().saturating_sub(heartbeat) > STALE_LOCK_AGE.as_secs() || !is_running(pid)
+}
+
+// A lock file that can't be read is only abandoned once it stopped changing
+fn is_abandoned(path: &Path) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_none_or(|age| age > STALE_LOCK_AGE)
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+// Without a portable way to look up processes, rely on the heartbeat alone
+#[cfg(not(target_os = "linux"))]
+fn is_running(_pid: u32) -> bool {
+    true
+}
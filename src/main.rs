@@ -1,29 +1,94 @@
-use color_eyre::Result;
-use regex::Regex;
+use chrono::Local;
+use color_eyre::{eyre::WrapErr, Result};
+use std::env;
 use std::fs;
+use std::io::{self, Read};
 use std::path::Path;
+use std::process;
 
-mod app;
-mod task;
-mod tui;
+use horme::{
+    archive_path, completion_script, import_tasks, print_tasks, storage_for, summarize, App,
+    Config, FileChange, Format, OutputFormat, Severity, Stats, Tab, Theme, View, SHELLS,
+};
+use horme::{init_error_hooks, init_terminal, restore_terminal};
+use horme::{run, run_shell};
 
-use task::Status;
-use task::Task;
+// File used when no other file is given
+const TODO_FILE: &str = "todo.md";
 
-use app::App;
-use tui::{init_error_hooks, init_terminal, restore_terminal};
+// Extensions of the files a list can be stored in, see `storage_for`
+const LIST_EXTENSIONS: [&str; 9] = [
+    "md", "markdown", "json", "txt", "ics", "org", "db", "sqlite", "sqlite3",
+];
 
 fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    match args.first().map(String::as_str) {
+        Some("check") => check(Path::new(args.get(1).map_or(TODO_FILE, String::as_str))),
+        Some("archive") => archive(&args[1..]),
+        Some("export") => export(&args[1..]),
+        Some("import") => import(&args[1..]),
+        Some("stats") => stats(&args[1..]),
+        Some("completions") => completions(args.get(1)),
+        Some("__complete") => complete(&args[1..]),
+        Some("shell") => run_shell(Path::new(args.get(1).map_or(TODO_FILE, String::as_str))),
+        Some(name @ ("today" | "upcoming" | "overdue" | "someday")) => view(name, &args[1..]),
+        Some(
+            "add" | "list" | "ls" | "edit" | "edit-list" | "delete" | "complete" | "tag" | "help",
+        ) => command(&args),
+        Some("view") => match args.get(1) {
+            Some(name) => view(name, &args[2..]),
+            None => {
+                eprintln!("Usage: horme view <name> [file]");
+                process::exit(2);
+            }
+        },
+        // Any other argument is a file to open in its own tab, as long as it
+        // can't be a mistyped command
+        Some(_) => {
+            if let Some(arg) = args.iter().find(|arg| !is_list_file(Path::new(arg))) {
+                eprintln!(
+                    "Unknown command or file \"{}\", run `horme help` to see the commands",
+                    arg
+                );
+                process::exit(2);
+            }
+            run_tui(args.iter().map(Path::new).collect())
+        }
+        None => run_tui(vec![Path::new(TODO_FILE)]),
+    }
+}
+
+// Whether a file can be opened as a list: it exists, or it would be created
+// in one of the formats lists are stored in
+fn is_list_file(file_path: &Path) -> bool {
+    file_path.exists()
+        || file_path.extension().is_some_and(|extension| {
+            let extension = extension.to_string_lossy().to_lowercase();
+            LIST_EXTENSIONS.contains(&extension.as_str())
+        })
+}
+
+fn run_tui(file_paths: Vec<&Path>) -> Result<()> {
     init_error_hooks()?;
-    let terminal = init_terminal()?;
+    let config = Config::load()?;
+
+    // Build the lists of tasks from the files
+    let tabs = file_paths
+        .into_iter()
+        .map(Tab::open)
+        .collect::<Result<Vec<Tab>>>()?;
 
-    // File todo.md must exist in the current path
-    let file_path = Path::new("todo.md");
-    // Build the list of tasks from a markdown file
-    let todo_list = build_todo_list(file_path)?;
+    let terminal = init_terminal()?;
 
     // Initialize the application
-    let mut app = App::new(todo_list, file_path);
+    let mut app = App::new(tabs);
+    app.add_saved_views(&config.views);
+    if let Some(days) = config.archive.after_days {
+        app.archive_old_tasks(days);
+    }
+    app.show_diagnostics();
 
     // Run the application
     app.run(terminal)?;
@@ -33,51 +98,357 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn build_todo_list(file_path: &Path) -> Result<Vec<Task>> {
-    // Build the list of tasks from a markdown file
-    let contents = fs::read_to_string(file_path).expect(
-        "Something went wrong reading the file, check if todo.md exists in the current path.",
+// Validate a file, exiting with a non-zero status if it contains errors
+fn check(file_path: &Path) -> Result<()> {
+    let (todo_list, diagnostics) = storage_for(file_path).load()?;
+
+    for diagnostic in &diagnostics {
+        eprintln!("{}:{}", file_path.display(), diagnostic);
+    }
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    println!(
+        "{}: {} tasks, {} errors, {} warnings",
+        file_path.display(),
+        todo_list.len(),
+        errors,
+        diagnostics.len() - errors
     );
 
-    // Define a regular expression pattern for headings (#)
-    let heading_pattern = Regex::new(r"^(#{1,6}) (.*)$").unwrap();
-
-    // Initialize the variables to store the current heading and lines after each heading
-    let mut current_heading: Option<String> = None;
-    let mut todo_list: Vec<Task> = vec![];
-
-    // Iterate over each line in the file
-    for line in contents.lines() {
-        if let Some(captures) = heading_pattern.captures(line) {
-            // If a heading is found, store its text and reset the lines vector
-            current_heading = Some(captures[2].to_string());
-        } else if line.starts_with("- ") {
-            let status = match &current_heading {
-                Some(head) if head.trim().eq_ignore_ascii_case("Todo") => Status::Todo,
-                Some(head) if head.trim().eq_ignore_ascii_case("Doing") => Status::Doing,
-                Some(head) if head.trim().eq_ignore_ascii_case("Done") => Status::Done,
-                _ => Status::Todo, // Default to Todo if no heading is found or doesn't match
-            };
-            todo_list.push(Task::new(
-                line[2..].to_string(),
-                String::new(),
-                status,
-                None,
-            ));
-        } else if line.starts_with("    >") {
-            if let Some(last_element) = todo_list.last_mut() {
-                last_element.add_description(&line[5..].trim());
+    if errors > 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// Move the done tasks of a file to its archive:
+// horme archive [--older-than <days>] [file]
+fn archive(args: &[String]) -> Result<()> {
+    let mut older_than_days = None;
+    let mut file_path = Path::new(TODO_FILE);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--older-than" | "-o" => {
+                let days = args.next().and_then(|days| days.parse::<u32>().ok());
+                if days.is_none() {
+                    eprintln!("Please provide the number of days after --older-than");
+                    process::exit(2);
+                }
+                older_than_days = days;
             }
-        } else if line.starts_with("    * [ ]") || line.starts_with("    * [x]") {
-            if let Some(last_element) = todo_list.last_mut() {
-                if line.starts_with("    * [ ]") {
-                    last_element.add_subtask(line[9..].trim().to_string(), false);
-                } else if line.starts_with("    * [x]") {
-                    last_element.add_subtask(line[9..].trim().to_string(), true);
+            _ => file_path = Path::new(arg),
+        }
+    }
+
+    let mut tab = Tab::open(file_path)?;
+    if !tab.lock()? {
+        eprintln!(
+            "{} is being edited by another instance, try again later",
+            file_path.display()
+        );
+        process::exit(1);
+    }
+    if older_than_days.is_some() {
+        tab.date_done_tasks();
+    }
+    let archived = tab
+        .archive_done_tasks(older_than_days)
+        .wrap_err("Unable to archive tasks")?;
+    if tab.has_unsaved_changes() {
+        tab.save()?;
+    }
+
+    println!(
+        "Archived {} tasks to {}",
+        archived,
+        archive_path(file_path).display()
+    );
+    Ok(())
+}
+
+// Write a list in another format, to stdout unless an output file is given:
+// horme export [--format <format>] [--theme <theme>] [--output <file>] [file]
+fn export(args: &[String]) -> Result<()> {
+    let mut format = None;
+    let mut theme = None;
+    let mut output = None;
+    let mut file_path = Path::new(TODO_FILE);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => format = Some(parse_format(args.next())),
+            "--output" | "-o" => output = args.next().map(Path::new),
+            "--theme" => match args.next().and_then(|name| Theme::from_name(name)) {
+                Some(name) => theme = Some(name),
+                None => {
+                    eprintln!("Please provide a theme after --theme, one of: light, tui");
+                    process::exit(2);
+                }
+            },
+            _ => file_path = Path::new(arg),
+        }
+    }
+    // Without --format, the extension of the output file decides
+    let mut format = format
+        .or_else(|| output.and_then(Format::from_path))
+        .unwrap_or(Format::Json);
+    if let (Format::Html(_), Some(theme)) = (format, theme) {
+        format = Format::Html(theme);
+    }
+
+    let (todo_list, _) = storage_for(file_path).load()?;
+    let data = format.render(&todo_list)?;
+    match output {
+        Some(output) => fs::write(output, data)
+            .wrap_err_with(|| format!("Unable to write {}", output.display()))?,
+        None => print!("{}", data),
+    }
+    Ok(())
+}
+
+// Add the tasks of a file, or of stdin with `-`, to a list. With --dry-run,
+// only describe what would be imported:
+// horme import [--format <format>] [--dry-run] <input> [file]
+fn import(args: &[String]) -> Result<()> {
+    let mut format = None;
+    let mut dry_run = false;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => format = Some(parse_format(args.next())),
+            "--dry-run" | "-n" => dry_run = true,
+            _ => paths.push(Path::new(arg)),
+        }
+    }
+    let (input, file_path) = match paths[..] {
+        [input] => (input, Path::new(TODO_FILE)),
+        [input, file_path] => (input, file_path),
+        _ => {
+            eprintln!("Usage: horme import [--format <format>] [--dry-run] <input> [file]");
+            process::exit(2);
+        }
+    };
+
+    let contents = if input == Path::new("-") {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        contents
+    } else {
+        fs::read_to_string(input).wrap_err_with(|| format!("Unable to read {}", input.display()))?
+    };
+    let format = format
+        .or_else(|| Format::from_path(input))
+        .unwrap_or(Format::Json);
+    let (imported, diagnostics) = format
+        .parse(&contents)
+        .wrap_err_with(|| format!("Invalid {}", input.display()))?;
+    for diagnostic in &diagnostics {
+        eprintln!("{}:{}", input.display(), diagnostic);
+    }
+    if dry_run {
+        println!("Would import into {}:", file_path.display());
+        for line in summarize(&imported) {
+            println!("  {}", line);
+        }
+        return Ok(());
+    }
+
+    let mut tab = Tab::open(file_path)?;
+    if !tab.lock()? {
+        eprintln!(
+            "{} is being edited by another instance, try again later",
+            file_path.display()
+        );
+        process::exit(1);
+    }
+    let count = import_tasks(&mut tab.todo_list.items, imported);
+    tab.save()?;
+
+    println!("Imported {} tasks into {}", count, file_path.display());
+    Ok(())
+}
+
+// Run a command on a list, `horme help` lists them:
+// horme <command> <arguments> [--file <file>]
+fn command(args: &[String]) -> Result<()> {
+    let mut file_path = Path::new(TODO_FILE);
+    let mut arguments = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--file" => match args.next() {
+                Some(path) => file_path = Path::new(path),
+                None => {
+                    eprintln!("Please provide a file after --file");
+                    process::exit(2);
                 }
+            },
+            _ => arguments.push(arg.as_str()),
+        }
+    }
+
+    let mut tab = Tab::open(file_path)?;
+    let read_only = matches!(arguments[0], "list" | "ls" | "help");
+    if !read_only && !tab.lock()? {
+        eprintln!(
+            "{} is being edited by another instance, try again later",
+            file_path.display()
+        );
+        process::exit(1);
+    }
+    // Commands can wait on $EDITOR or on a confirmation for a long time
+    let heartbeat = tab.keep_lock_alive();
+    run(arguments, &mut tab.todo_list.items);
+    drop(heartbeat);
+    if tab.has_unsaved_changes() {
+        // The file may have changed while the command waited on $EDITOR
+        if tab.check_external_changes() == FileChange::Conflict {
+            eprintln!(
+                "{} was changed by another program meanwhile, the changes were not saved",
+                file_path.display()
+            );
+            process::exit(1);
+        }
+        tab.save()?;
+    }
+    Ok(())
+}
+
+// Print the tasks of a list in a view, a smart view or one saved in the
+// configuration: horme today [--format <format>] [file],
+// horme view <name> [--format <format>] [file]
+fn view(name: &str, args: &[String]) -> Result<()> {
+    let config = Config::load()?;
+    let Some(view) = View::from_name(name, &config.views) else {
+        let names: Vec<String> = View::all(&config.views)
+            .iter()
+            .map(|view| view.name().to_lowercase())
+            .collect();
+        eprintln!("Unknown view \"{}\", one of: {}", name, names.join(", "));
+        process::exit(2);
+    };
+    let mut format = OutputFormat::default();
+    let mut file_path = Path::new(TODO_FILE);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => match args.next().and_then(|name| OutputFormat::from_name(name)) {
+                Some(name) => format = name,
+                None => {
+                    eprintln!(
+                        "Please provide a format after --format, one of: {}",
+                        OutputFormat::NAMES.join(", ")
+                    );
+                    process::exit(2);
+                }
+            },
+            _ => file_path = Path::new(arg),
+        }
+    }
+
+    let (todo_list, _) = storage_for(file_path).load()?;
+    let today = Local::now().date_naive();
+    let tasks: Vec<_> = view
+        .select(&todo_list, today)
+        .into_iter()
+        .map(|i| todo_list[i].clone())
+        .collect();
+    print_tasks(&tasks, format)
+}
+
+// Report the throughput of a list, as text or JSON:
+// horme stats [--format <text|json>] [file]
+fn stats(args: &[String]) -> Result<()> {
+    let mut json = false;
+    let mut file_path = Path::new(TODO_FILE);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" | "-f" => match args.next().map(String::as_str) {
+                Some("text") => json = false,
+                Some("json") => json = true,
+                _ => {
+                    eprintln!("Please provide a format after --format, one of: text, json");
+                    process::exit(2);
+                }
+            },
+            _ => file_path = Path::new(arg),
+        }
+    }
+
+    let (todo_list, _) = storage_for(file_path).load()?;
+    let stats = Stats::new(&todo_list, Local::now().naive_local());
+    if json {
+        print!("{}", stats.render_json()?);
+    } else {
+        print!("{}", stats.render_text());
+    }
+    Ok(())
+}
+
+// Print the completion script of a shell: horme completions <bash|zsh|fish>
+fn completions(shell: Option<&String>) -> Result<()> {
+    match shell.and_then(|shell| completion_script(shell)) {
+        Some(script) => print!("{}", script),
+        None => {
+            eprintln!("Usage: horme completions <{}>", SHELLS.join("|"));
+            process::exit(2);
+        }
+    }
+    Ok(())
+}
+
+// Print the words the completion scripts can't know in advance, one per line:
+// horme __complete ids [file] prints the id and name of each task, separated by
+// a tab, horme __complete tags [file] the tags of the tasks and
+// horme __complete views the names of the views
+fn complete(args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str) {
+        Some("ids") => {
+            let file_path = Path::new(args.get(1).map_or(TODO_FILE, String::as_str));
+            let (todo_list, _) = storage_for(file_path).load()?;
+            for task in &todo_list {
+                println!("{}\t{}", task.id, task.name.replace(['\t', '\n'], " "));
+            }
+        }
+        Some("tags") => {
+            let file_path = Path::new(args.get(1).map_or(TODO_FILE, String::as_str));
+            let (todo_list, _) = storage_for(file_path).load()?;
+            let mut tags: Vec<&String> = todo_list.iter().flat_map(|task| &task.tags).collect();
+            tags.sort();
+            tags.dedup();
+            for tag in tags {
+                println!("{}", tag);
+            }
+        }
+        Some("views") => {
+            let config = Config::load()?;
+            for view in View::all(&config.views) {
+                println!("{}", view.name().to_lowercase());
             }
         }
+        _ => {
+            eprintln!("Usage: horme __complete <ids [file]|tags [file]|views>");
+            process::exit(2);
+        }
     }
+    Ok(())
+}
 
-    Ok(todo_list)
+// Read the value of --format, exiting when it is missing or unknown
+fn parse_format(name: Option<&String>) -> Format {
+    match name.and_then(|name| Format::from_name(name)) {
+        Some(format) => format,
+        None => {
+            eprintln!(
+                "Please provide a format after --format, one of: {}",
+                Format::NAMES.join(", ")
+            );
+            process::exit(2);
+        }
+    }
 }
@@ -0,0 +1,365 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use std::fmt::Display;
+
+use crate::task::{Status, Task, DATE_FORMAT, DATE_TIME_FORMAT};
+
+// Default order of the sections written to the markdown file
+pub fn default_sections_order() -> Vec<String> {
+    vec![
+        "## Todo".to_string(),
+        "## Doing".to_string(),
+        "## Done".to_string(),
+    ]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    // The line was understood, but doesn't follow the format exactly
+    Warning,
+    // The line couldn't be used and its content is lost on save
+    Error,
+}
+
+// Problem found while parsing a markdown file, lines and columns start at 1.
+// Problems with the file as a whole use line 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line > 0 {
+            write!(f, "{}:{}: ", self.line, self.column)?;
+        }
+        write!(f, "{}: {}", self.severity, self.message)
+    }
+}
+
+impl Diagnostic {
+    pub(crate) fn new(line: usize, column: usize, severity: Severity, message: String) -> Self {
+        Self {
+            line,
+            column,
+            severity,
+            message,
+        }
+    }
+}
+
+// Build the list of tasks from the contents of a markdown file
+pub fn parse_todo_list(contents: &str) -> Vec<Task> {
+    parse_todo_list_checked(contents).0
+}
+
+// Build the list of tasks, also reporting the lines that don't follow the format
+pub fn parse_todo_list_checked(contents: &str) -> (Vec<Task>, Vec<Diagnostic>) {
+    // Define a regular expression pattern for headings (#)
+    let heading_pattern = Regex::new(r"^(#{1,6}) (.*)$").unwrap();
+    // Subtasks with any checkbox marker, so that unknown ones can be reported
+    let subtask_pattern = Regex::new(r"^\* \[(.)\](.*)$").unwrap();
+
+    // Initialize the variables to store the current heading and lines after each heading
+    let mut current_heading: Option<String> = None;
+    let mut todo_list: Vec<Task> = vec![];
+    let mut diagnostics: Vec<Diagnostic> = vec![];
+    // Unknown headings are only reported once a task is found under them
+    let mut unknown_heading: Option<Diagnostic> = None;
+
+    // Iterate over each line in the file
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let content = line.trim_start();
+        let indent = line.len() - content.len();
+        let column = indent + 1;
+
+        if content.is_empty() {
+            continue;
+        }
+
+        if let Some(captures) = heading_pattern.captures(line) {
+            // If a heading is found, store its text and reset the lines vector
+            let heading = captures[2].trim();
+            unknown_heading = match status_for(heading) {
+                Some(_) => None,
+                None => Some(Diagnostic::new(
+                    line_number,
+                    column,
+                    Severity::Warning,
+                    format!(
+                        "unknown section \"{}\", its tasks are read as Todo",
+                        heading
+                    ),
+                )),
+            };
+            current_heading = Some(heading.to_string());
+        } else if let Some(name) = line.strip_prefix("- ") {
+            let status = current_heading
+                .as_deref()
+                .and_then(status_for)
+                .unwrap_or(Status::Todo); // Default to Todo if no heading is found or doesn't match
+            if name.trim().is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    3,
+                    Severity::Error,
+                    "task without a name".to_string(),
+                ));
+                continue;
+            }
+            diagnostics.extend(unknown_heading.take());
+            todo_list.push(Task::new(name.to_string(), String::new(), status, None));
+        } else if content.starts_with('>') || subtask_pattern.is_match(content) {
+            let kind = if content.starts_with('>') {
+                "description"
+            } else {
+                "subtask"
+            };
+            let Some(last_element) = todo_list.last_mut() else {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    column,
+                    Severity::Error,
+                    format!("{} before any task", kind),
+                ));
+                continue;
+            };
+            if indent != 4 || line[..indent].contains('\t') {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    column,
+                    Severity::Warning,
+                    format!("{} should be indented with 4 spaces", kind),
+                ));
+            }
+
+            if let Some(description) = content.strip_prefix('>') {
+                if !last_element.description.is_empty() {
+                    last_element.add_description("\n");
+                }
+                last_element.add_description(description.trim());
+            } else if let Some(captures) = subtask_pattern.captures(content) {
+                let done = match &captures[1] {
+                    " " => false,
+                    "x" => true,
+                    marker => {
+                        diagnostics.push(Diagnostic::new(
+                            line_number,
+                            column + 3,
+                            Severity::Warning,
+                            format!(
+                                "unknown checkbox \"[{}]\", expected \"[ ]\" or \"[x]\"",
+                                marker
+                            ),
+                        ));
+                        marker.eq_ignore_ascii_case("x")
+                    }
+                };
+                last_element.add_subtask(captures[2].trim().to_string(), done);
+            }
+        } else if let Some(property) = content.strip_prefix('@') {
+            let Some(last_element) = todo_list.last_mut() else {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    column,
+                    Severity::Error,
+                    "property before any task".to_string(),
+                ));
+                continue;
+            };
+            if let Err(message) = parse_property(last_element, property) {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    column,
+                    Severity::Warning,
+                    message,
+                ));
+            }
+        } else if let Some(name) = content.strip_prefix("- ") {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                column,
+                Severity::Error,
+                format!("indented task \"{}\" is ignored", name.trim()),
+            ));
+        } else {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                column,
+                Severity::Warning,
+                "line is not a heading, task, description or subtask and is ignored".to_string(),
+            ));
+        }
+    }
+
+    (todo_list, diagnostics)
+}
+
+// Set a task property written as `@<key> <value>` below the task
+fn parse_property(task: &mut Task, property: &str) -> Result<(), String> {
+    let (key, value) = property.split_once(' ').unwrap_or((property, ""));
+    let value = value.trim();
+    match key {
+        "due" => {
+            let invalid = || format!("invalid date \"{}\", expected YYYY-MM-DD [HH:MM]", value);
+            let (date, time) = match value.split_once(' ') {
+                Some((date, time)) => (date, Some(time.trim())),
+                None => (value, None),
+            };
+            task.due_date =
+                Some(NaiveDate::parse_from_str(date, DATE_FORMAT).map_err(|_| invalid())?);
+            task.due_time = time
+                .map(|time| NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| invalid()))
+                .transpose()?;
+        }
+        "scheduled" => {
+            task.scheduled = Some(
+                NaiveDate::parse_from_str(value, DATE_FORMAT)
+                    .map_err(|_| format!("invalid date \"{}\", expected YYYY-MM-DD", value))?,
+            )
+        }
+        "every" => task.recurrence = Some(format!("every {}", value).parse()?),
+        "priority" => match value.chars().next() {
+            Some(priority) if value.len() == 1 && priority.is_ascii_uppercase() => {
+                task.priority = Some(priority)
+            }
+            _ => return Err(format!("invalid priority \"{}\", expected A to Z", value)),
+        },
+        "tags" => task
+            .tags
+            .extend(value.split_whitespace().map(str::to_string)),
+        "section" if !value.is_empty() => task.section = Some(value.to_string()),
+        "uid" if !value.is_empty() => task.uid = Some(value.to_string()),
+        "created" => {
+            let date = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+                .map_err(|_| format!("invalid date \"{}\", expected YYYY-MM-DD HH:MM", value))?;
+            task.created_at = Some(date);
+        }
+        "started" => {
+            let date = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+                .map_err(|_| format!("invalid date \"{}\", expected YYYY-MM-DD HH:MM", value))?;
+            task.started_at = Some(date);
+        }
+        "completed" => {
+            let date = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+                .map_err(|_| format!("invalid date \"{}\", expected YYYY-MM-DD HH:MM", value))?;
+            task.completed_at = Some(date);
+        }
+        _ => return Err(format!("unknown property \"@{}\" is ignored", key)),
+    }
+    Ok(())
+}
+
+// Write the properties of a task, one `@<key> <value>` line each
+fn render_properties(task: &Task, data: &mut String) {
+    if let Some(date) = task.due_date {
+        data.push_str(&format!("    @due {}", date.format(DATE_FORMAT)));
+        if let Some(time) = task.due_time {
+            data.push_str(&format!(" {}", time.format("%H:%M")));
+        }
+        data.push('\n');
+    }
+    if let Some(date) = task.scheduled {
+        data.push_str(&format!("    @scheduled {}\n", date.format(DATE_FORMAT)));
+    }
+    if let Some(recurrence) = &task.recurrence {
+        data.push_str(&format!("    @{}\n", recurrence));
+    }
+    if let Some(priority) = task.priority {
+        data.push_str(&format!("    @priority {}\n", priority));
+    }
+    if !task.tags.is_empty() {
+        data.push_str(&format!("    @tags {}\n", task.tags.join(" ")));
+    }
+    if let Some(section) = &task.section {
+        data.push_str(&format!("    @section {}\n", section));
+    }
+    if let Some(uid) = &task.uid {
+        data.push_str(&format!("    @uid {}\n", uid));
+    }
+    if let Some(date) = task.created_at {
+        data.push_str(&format!("    @created {}\n", date.format(DATE_TIME_FORMAT)));
+    }
+    if let Some(date) = task.started_at {
+        data.push_str(&format!("    @started {}\n", date.format(DATE_TIME_FORMAT)));
+    }
+    if let Some(date) = task.completed_at {
+        data.push_str(&format!(
+            "    @completed {}\n",
+            date.format(DATE_TIME_FORMAT)
+        ));
+    }
+}
+
+// Status of the tasks listed under a section heading
+fn status_for(heading: &str) -> Option<Status> {
+    match heading.trim() {
+        head if head.eq_ignore_ascii_case("Todo") => Some(Status::Todo),
+        head if head.eq_ignore_ascii_case("Doing") => Some(Status::Doing),
+        head if head.eq_ignore_ascii_case("Done") => Some(Status::Done),
+        _ => None,
+    }
+}
+
+// Generate the markdown data from the list of tasks
+pub fn render_todo_list(todo_list: &[Task], sections_order: &[String]) -> String {
+    let mut data = String::new();
+
+    for section in sections_order {
+        data.push_str(&format!("{}\n", section));
+        for task in todo_list
+            .iter()
+            .filter(|task| section_for(task.status) == section)
+        {
+            render_task(task, &mut data);
+        }
+        data.push('\n');
+    }
+
+    data
+}
+
+// Write a task with its description, subtasks and properties
+pub fn render_task(task: &Task, data: &mut String) {
+    data.push_str(&format!("- {}\n", task.name));
+    if !task.description.is_empty() {
+        // Each line of the description is quoted
+        for line in task.description.lines() {
+            if line.is_empty() {
+                data.push_str("    >\n");
+            } else {
+                data.push_str(&format!("    > {}\n", line));
+            }
+        }
+    }
+    for subtask in &task.subtasks {
+        if subtask.status {
+            data.push_str(&format!("    * [x] {}\n", subtask.name));
+        } else {
+            data.push_str(&format!("    * [ ] {}\n", subtask.name));
+        }
+    }
+    render_properties(task, data);
+}
+
+// Heading under which tasks with the given status are written
+pub fn section_for(status: Status) -> &'static str {
+    match status {
+        Status::Todo => "## Todo",
+        Status::Doing => "## Doing",
+        Status::Done => "## Done",
+    }
+}
@@ -0,0 +1,141 @@
+use crate::task::Task;
+
+// Result of a three-way merge between the list loaded from disk (base), the
+// list edited in horme (local) and the list currently on disk (remote)
+#[derive(Debug)]
+pub struct MergeResult {
+    pub tasks: Vec<Task>,
+    // Names of the tasks changed on both sides, where the local version was kept
+    pub conflicts: Vec<String>,
+}
+
+// Merge the changes made on disk into the local list, matching the tasks of
+// each side with those of the base list (see `pair_tasks`). Tasks changed on
+// only one side take that side's version, tasks changed on both sides keep
+// the local version and are reported as conflicts.
+pub fn merge_todo_lists(base: &[Task], local: &[Task], remote: &[Task]) -> MergeResult {
+    let local_pairs = pair_tasks(base, local);
+    let remote_pairs = pair_tasks(base, remote);
+    let base_of =
+        |pairs: &[Option<usize>], index: usize| pairs.iter().position(|pair| *pair == Some(index));
+
+    let mut tasks = vec![];
+    let mut conflicts = vec![];
+    let mut added_locally = vec![];
+
+    for (i, local_task) in local.iter().enumerate() {
+        let Some(b) = base_of(&local_pairs, i) else {
+            // Added locally
+            tasks.push(local_task.clone());
+            added_locally.push(local_task);
+            continue;
+        };
+        let base_task = &base[b];
+        match remote_pairs[b].map(|r| &remote[r]) {
+            Some(remote_task) => {
+                if local_task.same_content(base_task) {
+                    // Only changed on disk (or not at all)
+                    tasks.push(Task {
+                        id: local_task.id,
+                        ..remote_task.clone()
+                    });
+                } else {
+                    if !remote_task.same_content(base_task) && !remote_task.same_content(local_task)
+                    {
+                        conflicts.push(local_task.name.clone());
+                    }
+                    tasks.push(local_task.clone());
+                }
+            }
+            None => {
+                // Deleted on disk, keep it only if it was edited locally
+                if !local_task.same_content(base_task) {
+                    conflicts.push(local_task.name.clone());
+                    tasks.push(local_task.clone());
+                }
+            }
+        }
+    }
+
+    for (i, remote_task) in remote.iter().enumerate() {
+        match base_of(&remote_pairs, i) {
+            // Merged with its local version above
+            Some(b) if local_pairs[b].is_some() => {}
+            // Deleted locally, keep it only if it was edited on disk
+            Some(b) => {
+                if !remote_task.same_content(&base[b]) {
+                    conflicts.push(remote_task.name.clone());
+                    tasks.push(remote_task.clone());
+                }
+            }
+            // Added on disk, unless the same task was added locally
+            None => match added_locally
+                .iter()
+                .position(|task| task.same_content(remote_task))
+            {
+                Some(added) => {
+                    added_locally.remove(added);
+                }
+                None => tasks.push(remote_task.clone()),
+            },
+        }
+    }
+
+    MergeResult { tasks, conflicts }
+}
+
+// The index in `other` of the task each task of `base` became, if it is
+// still there. Tasks are matched by uid, then by id, which tasks keep while
+// horme runs, and then by name. Tasks of the same name are matched in order,
+// once those left as they were are set aside when some were added or removed.
+fn pair_tasks(base: &[Task], other: &[Task]) -> Vec<Option<usize>> {
+    let mut pairs: Vec<Option<usize>> = vec![None; base.len()];
+    let mut taken = vec![false; other.len()];
+
+    for (b, task) in base.iter().enumerate() {
+        let found =
+            (0..other.len()).find(|&o| !taken[o] && task.uid.is_some() && other[o].uid == task.uid);
+        let found =
+            found.or_else(|| (0..other.len()).find(|&o| !taken[o] && other[o].id == task.id));
+        if let Some(o) = found {
+            pairs[b] = Some(o);
+            taken[o] = true;
+        }
+    }
+
+    for b in 0..base.len() {
+        if pairs[b].is_some() {
+            continue;
+        }
+        let name = &base[b].name;
+        let base_group: Vec<usize> = (0..base.len())
+            .filter(|&i| pairs[i].is_none() && base[i].name == *name)
+            .collect();
+        let other_group: Vec<usize> = (0..other.len())
+            .filter(|&o| !taken[o] && other[o].name == *name)
+            .collect();
+        if base_group.len() != other_group.len() {
+            for &i in &base_group {
+                let same = other_group
+                    .iter()
+                    .copied()
+                    .find(|&o| !taken[o] && other[o].same_content(&base[i]));
+                if let Some(o) = same {
+                    pairs[i] = Some(o);
+                    taken[o] = true;
+                }
+            }
+        }
+        let rest: Vec<usize> = other_group.into_iter().filter(|&o| !taken[o]).collect();
+        let unpaired: Vec<usize> = base_group
+            .into_iter()
+            .filter(|&i| pairs[i].is_none())
+            .collect();
+        for (i, o) in unpaired.into_iter().zip(rest) {
+            pairs[i] = Some(o);
+            taken[o] = true;
+        }
+    }
+
+    pairs
+}
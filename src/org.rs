@@ -0,0 +1,524 @@
+use chrono::{NaiveDate, NaiveTime};
+use regex::Regex;
+
+use crate::markdown::{Diagnostic, Severity};
+use crate::recurrence::{Recurrence, Unit};
+use crate::task::{Status, Subtask, Task};
+
+// Keywords used when the file doesn't declare its own with `#+TODO:`
+const DEFAULT_KEYWORDS: &str = "TODO NEXT STARTED DOING WAITING | DONE CANCELLED CANCELED";
+
+// Keywords of the headlines that are tasks, split between the states that
+// are still open and those that are finished
+struct Keywords {
+    open: Vec<String>,
+    done: Vec<String>,
+}
+
+impl Keywords {
+    // Read `TODO NEXT | DONE`, or `TODO NEXT DONE` where the last one is done.
+    // Fast access keys like `TODO(t)` are dropped.
+    fn parse(sequence: &str) -> Self {
+        let words = |part: &str| -> Vec<String> {
+            part.split_whitespace()
+                .map(|word| word.split('(').next().unwrap_or(word).to_string())
+                .collect()
+        };
+        match sequence.split_once('|') {
+            Some((open, done)) => Self {
+                open: words(open),
+                done: words(done),
+            },
+            None => {
+                let mut open = words(sequence);
+                let done = open.pop().into_iter().collect();
+                Self { open, done }
+            }
+        }
+    }
+
+    // The first open keyword is to do, the others are in progress
+    fn status(&self, keyword: &str) -> Option<Status> {
+        if self.done.iter().any(|done| done == keyword) {
+            return Some(Status::Done);
+        }
+        match self.open.iter().position(|open| open == keyword)? {
+            0 => Some(Status::Todo),
+            _ => Some(Status::Doing),
+        }
+    }
+}
+
+// An active `<2026-11-01 Sun 09:00 +1w>` or inactive `[...]` timestamp
+struct Timestamp {
+    date: NaiveDate,
+    time: Option<NaiveTime>,
+    repeater: Option<Recurrence>,
+}
+
+// A headline: `** TODO [#A] Name   :tag:other:`
+struct Headline<'a> {
+    level: usize,
+    keyword: Option<&'a str>,
+    priority: Option<char>,
+    title: &'a str,
+    tags: Vec<String>,
+}
+
+// Read the tasks of an Org file. Headlines with a TODO keyword are tasks and
+// the headlines nested in them are their subtasks, the headlines without a
+// keyword group the tasks under them in a section. `DEADLINE:` gives the due
+// date, or `SCHEDULED:` without a deadline, and the `ID`, `CREATED`, `STARTED`
+// and `REPEAT` properties the fields that have no place in a headline.
+pub fn parse_org(contents: &str) -> (Vec<Task>, Vec<Diagnostic>) {
+    let headline_pattern = Regex::new(r"^(\*+)\s+(.*)$").unwrap();
+    let checkbox_pattern = Regex::new(r"^[-+] \[([ xX-])\] (.*)$").unwrap();
+    let planning_pattern =
+        Regex::new(r"(DEADLINE|SCHEDULED|CLOSED):\s*[<\[]([^>\]]*)[>\]]").unwrap();
+
+    let mut keywords = Keywords::parse(DEFAULT_KEYWORDS);
+    let mut todo_list: Vec<Task> = vec![];
+    let mut diagnostics = vec![];
+    // Headlines without a keyword enclosing the current line, with their level
+    let mut groups: Vec<(usize, String)> = vec![];
+    // Level of the task the current line belongs to
+    let mut task_level: Option<usize> = None;
+    let mut in_drawer = false;
+    // Set in the body of a subtask, which has nowhere to go
+    let mut in_subtask = false;
+    // Set once the current task has a deadline, which a scheduled date
+    // doesn't replace as the due date
+    let mut deadline = false;
+
+    for (index, line) in contents.lines().enumerate() {
+        let line_number = index + 1;
+        let content = line.trim();
+
+        if let Some(sequence) = content
+            .strip_prefix("#+TODO:")
+            .or_else(|| content.strip_prefix("#+SEQ_TODO:"))
+            .or_else(|| content.strip_prefix("#+TYP_TODO:"))
+        {
+            keywords = Keywords::parse(sequence);
+            continue;
+        }
+
+        if let Some(captures) = headline_pattern.captures(line) {
+            in_drawer = false;
+            let headline = parse_headline(
+                captures[1].len(),
+                captures.get(2).unwrap().as_str(),
+                &keywords,
+            );
+
+            // Headlines nested in a task are its subtasks
+            if let (Some(level), Some(task)) = (task_level, todo_list.last_mut()) {
+                if headline.level > level {
+                    let done = headline
+                        .keyword
+                        .and_then(|keyword| keywords.status(keyword))
+                        == Some(Status::Done);
+                    task.subtasks.push(Subtask {
+                        name: headline.title.to_string(),
+                        status: done,
+                    });
+                    in_subtask = true;
+                    continue;
+                }
+            }
+
+            in_subtask = false;
+            groups.retain(|(level, _)| *level < headline.level);
+            let status = headline
+                .keyword
+                .and_then(|keyword| keywords.status(keyword));
+            let Some(status) = status else {
+                groups.push((headline.level, headline.title.to_string()));
+                task_level = None;
+                continue;
+            };
+
+            if headline.title.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    1,
+                    Severity::Error,
+                    "task without a name".to_string(),
+                ));
+                task_level = None;
+                continue;
+            }
+            let mut task = Task::new(headline.title.to_string(), String::new(), status, None);
+            task.priority = headline.priority;
+            task.tags = headline.tags;
+            task.section = groups.last().map(|(_, title)| title.clone());
+            todo_list.push(task);
+            task_level = Some(headline.level);
+            deadline = false;
+            continue;
+        }
+
+        let task = todo_list.last_mut().filter(|_| task_level.is_some());
+        let Some(task) = task.filter(|_| !in_subtask) else {
+            if !content.is_empty() && !content.starts_with('#') {
+                let message = if in_subtask {
+                    "text of a subtask is ignored"
+                } else {
+                    "text outside of a task is ignored"
+                };
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    1,
+                    Severity::Warning,
+                    message.to_string(),
+                ));
+            }
+            continue;
+        };
+
+        if in_drawer {
+            in_drawer = content != ":END:";
+            if let Err(message) = parse_drawer_property(task, content) {
+                diagnostics.push(Diagnostic::new(
+                    line_number,
+                    line.len() - line.trim_start().len() + 1,
+                    Severity::Warning,
+                    message,
+                ));
+            }
+            continue;
+        }
+        // Lines of the description that would be read as something else
+        // are escaped with a comma
+        if let Some(text) = content.strip_prefix(',') {
+            if !task.description.is_empty() {
+                task.description.push('\n');
+            }
+            task.description.push_str(text);
+            continue;
+        }
+        if content.starts_with(':') && content.ends_with(':') && content.len() > 1 {
+            // Drawers, `:PROPERTIES:` to `:END:`
+            in_drawer = true;
+            continue;
+        }
+
+        if planning_pattern.is_match(content) {
+            for captures in planning_pattern.captures_iter(content) {
+                let Some(timestamp) = parse_timestamp(&captures[2]) else {
+                    let indent = line.len() - line.trim_start().len();
+                    diagnostics.push(Diagnostic::new(
+                        line_number,
+                        indent + captures.get(2).unwrap().start() + 1,
+                        Severity::Warning,
+                        format!("invalid timestamp \"{}\"", &captures[2]),
+                    ));
+                    continue;
+                };
+                match &captures[1] {
+                    "DEADLINE" => {
+                        task.due_date = Some(timestamp.date);
+                        task.due_time = timestamp.time;
+                        deadline = true;
+                        if timestamp.repeater.is_some() {
+                            task.recurrence = timestamp.repeater;
+                        }
+                    }
+                    "SCHEDULED" => {
+                        task.scheduled = Some(timestamp.date);
+                        // Only used as the due date when there is no deadline
+                        if !deadline {
+                            task.due_date = Some(timestamp.date);
+                            task.due_time = timestamp.time;
+                        }
+                        if task.recurrence.is_none() {
+                            task.recurrence = timestamp.repeater;
+                        }
+                    }
+                    _ => {
+                        let time = timestamp.time.unwrap_or_default();
+                        task.completed_at = Some(timestamp.date.and_time(time));
+                    }
+                }
+            }
+        } else if let Some(captures) = checkbox_pattern.captures(content) {
+            task.subtasks.push(Subtask {
+                name: captures[2].to_string(),
+                status: &captures[1] != " ",
+            });
+        } else if !content.is_empty() {
+            if !task.description.is_empty() {
+                task.description.push('\n');
+            }
+            task.description.push_str(content);
+        }
+    }
+
+    (todo_list, diagnostics)
+}
+
+// Write the tasks as Org headlines, grouped under a headline for each section
+pub fn render_org(todo_list: &[Task]) -> String {
+    let mut data = String::from("#+TODO: TODO DOING | DONE\n\n");
+
+    let mut sections: Vec<Option<&str>> = vec![];
+    for task in todo_list {
+        if !sections.contains(&task.section.as_deref()) {
+            sections.push(task.section.as_deref());
+        }
+    }
+    // Tasks without a section come first, at the top level
+    sections.sort_by_key(|section| section.is_some());
+
+    for section in sections {
+        let level = match section {
+            Some(title) => {
+                data.push_str(&format!("* {}\n", title));
+                2
+            }
+            None => 1,
+        };
+        for task in todo_list
+            .iter()
+            .filter(|task| task.section.as_deref() == section)
+        {
+            render_headline(task, level, &mut data);
+        }
+    }
+    data
+}
+
+fn render_headline(task: &Task, level: usize, data: &mut String) {
+    let stars = "*".repeat(level);
+    let keyword = match task.status {
+        Status::Todo => "TODO",
+        Status::Doing => "DOING",
+        Status::Done => "DONE",
+    };
+    let mut headline = format!("{} {} ", stars, keyword);
+    if let Some(priority) = task.priority {
+        headline.push_str(&format!("[#{}] ", priority));
+    }
+    headline.push_str(&task.name);
+    if !task.tags.is_empty() {
+        let tags: Vec<String> = task.tags.iter().map(|tag| org_tag(tag)).collect();
+        headline.push_str(&format!(" :{}:", tags.join(":")));
+    }
+    data.push_str(&headline);
+    data.push('\n');
+
+    let indent = " ".repeat(level + 1);
+    // Recurrences on given days of the week have no repeater
+    let repeater = task.recurrence.as_ref().and_then(org_repeater);
+    // A due date on the scheduled day is read back from SCHEDULED alone
+    let deadline = task.due_date.filter(|date| task.scheduled != Some(*date));
+    let mut planning = vec![];
+    if let Some(date) = task.completed_at {
+        planning.push(format!("CLOSED: [{}]", date.format("%Y-%m-%d %a %H:%M")));
+    }
+    if let Some(date) = deadline {
+        planning.push(format!(
+            "DEADLINE: {}",
+            format_timestamp(date, task.due_time, repeater.as_deref())
+        ));
+    }
+    if let Some(date) = task.scheduled {
+        let time = deadline.is_none().then_some(task.due_time).flatten();
+        let repeater = deadline.is_none().then_some(repeater.as_deref()).flatten();
+        planning.push(format!(
+            "SCHEDULED: {}",
+            format_timestamp(date, time, repeater)
+        ));
+    }
+    if !planning.is_empty() {
+        data.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+    }
+
+    let mut properties = vec![];
+    if let Some(uid) = &task.uid {
+        properties.push(format!(":ID: {}", uid));
+    }
+    if let Some(date) = task.created_at {
+        properties.push(format!(":CREATED: [{}]", date.format("%Y-%m-%d %a %H:%M")));
+    }
+    if let Some(date) = task.started_at {
+        properties.push(format!(":STARTED: [{}]", date.format("%Y-%m-%d %a %H:%M")));
+    }
+    if let Some(recurrence) = &task.recurrence {
+        if repeater.is_none() || (deadline.is_none() && task.scheduled.is_none()) {
+            properties.push(format!(":REPEAT: {}", recurrence));
+        }
+    }
+    if !properties.is_empty() {
+        data.push_str(&format!("{}:PROPERTIES:\n", indent));
+        for property in properties {
+            data.push_str(&format!("{}{}\n", indent, property));
+        }
+        data.push_str(&format!("{}:END:\n", indent));
+    }
+
+    for line in task.description.lines() {
+        let content = line.trim();
+        let escape = if needs_escape(content) { "," } else { "" };
+        data.push_str(&format!("{}{}{}\n", indent, escape, content));
+    }
+    for subtask in &task.subtasks {
+        let keyword = if subtask.status { "DONE" } else { "TODO" };
+        data.push_str(&format!("{}* {} {}\n", stars, keyword, subtask.name));
+    }
+}
+
+// Whether a line of a description would be read as a headline, a keyword, a
+// drawer, a planning line or a checkbox, or starts with the escaping comma
+fn needs_escape(line: &str) -> bool {
+    let checkbox_pattern = Regex::new(r"^[-+] \[[ xX-]\] ").unwrap();
+    line.starts_with(['*', '#', ':', ','])
+        || checkbox_pattern.is_match(line)
+        || ["DEADLINE:", "SCHEDULED:", "CLOSED:"]
+            .iter()
+            .any(|keyword| line.contains(keyword))
+}
+
+// `<2026-11-01 Sun 09:00 +1w>`
+fn format_timestamp(date: NaiveDate, time: Option<NaiveTime>, repeater: Option<&str>) -> String {
+    let mut timestamp = date.format("%Y-%m-%d %a").to_string();
+    if let Some(time) = time {
+        timestamp.push_str(&time.format(" %H:%M").to_string());
+    }
+    if let Some(repeater) = repeater {
+        timestamp.push(' ');
+        timestamp.push_str(repeater);
+    }
+    format!("<{}>", timestamp)
+}
+
+// `+1w` for every week
+fn org_repeater(recurrence: &Recurrence) -> Option<String> {
+    let Recurrence::Every(count, unit) = recurrence else {
+        return None;
+    };
+    let unit = match unit {
+        Unit::Day => 'd',
+        Unit::Week => 'w',
+        Unit::Month => 'm',
+        Unit::Year => 'y',
+    };
+    Some(format!("+{}{}", count, unit))
+}
+
+// Read a `:KEY: value` line of a property drawer into the task
+fn parse_drawer_property(task: &mut Task, line: &str) -> Result<(), String> {
+    let Some((key, value)) = line.strip_prefix(':').and_then(|line| line.split_once(':')) else {
+        return Ok(());
+    };
+    let value = value.trim();
+    let date = || {
+        let timestamp = value
+            .strip_prefix('[')
+            .and_then(|value| value.strip_suffix(']'))
+            .and_then(parse_timestamp)
+            .ok_or_else(|| format!("invalid timestamp \"{}\"", value))?;
+        Ok::<_, String>(timestamp.date.and_time(timestamp.time.unwrap_or_default()))
+    };
+    match key.to_uppercase().as_str() {
+        "ID" if !value.is_empty() => task.uid = Some(value.to_string()),
+        "CREATED" => task.created_at = Some(date()?),
+        "STARTED" => task.started_at = Some(date()?),
+        "REPEAT" => task.recurrence = Some(value.parse()?),
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_headline<'a>(level: usize, text: &'a str, keywords: &Keywords) -> Headline<'a> {
+    let mut text = text.trim();
+
+    let mut tags = vec![];
+    if let Some(start) = text.trim_end().rfind(" :") {
+        let candidate = text[start + 1..].trim_end();
+        if candidate.len() > 1 && candidate.ends_with(':') && !candidate.contains(' ') {
+            tags = candidate
+                .split(':')
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+            text = text[..start].trim_end();
+        }
+    }
+
+    let keyword = text
+        .split_whitespace()
+        .next()
+        .filter(|word| keywords.status(word).is_some());
+    if let Some(keyword) = keyword {
+        text = text[keyword.len()..].trim_start();
+    }
+
+    let mut priority = None;
+    if let Some(rest) = text.strip_prefix("[#") {
+        let mut chars = rest.chars();
+        if let (Some(letter), Some(']')) = (chars.next(), chars.next()) {
+            if letter.is_ascii_uppercase() {
+                priority = Some(letter);
+                text = rest[2..].trim_start();
+            }
+        }
+    }
+
+    Headline {
+        level,
+        keyword,
+        priority,
+        title: text,
+        tags,
+    }
+}
+
+// `2026-11-01 Sun` or `2026-10-01 Wed 10:00`, with an optional repeater
+// like `+1w`, `++1w` or `.+1w`
+fn parse_timestamp(timestamp: &str) -> Option<Timestamp> {
+    let mut words = timestamp.split_whitespace();
+    let date = NaiveDate::parse_from_str(words.next()?, "%Y-%m-%d").ok()?;
+    let mut time = None;
+    let mut repeater = None;
+    for word in words {
+        if word.contains(':') {
+            // A time range `09:00-10:00` starts at its first time
+            let start = word.split('-').next().unwrap_or(word);
+            time = NaiveTime::parse_from_str(start, "%H:%M").ok();
+        } else if word.starts_with(['+', '.']) {
+            repeater = parse_repeater(word.trim_start_matches(['+', '.']));
+        }
+    }
+    Some(Timestamp {
+        date,
+        time,
+        repeater,
+    })
+}
+
+// `1w` of a repeater, hourly repeaters have no recurrence
+fn parse_repeater(interval: &str) -> Option<Recurrence> {
+    let unit = match interval.chars().last()? {
+        'd' => Unit::Day,
+        'w' => Unit::Week,
+        'm' => Unit::Month,
+        'y' => Unit::Year,
+        _ => return None,
+    };
+    let count: u32 = interval[..interval.len() - 1].parse().ok()?;
+    (count > 0).then_some(Recurrence::Every(count, unit))
+}
+
+// Org tags only contain letters, numbers and `_@#%`
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "_@#%".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
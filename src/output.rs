@@ -0,0 +1,241 @@
+use color_eyre::Result;
+use crossterm::terminal;
+use std::io::{self, IsTerminal};
+
+use crate::json::render_json_document;
+use crate::markdown::{render_todo_list, section_for};
+use crate::task::{Status, Task, DATE_FORMAT, DATE_TIME_FORMAT};
+
+// Narrowest the name column gets when the table is fitted to the terminal
+const MIN_NAME_WIDTH: usize = 12;
+
+// How list commands print tasks, `--format <name>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    // Aligned columns fitted to the terminal, for humans
+    #[default]
+    Table,
+    // Tab-separated fields without a header, for `cut` and `awk`
+    Plain,
+    // The JSON document of `horme export`
+    Json,
+    // The markdown list format
+    Markdown,
+    // A header line and a row for each task
+    Csv,
+}
+
+impl OutputFormat {
+    pub const NAMES: [&'static str; 5] = ["table", "plain", "json", "markdown", "csv"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "table" => Some(OutputFormat::Table),
+            "plain" => Some(OutputFormat::Plain),
+            "json" => Some(OutputFormat::Json),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "csv" => Some(OutputFormat::Csv),
+            _ => None,
+        }
+    }
+
+    // Write the tasks, the table being fitted to `width` columns when given
+    pub fn render(self, tasks: &[Task], width: Option<usize>) -> Result<String> {
+        match self {
+            OutputFormat::Table => Ok(render_table(tasks, width)),
+            OutputFormat::Plain => Ok(render_plain(tasks)),
+            OutputFormat::Json => render_json_document(tasks),
+            OutputFormat::Markdown => {
+                let sections = [Status::Todo, Status::Doing, Status::Done]
+                    .map(|status| section_for(status).to_string());
+                Ok(render_todo_list(tasks, &sections))
+            }
+            OutputFormat::Csv => render_csv(tasks),
+        }
+    }
+}
+
+// Print the tasks to stdout, fitting the table to the terminal if there is one
+pub fn print_tasks(tasks: &[Task], format: OutputFormat) -> Result<()> {
+    if tasks.is_empty() && format == OutputFormat::Table {
+        println!("No tasks found");
+        return Ok(());
+    }
+    let width = io::stdout()
+        .is_terminal()
+        .then(|| terminal::size().ok())
+        .flatten()
+        .map(|(columns, _)| usize::from(columns));
+    print!("{}", format.render(tasks, width)?);
+    Ok(())
+}
+
+// The fields shown for each task, in the order of the columns
+fn fields(task: &Task) -> [String; 7] {
+    let done = task
+        .subtasks
+        .iter()
+        .filter(|subtask| subtask.status)
+        .count();
+    [
+        task.id.to_string(),
+        task.status.to_string().to_lowercase(),
+        task.priority.map(String::from).unwrap_or_default(),
+        due(task),
+        task.tags.join(" "),
+        if task.subtasks.is_empty() {
+            String::new()
+        } else {
+            format!("{}/{}", done, task.subtasks.len())
+        },
+        task.name.clone(),
+    ]
+}
+
+fn due(task: &Task) -> String {
+    let Some(date) = task.due_date else {
+        return String::new();
+    };
+    let mut due = date.format(DATE_FORMAT).to_string();
+    if let Some(time) = task.due_time {
+        due.push_str(&format!(" {}", time.format("%H:%M")));
+    }
+    if task.recurrence.is_some() {
+        due.push_str(" ↻");
+    }
+    due
+}
+
+fn render_table(tasks: &[Task], width: Option<usize>) -> String {
+    const HEADERS: [&str; 7] = ["ID", "STATUS", "PRI", "DUE", "TAGS", "SUBTASKS", "NAME"];
+    const NAME: usize = 6;
+    const TAGS: usize = 4;
+
+    let rows: Vec<[String; 7]> = tasks.iter().map(fields).collect();
+    let mut widths = HEADERS.map(|header| header.chars().count());
+    for row in &rows {
+        for (width, field) in widths.iter_mut().zip(row) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    // Columns empty for every task are left out, except the id, status and name
+    let shown: Vec<usize> = (0..HEADERS.len())
+        .filter(|column| {
+            matches!(*column, 0 | 1 | NAME) || rows.iter().any(|row| !row[*column].is_empty())
+        })
+        .collect();
+
+    // Narrow the names, then the tags, until the table fits
+    if let Some(width) = width {
+        let total = |widths: &[usize; 7]| -> usize {
+            shown.iter().map(|column| widths[*column]).sum::<usize>() + 2 * (shown.len() - 1)
+        };
+        let excess = total(&widths).saturating_sub(width);
+        let name_slack = widths[NAME].saturating_sub(MIN_NAME_WIDTH);
+        widths[NAME] -= excess.min(name_slack);
+        let excess = total(&widths).saturating_sub(width);
+        if shown.contains(&TAGS) {
+            widths[TAGS] -= excess.min(widths[TAGS].saturating_sub(HEADERS[TAGS].len()));
+        }
+    }
+
+    let mut data = String::new();
+    let header = HEADERS.map(str::to_string);
+    for row in std::iter::once(&header).chain(&rows) {
+        let cells: Vec<String> = shown
+            .iter()
+            .map(|column| {
+                let cell = truncate(&row[*column], widths[*column]);
+                if *column == 0 {
+                    format!("{:>width$}", cell, width = widths[*column])
+                } else {
+                    format!("{:<width$}", cell, width = widths[*column])
+                }
+            })
+            .collect();
+        data.push_str(cells.join("  ").trim_end());
+        data.push('\n');
+    }
+    data
+}
+
+// Cut text to `width` characters, ending with an ellipsis when it was cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut text: String = text.chars().take(width.saturating_sub(1)).collect();
+    text.push('…');
+    text
+}
+
+fn render_plain(tasks: &[Task]) -> String {
+    tasks
+        .iter()
+        .map(|task| {
+            // Tabs and line breaks would break the fields
+            let fields = fields(task).map(|field| field.replace(['\t', '\n'], " "));
+            format!("{}\n", fields.join("\t"))
+        })
+        .collect()
+}
+
+fn render_csv(tasks: &[Task]) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record([
+        "id",
+        "status",
+        "name",
+        "description",
+        "priority",
+        "due_date",
+        "due_time",
+        "scheduled",
+        "recurrence",
+        "tags",
+        "section",
+        "subtasks_done",
+        "subtasks",
+        "created_at",
+        "started_at",
+        "completed_at",
+    ])?;
+    for task in tasks {
+        let date_time = |date: Option<chrono::NaiveDateTime>| {
+            date.map(|date| date.format(DATE_TIME_FORMAT).to_string())
+                .unwrap_or_default()
+        };
+        writer.write_record([
+            task.id.to_string(),
+            task.status.to_string().to_lowercase(),
+            task.name.clone(),
+            task.description.clone(),
+            task.priority.map(String::from).unwrap_or_default(),
+            task.due_date
+                .map(|date| date.format(DATE_FORMAT).to_string())
+                .unwrap_or_default(),
+            task.due_time
+                .map(|time| time.format("%H:%M").to_string())
+                .unwrap_or_default(),
+            task.scheduled
+                .map(|date| date.format(DATE_FORMAT).to_string())
+                .unwrap_or_default(),
+            task.recurrence
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            task.tags.join(" "),
+            task.section.clone().unwrap_or_default(),
+            task.subtasks
+                .iter()
+                .filter(|subtask| subtask.status)
+                .count()
+                .to_string(),
+            task.subtasks.len().to_string(),
+            date_time(task.created_at),
+            date_time(task.started_at),
+            date_time(task.completed_at),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
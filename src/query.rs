@@ -0,0 +1,444 @@
+use chrono::{Days, Months, NaiveDate};
+use serde::Deserialize;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::checklist::lane_status;
+use crate::quickadd::parse_priority;
+use crate::task::{Status, Task, DATE_FORMAT};
+use crate::view::SmartView;
+
+// Fields that can be filtered on, for error messages
+const FIELDS: &str = "status, tag, priority, section, due, created, done, id, is";
+
+// Filter on the tasks of a list, a list of terms that must all match:
+//
+//     status:todo,doing tag:infra due<7d -tag:someday "release notes"
+//
+// A term is `field:value` (values separated by commas match any of them),
+// `field<value` for dates (also `<=`, `>`, `>=`), or text looked up in the
+// name and description. A leading `-` negates a term and double quotes keep
+// text together.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    negated: bool,
+    condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Text(String),
+    Status(Vec<Status>),
+    Tag(Vec<String>),
+    // `None` for the tasks without a priority
+    Priority(Vec<Option<char>>),
+    Section(Vec<String>),
+    Id(Vec<u16>),
+    Is(SmartView),
+    Due(DateCondition),
+    Created(DateCondition),
+    Done(DateCondition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateCondition {
+    // The date is set, `due:any`
+    Any,
+    // The date isn't set, `due:none`
+    Missing,
+    Compare(Comparison, DateValue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    Before,
+    BeforeOrOn,
+    After,
+    AfterOrOn,
+}
+
+// A date, relative ones are resolved when the query is matched
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateValue {
+    Date(NaiveDate),
+    // `today`, `tomorrow`, `7d`, `-2w`...
+    Days(i64),
+    // `1m`, `-3m`
+    Months(i32),
+}
+
+// Why a query couldn't be read, at a column of the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryError {
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+impl QueryError {
+    // The error with the query and a caret under the column it was found at
+    pub fn pointing_at(&self, input: &str) -> String {
+        format!(
+            "Invalid query: {}\n  {}\n  {}^",
+            self.message,
+            input,
+            " ".repeat(self.column.saturating_sub(1))
+        )
+    }
+}
+
+// A word of the query with the column it starts at
+struct Token {
+    column: usize,
+    text: String,
+    // The whole word was between double quotes
+    quoted: bool,
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let terms = tokenize(input)?
+            .into_iter()
+            .map(parse_term)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { terms })
+    }
+
+    // A query without terms matches every task
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    // Whether a term filters on a field, `status:done` rather than text
+    pub fn has_field_terms(&self) -> bool {
+        self.terms
+            .iter()
+            .any(|term| !matches!(term.condition, Condition::Text(_)))
+    }
+
+    pub fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        self.terms
+            .iter()
+            .all(|term| term.condition.matches(task, today) != term.negated)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+impl TryFrom<String> for Query {
+    type Error = QueryError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        Self::parse(&input)
+    }
+}
+
+impl Condition {
+    fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        match self {
+            Condition::Text(text) => {
+                let text = text.to_lowercase();
+                task.name.to_lowercase().contains(&text)
+                    || task.description.to_lowercase().contains(&text)
+            }
+            Condition::Status(statuses) => statuses.contains(&task.status),
+            Condition::Tag(tags) => task
+                .tags
+                .iter()
+                .any(|tag| tags.iter().any(|other| tag.eq_ignore_ascii_case(other))),
+            Condition::Priority(priorities) => priorities.contains(&task.priority),
+            Condition::Section(sections) => {
+                let section = match &task.section {
+                    Some(section) => section.clone(),
+                    None => task.status.to_string(),
+                };
+                sections
+                    .iter()
+                    .any(|other| section.eq_ignore_ascii_case(other))
+            }
+            Condition::Id(ids) => ids.contains(&task.id),
+            Condition::Is(view) => view.matches(task, today),
+            Condition::Due(condition) => condition.matches(task.due_date, today),
+            Condition::Created(condition) => {
+                condition.matches(task.created_at.map(|date| date.date()), today)
+            }
+            Condition::Done(condition) => {
+                condition.matches(task.completed_at.map(|date| date.date()), today)
+            }
+        }
+    }
+}
+
+impl DateCondition {
+    fn matches(self, date: Option<NaiveDate>, today: NaiveDate) -> bool {
+        match (self, date) {
+            (DateCondition::Any, date) => date.is_some(),
+            (DateCondition::Missing, date) => date.is_none(),
+            (DateCondition::Compare(comparison, value), Some(date)) => {
+                let Some(value) = value.resolve(today) else {
+                    return false;
+                };
+                match comparison {
+                    Comparison::Equal => date == value,
+                    Comparison::Before => date < value,
+                    Comparison::BeforeOrOn => date <= value,
+                    Comparison::After => date > value,
+                    Comparison::AfterOrOn => date >= value,
+                }
+            }
+            (DateCondition::Compare(..), None) => false,
+        }
+    }
+}
+
+impl DateValue {
+    fn parse(value: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "invalid date \"{}\", expected YYYY-MM-DD, today, tomorrow or a number of days like 7d",
+                value
+            )
+        };
+        match value {
+            "today" => return Ok(DateValue::Days(0)),
+            "tomorrow" => return Ok(DateValue::Days(1)),
+            "yesterday" => return Ok(DateValue::Days(-1)),
+            _ => {}
+        }
+        if let Ok(date) = NaiveDate::parse_from_str(value, DATE_FORMAT) {
+            return Ok(DateValue::Date(date));
+        }
+        // `7d`, `2w`, `1m`, `1y`, in the past with a `-`
+        let unit = value.chars().last().ok_or_else(invalid)?;
+        let count: i64 = value[..value.len() - unit.len_utf8()]
+            .parse()
+            .map_err(|_| invalid())?;
+        let too_far = || format!("date \"{}\" is too far away", value);
+        match unit {
+            'd' => Ok(DateValue::Days(count)),
+            'w' => Ok(DateValue::Days(count.checked_mul(7).ok_or_else(too_far)?)),
+            'm' => Ok(DateValue::Months(
+                i32::try_from(count).map_err(|_| too_far())?,
+            )),
+            'y' => Ok(DateValue::Months(
+                count
+                    .checked_mul(12)
+                    .and_then(|months| i32::try_from(months).ok())
+                    .ok_or_else(too_far)?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+
+    fn resolve(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            DateValue::Date(date) => Some(date),
+            DateValue::Days(days) if days < 0 => {
+                today.checked_sub_days(Days::new(days.unsigned_abs()))
+            }
+            DateValue::Days(days) => today.checked_add_days(Days::new(days.unsigned_abs())),
+            DateValue::Months(months) if months < 0 => {
+                today.checked_sub_months(Months::new(months.unsigned_abs()))
+            }
+            DateValue::Months(months) => {
+                today.checked_add_months(Months::new(months.unsigned_abs()))
+            }
+        }
+    }
+}
+
+// Split the query in words, keeping the text between double quotes together
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens: Vec<Token> = vec![];
+    let mut current: Option<Token> = None;
+    let mut quote_column = None;
+
+    for (index, c) in input.chars().enumerate() {
+        let column = index + 1;
+        if c.is_whitespace() && quote_column.is_none() {
+            tokens.extend(current.take());
+            continue;
+        }
+        // A word is quoted when it starts with a quote and has no text outside quotes
+        let token = current.get_or_insert(Token {
+            column,
+            text: String::new(),
+            quoted: c == '"',
+        });
+        if c == '"' {
+            quote_column = match quote_column {
+                Some(_) => None,
+                None => Some(column),
+            };
+        } else {
+            token.quoted &= quote_column.is_some();
+            token.text.push(c);
+        }
+    }
+    if let Some(column) = quote_column {
+        return Err(QueryError {
+            column,
+            message: "missing closing quote".to_string(),
+        });
+    }
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+fn parse_term(token: Token) -> Result<Term, QueryError> {
+    let error = |offset: usize, message: String| QueryError {
+        column: token.column + offset,
+        message,
+    };
+    if token.quoted {
+        return Ok(Term {
+            negated: false,
+            condition: Condition::Text(token.text),
+        });
+    }
+
+    let (negated, text) = match token.text.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token.text.as_str()),
+    };
+    let offset = usize::from(negated);
+
+    // `field:value`, or text when the word doesn't start with a field name
+    let field_length = text
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(text.len());
+    let (field, rest) = text.split_at(field_length);
+    let operator = [">=", "<=", ":", "<", ">", "="]
+        .into_iter()
+        .find(|operator| rest.starts_with(operator));
+    let (Some(operator), false) = (operator, field.is_empty()) else {
+        return Ok(Term {
+            negated,
+            condition: Condition::Text(text.to_string()),
+        });
+    };
+    let value = &rest[operator.len()..];
+    let value_offset = offset + field.len() + operator.len();
+    if value.is_empty() {
+        return Err(error(
+            value_offset,
+            format!("missing value after \"{}{}\"", field, operator),
+        ));
+    }
+
+    let field = field.to_lowercase();
+    let date_field = matches!(field.as_str(), "due" | "created" | "done" | "completed");
+    if !matches!(operator, ":" | "=") && !date_field {
+        return Err(error(
+            offset + field.len(),
+            format!("\"{}\" can only compare dates", operator),
+        ));
+    }
+
+    let column = token.column + value_offset;
+
+    let condition = match field.as_str() {
+        "status" => Condition::Status(parse_list(
+            value,
+            column,
+            "status, expected todo, doing or done",
+            &lane_status,
+        )?),
+        "tag" | "tags" => Condition::Tag(value.split(',').map(str::to_string).collect()),
+        "priority" | "pri" => Condition::Priority(parse_list(
+            value,
+            column,
+            "priority, expected A to Z, high, medium, low or none",
+            &|priority: &str| match priority {
+                "none" => Some(None),
+                priority => parse_priority(priority).map(Some),
+            },
+        )?),
+        "section" => Condition::Section(value.split(',').map(str::to_string).collect()),
+        "id" => Condition::Id(parse_list(value, column, "id", &|id: &str| {
+            id.parse().ok()
+        })?),
+        "is" => Condition::Is(SmartView::from_name(value).ok_or_else(|| {
+            error(
+                value_offset,
+                format!(
+                    "unknown view \"{}\", expected today, upcoming, overdue or someday",
+                    value
+                ),
+            )
+        })?),
+        "due" | "created" | "done" | "completed" => {
+            let comparison = match operator {
+                "<" => Comparison::Before,
+                "<=" => Comparison::BeforeOrOn,
+                ">" => Comparison::After,
+                ">=" => Comparison::AfterOrOn,
+                _ => Comparison::Equal,
+            };
+            let condition = match (value.to_lowercase().as_str(), comparison) {
+                ("any", Comparison::Equal) => DateCondition::Any,
+                ("none", Comparison::Equal) => DateCondition::Missing,
+                (value, _) => match DateValue::parse(value) {
+                    Ok(date) => DateCondition::Compare(comparison, date),
+                    Err(message) => return Err(error(value_offset, message)),
+                },
+            };
+            match field.as_str() {
+                "due" => Condition::Due(condition),
+                "created" => Condition::Created(condition),
+                _ => Condition::Done(condition),
+            }
+        }
+        _ => {
+            return Err(error(
+                offset,
+                format!("unknown field \"{}\", expected one of: {}", field, FIELDS),
+            ))
+        }
+    };
+    Ok(Term { negated, condition })
+}
+
+// Values separated by commas, each read by `parse` in lower case
+fn parse_list<T>(
+    value: &str,
+    column: usize,
+    kind: &str,
+    parse: &dyn Fn(&str) -> Option<T>,
+) -> Result<Vec<T>, QueryError> {
+    let mut values = vec![];
+    let mut column = column;
+    for item in value.split(',') {
+        match parse(&item.to_lowercase()) {
+            Some(parsed) => values.push(parsed),
+            None => {
+                return Err(QueryError {
+                    column,
+                    message: format!("invalid {} \"{}\"", kind, item),
+                })
+            }
+        }
+        column += item.chars().count() + 1;
+    }
+    Ok(values)
+}
@@ -0,0 +1,331 @@
+use chrono::{Datelike, Days, Months, NaiveDate, NaiveTime, Weekday};
+
+use crate::recurrence::{parse_weekday, weekday_name, Recurrence};
+use crate::task::{Status, Task, DATE_FORMAT};
+
+// What was recognised in the name typed for a new task, like
+// `Deploy nginx tomorrow 9am #infra !high every monday`. Words between double
+// quotes are always part of the name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuickAdd {
+    pub name: String,
+    pub due_date: Option<NaiveDate>,
+    pub due_time: Option<NaiveTime>,
+    pub tags: Vec<String>,
+    pub priority: Option<char>,
+    pub recurrence: Option<Recurrence>,
+}
+
+// A word of the input, `literal` when it was quoted
+struct Word<'a> {
+    text: &'a str,
+    literal: bool,
+}
+
+// Extract the due date, tags, priority and recurrence from the name of a task,
+// dates being relative to `today`
+pub fn parse_quick_add(input: &str, today: NaiveDate) -> QuickAdd {
+    let words = split_words(input);
+    let mut quick_add = QuickAdd::default();
+    let mut name = vec![];
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = &words[i];
+        let lower = word.text.to_lowercase();
+        if word.literal {
+            name.push(word.text);
+            i += 1;
+            continue;
+        }
+
+        if let Some(tag) = word.text.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+            quick_add.tags.push(tag.to_string());
+            i += 1;
+            continue;
+        }
+        if let Some(priority) = lower.strip_prefix('!').and_then(parse_priority) {
+            if quick_add.priority.is_none() {
+                quick_add.priority = Some(priority);
+                i += 1;
+                continue;
+            }
+        }
+        if quick_add.recurrence.is_none() {
+            if let Some((recurrence, used)) = parse_recurrence(&words[i..]) {
+                quick_add.recurrence = Some(recurrence);
+                i += used;
+                continue;
+            }
+        }
+        if quick_add.due_date.is_none() {
+            if let Some((date, used)) = parse_date(&words[i..], today) {
+                quick_add.due_date = Some(date);
+                i += used;
+                continue;
+            }
+        }
+        if quick_add.due_time.is_none() {
+            if let Some((time, used)) = parse_time(&words[i..]) {
+                quick_add.due_time = Some(time);
+                i += used;
+                continue;
+            }
+        }
+
+        name.push(word.text);
+        i += 1;
+    }
+
+    // A task that repeats or has a time is due from its first occurrence
+    if quick_add.due_date.is_none() {
+        quick_add.due_date = match &quick_add.recurrence {
+            Some(Recurrence::On(days)) if !days.contains(&today.weekday()) => quick_add
+                .recurrence
+                .as_ref()
+                .map(|recurrence| recurrence.next_date(today)),
+            Some(_) => Some(today),
+            None => quick_add.due_time.map(|_| today),
+        };
+    }
+    quick_add.name = name.join(" ");
+    quick_add
+}
+
+impl QuickAdd {
+    // The task described, still to do
+    pub fn into_task(self, description: String) -> Task {
+        let mut task = Task::new(self.name, description, Status::Todo, None);
+        task.due_date = self.due_date;
+        task.due_time = self.due_time;
+        task.tags = self.tags;
+        task.priority = self.priority;
+        task.recurrence = self.recurrence;
+        task
+    }
+
+    // What was recognised besides the name, to show it before the task is added
+    pub fn describe(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![];
+        if let Some(date) = self.due_date {
+            let mut due = format!("{} {}", date.format("%a"), date.format(DATE_FORMAT));
+            if let Some(time) = self.due_time {
+                due.push_str(&format!(" {}", time.format("%H:%M")));
+            }
+            fields.push(("Due", due));
+        }
+        if let Some(recurrence) = &self.recurrence {
+            fields.push(("Repeats", recurrence.to_string()));
+        }
+        if let Some(priority) = self.priority {
+            fields.push(("Priority", priority.to_string()));
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|tag| format!("#{}", tag)).collect();
+            fields.push(("Tags", tags.join(" ")));
+        }
+        fields
+    }
+}
+
+fn split_words(input: &str) -> Vec<Word<'_>> {
+    let mut words = vec![];
+    let mut rest = input.trim_start();
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            words.push(Word {
+                text: &quoted[..end],
+                literal: true,
+            });
+            rest = quoted.get(end + 1..).unwrap_or("");
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            words.push(Word {
+                text: &rest[..end],
+                literal: false,
+            });
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    words
+}
+
+// `!high`, `!2`, `!B`...
+pub(crate) fn parse_priority(priority: &str) -> Option<char> {
+    match priority {
+        "high" | "h" | "1" => Some('A'),
+        "medium" | "med" | "m" | "2" => Some('B'),
+        "low" | "l" | "3" => Some('C'),
+        _ if priority.len() == 1 => priority
+            .chars()
+            .next()
+            .map(|letter| letter.to_ascii_uppercase())
+            .filter(char::is_ascii_uppercase),
+        _ => None,
+    }
+}
+
+// `daily`, `every 2 weeks`, `every monday and thursday`... Returns the
+// recurrence and the number of words it took.
+fn parse_recurrence(words: &[Word]) -> Option<(Recurrence, usize)> {
+    let first = words.first().filter(|word| !word.literal)?;
+    if first.text.eq_ignore_ascii_case("every") {
+        // The longest phrase that reads as a recurrence
+        (2..=words.len().min(5)).rev().find_map(|count| {
+            if words[..count].iter().any(|word| word.literal) {
+                return None;
+            }
+            let phrase: Vec<&str> = words[..count].iter().map(|word| word.text).collect();
+            let recurrence = phrase.join(" ").parse().ok()?;
+            Some((recurrence, count))
+        })
+    } else {
+        match first.text.to_lowercase().as_str() {
+            "daily" | "weekly" | "monthly" | "yearly" => Some((first.text.parse().ok()?, 1)),
+            _ => None,
+        }
+    }
+}
+
+// `today`, `tomorrow`, `friday`, `next monday`, `in 3 days`, `2026-11-01`,
+// `nov 1`, `1st november`... Returns the date and the number of words it took.
+fn parse_date(words: &[Word], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    parse_date_after(words, today, false)
+}
+
+// A short weekday like `sat` is only a date after `on`, `due`, `by` or `next`,
+// alone it is too often a word of the name
+fn parse_date_after(words: &[Word], today: NaiveDate, keyword: bool) -> Option<(NaiveDate, usize)> {
+    let lower: Vec<String> = words
+        .iter()
+        .take(3)
+        .take_while(|word| !word.literal)
+        .map(|word| word.text.to_lowercase())
+        .collect();
+    let lower: Vec<&str> = lower.iter().map(String::as_str).collect();
+
+    // Words that can come before a date
+    if let ["on" | "due" | "by", ..] = lower.as_slice() {
+        return parse_date_after(&words[1..], today, true).map(|(date, used)| (date, used + 1));
+    }
+
+    match lower.as_slice() {
+        ["today" | "tonight", ..] => Some((today, 1)),
+        ["tomorrow" | "tmr", ..] => Some((today.succ_opt()?, 1)),
+        ["next", "week", ..] => Some((next_weekday(today, Weekday::Mon), 2)),
+        ["next", "month", ..] => Some((today.with_day(1)? + Months::new(1), 2)),
+        ["next", day, ..] if parse_weekday(day).is_some() => {
+            Some((next_weekday(today, parse_weekday(day)?), 2))
+        }
+        ["in", count, unit, ..] => {
+            let count = count.parse::<u32>().ok()?;
+            let date = match unit.strip_suffix('s').unwrap_or(unit) {
+                "day" => today + Days::new(u64::from(count)),
+                "week" => today + Days::new(7 * u64::from(count)),
+                "month" => today + Months::new(count),
+                _ => return None,
+            };
+            Some((date, 3))
+        }
+        [day, ..] if parse_weekday(day).is_some() && (keyword || is_weekday_name(day)) => {
+            Some((next_weekday(today, parse_weekday(day)?), 1))
+        }
+        [date, ..] if NaiveDate::parse_from_str(date, DATE_FORMAT).is_ok() => {
+            Some((NaiveDate::parse_from_str(date, DATE_FORMAT).ok()?, 1))
+        }
+        [month, day, ..] if parse_month(month).is_some() && parse_day(day).is_some() => {
+            Some((month_day(today, parse_month(month)?, parse_day(day)?)?, 2))
+        }
+        [day, month, ..] if parse_day(day).is_some() && parse_month(month).is_some() => {
+            Some((month_day(today, parse_month(month)?, parse_day(day)?)?, 2))
+        }
+        _ => None,
+    }
+}
+
+// `9am`, `9:30pm`, `14:00`, `noon`, optionally after `at`
+fn parse_time(words: &[Word]) -> Option<(NaiveTime, usize)> {
+    let first = words.first().filter(|word| !word.literal)?;
+    let lower = first.text.to_lowercase();
+    if lower == "at" {
+        return parse_time(&words[1..]).map(|(time, used)| (time, used + 1));
+    }
+
+    let time = match lower.as_str() {
+        "noon" => NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {
+            let (clock, offset) = if let Some(clock) = lower.strip_suffix("am") {
+                (clock, Some(0))
+            } else if let Some(clock) = lower.strip_suffix("pm") {
+                (clock, Some(12))
+            } else {
+                (lower.as_str(), None)
+            };
+            let (hours, minutes) = match clock.split_once(':') {
+                Some((hours, minutes)) => (hours.parse::<u32>().ok()?, minutes.parse().ok()?),
+                // A number alone is only a time with am or pm
+                None if offset.is_some() => (clock.parse::<u32>().ok()?, 0),
+                None => return None,
+            };
+            let hours = match offset {
+                Some(_) if !(1..=12).contains(&hours) => return None,
+                Some(offset) => hours % 12 + offset,
+                None => hours,
+            };
+            NaiveTime::from_hms_opt(hours, minutes, 0)
+        }
+    };
+    Some((time?, 1))
+}
+
+// `friday`, but not `fri` or `fridays`
+fn is_weekday_name(word: &str) -> bool {
+    parse_weekday(word).is_some_and(|day| weekday_name(day) == word)
+}
+
+// The first `weekday` after `today`
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+    today + Days::new(if days == 0 { 7 } else { u64::from(days) })
+}
+
+fn parse_month(word: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let word = word.trim_end_matches('.');
+    let index = MONTHS
+        .iter()
+        .position(|month| *month == word || (word.len() >= 3 && month.starts_with(word)))?;
+    Some(index as u32 + 1)
+}
+
+// `1`, `1st`, `22nd`...
+fn parse_day(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic() || c == ',');
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+// The next time this day of the year comes, today included
+fn month_day(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
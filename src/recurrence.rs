@@ -0,0 +1,176 @@
+use chrono::{Datelike, Days, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+// How often a task comes back once it is done, written the way it is typed:
+// `every day`, `every 2 weeks`, `every monday, thursday`, `every weekday`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Recurrence {
+    Every(u32, Unit),
+    On(Vec<Weekday>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+const WORKING_DAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+impl Recurrence {
+    // The first date of the next occurrence strictly after `date`
+    pub fn next_date(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Recurrence::Every(count, Unit::Day) => date + Days::new(u64::from(*count)),
+            Recurrence::Every(count, Unit::Week) => date + Days::new(7 * u64::from(*count)),
+            Recurrence::Every(count, Unit::Month) => date + Months::new(*count),
+            Recurrence::Every(count, Unit::Year) => date + Months::new(12 * count),
+            Recurrence::On(days) => {
+                let mut next = date.succ_opt().unwrap_or(date);
+                while !days.contains(&next.weekday()) && !days.is_empty() {
+                    next = next.succ_opt().unwrap_or(next);
+                }
+                next
+            }
+        }
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Every(1, unit) => write!(f, "every {}", unit.name()),
+            Recurrence::Every(count, unit) => write!(f, "every {} {}s", count, unit.name()),
+            Recurrence::On(days) if days.as_slice() == WORKING_DAYS => write!(f, "every weekday"),
+            Recurrence::On(days) => {
+                let names: Vec<&str> = days.iter().map(|day| weekday_name(*day)).collect();
+                write!(f, "every {}", names.join(", "))
+            }
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim().to_lowercase();
+        let invalid = || format!("invalid recurrence \"{}\"", text);
+        match text.as_str() {
+            "daily" => return Ok(Recurrence::Every(1, Unit::Day)),
+            "weekly" => return Ok(Recurrence::Every(1, Unit::Week)),
+            "monthly" => return Ok(Recurrence::Every(1, Unit::Month)),
+            "yearly" | "annually" => return Ok(Recurrence::Every(1, Unit::Year)),
+            _ => {}
+        }
+
+        let rest = text.strip_prefix("every ").ok_or_else(invalid)?;
+        let words: Vec<&str> = rest
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty() && *word != "and")
+            .collect();
+        match words.as_slice() {
+            ["weekday"] | ["weekdays"] => Ok(Recurrence::On(WORKING_DAYS.to_vec())),
+            [unit] if parse_unit(unit).is_some() => {
+                Ok(Recurrence::Every(1, parse_unit(unit).ok_or_else(invalid)?))
+            }
+            [count, unit] if count.parse::<u32>().is_ok_and(|count| count > 0) => {
+                let count = count.parse().map_err(|_| invalid())?;
+                Ok(Recurrence::Every(
+                    count,
+                    parse_unit(unit).ok_or_else(invalid)?,
+                ))
+            }
+            days => {
+                let mut weekdays = vec![];
+                for day in days {
+                    weekdays.push(parse_weekday(day).ok_or_else(invalid)?);
+                }
+                if weekdays.is_empty() {
+                    return Err(invalid());
+                }
+                weekdays.sort_by_key(|day| day.num_days_from_monday());
+                weekdays.dedup();
+                Ok(Recurrence::On(weekdays))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = String;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl Unit {
+    fn name(self) -> &'static str {
+        match self {
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Month => "month",
+            Unit::Year => "year",
+        }
+    }
+}
+
+fn parse_unit(word: &str) -> Option<Unit> {
+    match word.strip_suffix('s').unwrap_or(word) {
+        "day" => Some(Unit::Day),
+        "week" => Some(Unit::Week),
+        "month" => Some(Unit::Month),
+        "year" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+// `monday`, `mon` or `mondays`
+pub fn parse_weekday(word: &str) -> Option<Weekday> {
+    let word = word.to_lowercase();
+    let word = word.strip_suffix('s').unwrap_or(&word);
+    [
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+        Weekday::Sat,
+        Weekday::Sun,
+    ]
+    .into_iter()
+    .find(|day| {
+        let name = weekday_name(*day);
+        word == name || (word.len() >= 3 && name.starts_with(word))
+    })
+}
+
+pub fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
@@ -12,12 +12,18 @@ pub fn run(args: Vec<&str>, todo_list: &mut Vec<Task>) {
         "edit" => {
             user_interactions::edit_task(args, todo_list);
         }
+        "edit-list" => {
+            user_interactions::edit_list(args, todo_list);
+        }
         "delete" => {
             user_interactions::delete_task(args, todo_list);
         }
         "complete" => {
             user_interactions::complete_task(args, todo_list);
         }
+        "tag" => {
+            user_interactions::tag_tasks(args, todo_list);
+        }
         "exit" => {
             std::process::exit(0);
         }
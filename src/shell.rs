@@ -0,0 +1,314 @@
+use color_eyre::Result;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::completions::QUERY_FIELDS;
+use crate::output::OutputFormat;
+use crate::run::run;
+use crate::tab::{FileChange, Tab};
+use crate::task::Task;
+use crate::view::SmartView;
+
+// Commands offered at the start of a line
+const COMMANDS: [&str; 11] = [
+    "add",
+    "list",
+    "ls",
+    "edit",
+    "edit-list",
+    "delete",
+    "complete",
+    "tag",
+    "help",
+    "exit",
+    "quit",
+];
+
+// Commands that change the list, run with the file locked
+const EDITING_COMMANDS: [&str; 6] = ["add", "edit", "edit-list", "delete", "complete", "tag"];
+
+// Commands taking a list of ids, ranges or a query
+const BULK_COMMANDS: [&str; 4] = ["edit-list", "delete", "complete", "tag"];
+
+// Completes the words of the prompt from the commands and the list
+#[derive(Default)]
+struct ShellHelper {
+    // The tasks of the list when the line was started
+    tasks: Vec<Task>,
+}
+
+// Read commands from a prompt and run them on a list until `exit` or Ctrl-D.
+// The list is saved after each command that changes it.
+pub fn run_shell(file_path: &Path) -> Result<()> {
+    let mut tab = Tab::open(file_path)?;
+    let mut editor: Editor<ShellHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(ShellHelper::default()));
+    let history = history_path();
+    if let Some(history) = &history {
+        // There is no history the first time
+        let _ = editor.load_history(history);
+    }
+
+    println!(
+        "Editing {}, type help for the commands and exit to quit",
+        file_path.display()
+    );
+    loop {
+        if let Some(helper) = editor.helper_mut() {
+            helper.tasks = tab.todo_list.items.clone();
+        }
+
+        let line = match editor.readline("horme> ") {
+            Ok(line) => line,
+            // Ctrl-C drops the line being typed
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let args = match split_args(&line) {
+            Ok(args) => args,
+            Err(e) => {
+                eprintln!("{}", e);
+                continue;
+            }
+        };
+        let Some(command) = args.first() else {
+            continue;
+        };
+        let _ = editor.add_history_entry(line.as_str());
+        if command == "exit" || command == "quit" {
+            break;
+        }
+
+        let editing = EDITING_COMMANDS.contains(&command.as_str());
+        if editing && !tab.lock()? {
+            eprintln!(
+                "{} is being edited by another instance, try again later",
+                file_path.display()
+            );
+            continue;
+        }
+        // Pick up the changes made by other programs while the prompt was
+        // waiting, once nobody else can write the file. Edits are saved right
+        // away, they are only left over when saving failed.
+        merge_external_changes(&mut tab);
+        let heartbeat = tab.keep_lock_alive();
+        run(
+            args.iter().map(String::as_str).collect(),
+            &mut tab.todo_list.items,
+        );
+        drop(heartbeat);
+        if tab.has_unsaved_changes() {
+            // Other programs may also have written the file while $EDITOR ran
+            merge_external_changes(&mut tab);
+            if let Err(e) = tab.save() {
+                eprintln!("{:#}", e);
+            }
+        }
+        // Only hold the lock while the list is being changed
+        tab.sync_lock(false);
+    }
+
+    if let Some(history) = &history {
+        if let Some(dir) = history.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        editor.save_history(history)?;
+    }
+    Ok(())
+}
+
+// Take the changes made to the file on disk, along with the local edits
+fn merge_external_changes(tab: &mut Tab) {
+    if tab.check_external_changes() == FileChange::Conflict {
+        for name in tab.merge_remote_list() {
+            eprintln!("\"{}\" was changed here and in the file", name);
+        }
+    }
+}
+
+// `$XDG_STATE_HOME/horme/history`, `~/.local/state/horme/history` by default
+fn history_path() -> Option<PathBuf> {
+    match env::var_os("XDG_STATE_HOME") {
+        Some(dir) => Some(Path::new(&dir).join("horme/history")),
+        None => env::var_os("HOME").map(|home| Path::new(&home).join(".local/state/horme/history")),
+    }
+}
+
+// Split a line in arguments the way a shell does: double or single quotes keep
+// spaces in an argument and a backslash escapes the next character
+pub(crate) fn split_args(line: &str) -> Result<Vec<String>, String> {
+    let mut args = vec![];
+    let mut current: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('\'')) => current.get_or_insert_with(String::new).push(c),
+            ('\\', _) => {
+                let escaped = chars.next().ok_or("nothing to escape after \\")?;
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                current.get_or_insert_with(String::new);
+            }
+            (c, Some(open)) if c == open => quote = None,
+            (c, None) if c.is_whitespace() => args.extend(current.take()),
+            (c, _) => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(quote) = quote {
+        return Err(format!("missing closing {}", quote));
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+// Candidates for `word`, the word being typed after `before`, as the text
+// replacing the word and the text shown in the list
+pub(crate) fn complete_word(
+    before: &[String],
+    word: &str,
+    tasks: &[Task],
+) -> Vec<(String, String)> {
+    let same = |text: String| (text.clone(), text);
+    let candidates: Vec<(String, String)> = match before {
+        [] => COMMANDS
+            .iter()
+            .map(|command| same(command.to_string()))
+            .collect(),
+        [.., option] if option == "--format" => OutputFormat::NAMES
+            .iter()
+            .map(|name| same(name.to_string()))
+            .collect(),
+        // Task ids, shown with the name of the task. Bulk commands take
+        // several of them, or a query.
+        [command] if command == "edit" => ids(tasks),
+        [command, ..]
+            if BULK_COMMANDS.contains(&command.as_str())
+                && word.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            ids(tasks)
+        }
+        [command, ..] if command == "tag" && (word.starts_with('+') || word == "-") => {
+            let sign = &word[..1];
+            tags(tasks)
+                .into_iter()
+                .map(|tag| same(format!("{}{}", sign, tag)))
+                .collect()
+        }
+        [command, _] if command == "edit" => ["--name", "--description", "--editor"]
+            .iter()
+            .map(|option| same(option.to_string()))
+            .collect(),
+        _ if word.starts_with('#') => tags(tasks)
+            .into_iter()
+            .map(|tag| same(format!("#{}", tag)))
+            .collect(),
+        [command, ..]
+            if ["list", "ls"].contains(&command.as_str())
+                || BULK_COMMANDS.contains(&command.as_str()) =>
+        {
+            let listing = ["list", "ls"].contains(&command.as_str());
+            // Values of a field of a query, keeping the field and its `-`
+            let (prefix, field) = match word.split_once(':') {
+                Some((field, _)) => (format!("{}:", field), field.trim_start_matches('-')),
+                None => (String::new(), ""),
+            };
+            let values: Vec<String> = match field {
+                "" => {
+                    let negated = if word.starts_with('-') { "-" } else { "" };
+                    QUERY_FIELDS
+                        .iter()
+                        .map(|field| format!("{}{}", negated, field))
+                        .chain(listing.then(|| "--format".to_string()))
+                        .collect()
+                }
+                "status" => ["todo", "doing", "done"].map(str::to_string).to_vec(),
+                "tag" => tags(tasks),
+                "priority" => ["high", "medium", "low", "none"]
+                    .map(str::to_string)
+                    .to_vec(),
+                "due" => ["today", "tomorrow", "7d", "any", "none"]
+                    .map(str::to_string)
+                    .to_vec(),
+                "is" => SmartView::ALL
+                    .iter()
+                    .map(|view| view.name().to_lowercase())
+                    .collect(),
+                _ => vec![],
+            };
+            values
+                .into_iter()
+                .map(|value| same(format!("{}{}", prefix, value)))
+                .collect()
+        }
+        _ => vec![],
+    };
+    candidates
+        .into_iter()
+        .filter(|(replacement, _)| replacement.starts_with(word))
+        .collect()
+}
+
+fn ids(tasks: &[Task]) -> Vec<(String, String)> {
+    tasks
+        .iter()
+        .map(|task| (task.id.to_string(), format!("{}  {}", task.id, task.name)))
+        .collect()
+}
+
+// Tags of the tasks, each once
+fn tags(tasks: &[Task]) -> Vec<String> {
+    let mut tags: Vec<String> = tasks.iter().flat_map(|task| task.tags.clone()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+impl Completer for ShellHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos]
+            .rfind(char::is_whitespace)
+            .map_or(0, |index| index + 1);
+        // Words that can't be split yet, inside an open quote, aren't completed
+        let Ok(before) = split_args(&line[..start]) else {
+            return Ok((pos, vec![]));
+        };
+        let candidates = complete_word(&before, &line[start..pos], &self.tasks)
+            .into_iter()
+            .map(|(replacement, display)| Pair {
+                display,
+                replacement,
+            })
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
@@ -0,0 +1,189 @@
+use color_eyre::{eyre::WrapErr, Result};
+use rusqlite::{params, Connection};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use crate::markdown::Diagnostic;
+use crate::storage::{Change, Storage};
+use crate::task::{next_id, Task};
+
+// Version of the database schema, stored in `PRAGMA user_version`
+const SCHEMA_VERSION: u32 = 2;
+
+// Rows are keyed by a key of their own: task ids only last for a run and can
+// be handed out again, so they can't tell rows apart
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS tasks (
+        key INTEGER PRIMARY KEY AUTOINCREMENT,
+        position INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        name TEXT NOT NULL,
+        -- The whole task, in the same format as the JSON storage
+        data TEXT NOT NULL
+    );
+";
+
+// Version 1 keyed the rows by task id
+const MIGRATE_FROM_V1: &str = "
+    ALTER TABLE tasks RENAME TO tasks_v1;
+    CREATE TABLE tasks (
+        key INTEGER PRIMARY KEY AUTOINCREMENT,
+        position INTEGER NOT NULL,
+        status TEXT NOT NULL,
+        name TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    INSERT INTO tasks (position, status, name, data)
+        SELECT position, status, name, data FROM tasks_v1 ORDER BY position;
+    DROP TABLE tasks_v1;
+";
+
+// The list as a SQLite database, where each save only writes the tasks that
+// changed instead of the whole list
+pub struct SqliteStorage {
+    file_path: PathBuf,
+    connection: Option<Connection>,
+    // Row of each task of the list last read or written
+    keys: HashMap<u16, i64>,
+}
+
+impl SqliteStorage {
+    pub fn new(file_path: &Path) -> Self {
+        Self {
+            file_path: file_path.to_path_buf(),
+            connection: None,
+            keys: HashMap::new(),
+        }
+    }
+}
+
+// Open the database the first time it is needed, creating or upgrading the
+// schema
+fn open<'a>(
+    connection: &'a mut Option<Connection>,
+    file_path: &Path,
+) -> Result<&'a mut Connection> {
+    if connection.is_none() {
+        let mut opened = Connection::open(file_path)
+            .wrap_err_with(|| format!("Unable to open {}", file_path.display()))?;
+        let version: u32 = opened.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            color_eyre::eyre::bail!(
+                "{} uses schema version {}, newer than the supported version {}",
+                file_path.display(),
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        let transaction = opened.transaction()?;
+        if version == 1 {
+            transaction.execute_batch(MIGRATE_FROM_V1)?;
+        } else {
+            transaction.execute_batch(SCHEMA)?;
+        }
+        transaction.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        transaction.commit()?;
+        *connection = Some(opened);
+    }
+    Ok(connection.as_mut().unwrap())
+}
+
+impl Storage for SqliteStorage {
+    fn load(&mut self) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+        let connection = open(&mut self.connection, &self.file_path)?;
+        let mut statement = connection.prepare("SELECT key, data FROM tasks ORDER BY position")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        // The ids stored along with the tasks may clash with those of other
+        // lists, each task gets a new one
+        let mut keys = HashMap::new();
+        let mut tasks = vec![];
+        for row in rows {
+            let (key, data) = row?;
+            let mut task: Task = serde_json::from_str(&data)?;
+            task.id = next_id();
+            keys.insert(task.id, key);
+            tasks.push(task);
+        }
+        self.keys = keys;
+        Ok((tasks, vec![]))
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> Result<()> {
+        let transaction = open(&mut self.connection, &self.file_path)?.transaction()?;
+        transaction.execute("DELETE FROM tasks", [])?;
+        let mut keys = HashMap::new();
+        for (position, task) in tasks.iter().enumerate() {
+            keys.insert(task.id, insert_task(&transaction, position, task)?);
+        }
+        transaction.commit()?;
+        self.keys = keys;
+        Ok(())
+    }
+
+    fn save_changes(&mut self, changes: &[Change], tasks: &[Task]) -> Result<()> {
+        // Changes are matched to rows by task id, which only works when no
+        // two tasks share one
+        let mut ids = HashSet::new();
+        if !tasks.iter().all(|task| ids.insert(task.id)) {
+            return self.save(tasks);
+        }
+
+        let mut keys = self.keys.clone();
+        let transaction = open(&mut self.connection, &self.file_path)?.transaction()?;
+        for change in changes {
+            match change {
+                Change::Upsert { position, task } => {
+                    let updated = match keys.get(&task.id) {
+                        Some(key) => update_task(&transaction, *key, *position, task)?,
+                        None => false,
+                    };
+                    if !updated {
+                        keys.insert(task.id, insert_task(&transaction, *position, task)?);
+                    }
+                }
+                Change::Remove(id) => {
+                    if let Some(key) = keys.remove(id) {
+                        transaction.execute("DELETE FROM tasks WHERE key = ?1", params![key])?;
+                    }
+                }
+            }
+        }
+        transaction.commit()?;
+        self.keys = keys;
+        Ok(())
+    }
+}
+
+// Add a task in a new row, returning its key
+fn insert_task(connection: &Connection, position: usize, task: &Task) -> Result<i64> {
+    connection.execute(
+        "INSERT INTO tasks (position, status, name, data) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            position,
+            task.status.to_string(),
+            task.name,
+            serde_json::to_string(task)?
+        ],
+    )?;
+    Ok(connection.last_insert_rowid())
+}
+
+// Write a task over the row `key`, returning false when there is no such row
+fn update_task(connection: &Connection, key: i64, position: usize, task: &Task) -> Result<bool> {
+    let updated = connection.execute(
+        "UPDATE tasks SET position = ?2, status = ?3, name = ?4, data = ?5 WHERE key = ?1",
+        params![
+            key,
+            position,
+            task.status.to_string(),
+            task.name,
+            serde_json::to_string(task)?
+        ],
+    )?;
+    Ok(updated > 0)
+}
@@ -0,0 +1,366 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use color_eyre::Result;
+use serde::Serialize;
+
+use crate::task::{Status, Task, DATE_FORMAT};
+use crate::view::SmartView;
+
+// Days and weeks over which completions are counted
+pub const STATS_DAYS: i64 = 14;
+pub const STATS_WEEKS: i64 = 8;
+
+// Longest bar drawn in the text report
+const MAX_BAR: usize = 40;
+
+// Tasks of a section or with a tag
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Count {
+    pub name: String,
+    pub open: usize,
+    pub done: usize,
+}
+
+// A number of tasks on a day, or in the week starting on a Monday
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DayCount {
+    pub date: NaiveDate,
+    pub tasks: usize,
+}
+
+// Average time taken by the tasks it could be measured on
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Average {
+    pub days: f64,
+    pub tasks: usize,
+}
+
+// Tasks with a due date, and how many of them were left each day
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Burndown {
+    pub open: usize,
+    pub done: usize,
+    // Oldest first, up to today
+    pub remaining: Vec<DayCount>,
+}
+
+// A task in progress and for how long, unknown when it was started before
+// start dates were recorded
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Aging {
+    pub id: u16,
+    pub name: String,
+    pub started_at: Option<NaiveDateTime>,
+    pub days: Option<f64>,
+}
+
+// Throughput of a list, `horme stats`
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub tasks: usize,
+    pub open: usize,
+    pub done: usize,
+    // Open tasks due before today
+    pub overdue: usize,
+    pub sections: Vec<Count>,
+    pub tags: Vec<Count>,
+    // Oldest first, up to today
+    pub completed_per_day: Vec<DayCount>,
+    pub completed_per_week: Vec<DayCount>,
+    // From creation to completion
+    pub lead_time: Option<Average>,
+    // From start to completion
+    pub cycle_time: Option<Average>,
+    // Oldest first
+    pub doing: Vec<Aging>,
+    pub burndown: Burndown,
+}
+
+impl Stats {
+    pub fn new(tasks: &[Task], now: NaiveDateTime) -> Self {
+        let today = now.date();
+        let done = tasks
+            .iter()
+            .filter(|task| task.status == Status::Done)
+            .count();
+
+        // Sections named after a status come first, in the order of the file
+        let mut sections: Vec<Count> = [Status::Todo, Status::Doing, Status::Done]
+            .iter()
+            .map(|status| Count::new(status.to_string()))
+            .collect();
+        for task in tasks {
+            let name = task
+                .section
+                .clone()
+                .unwrap_or_else(|| task.status.to_string());
+            Count::find(&mut sections, name).add(task);
+        }
+        sections.retain(|count| count.open + count.done > 0);
+
+        let mut tags = vec![];
+        for task in tasks {
+            for tag in &task.tags {
+                Count::find(&mut tags, tag.clone()).add(task);
+            }
+        }
+        tags.sort_by(|a, b| {
+            (b.open + b.done)
+                .cmp(&(a.open + a.done))
+                .then(a.name.cmp(&b.name))
+        });
+
+        let completed: Vec<NaiveDate> = tasks
+            .iter()
+            .filter(|task| task.status == Status::Done)
+            .filter_map(|task| task.completed_at.map(|date| date.date()))
+            .collect();
+        let completed_per_day = (0..STATS_DAYS)
+            .rev()
+            .map(|days| today - Duration::days(days))
+            .map(|date| DayCount {
+                date,
+                tasks: completed.iter().filter(|done| **done == date).count(),
+            })
+            .collect();
+        let monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+        let completed_per_week = (0..STATS_WEEKS)
+            .rev()
+            .map(|weeks| monday - Duration::weeks(weeks))
+            .map(|date| DayCount {
+                date,
+                tasks: completed
+                    .iter()
+                    .filter(|done| (date..date + Duration::weeks(1)).contains(done))
+                    .count(),
+            })
+            .collect();
+
+        let done_tasks = tasks.iter().filter(|task| task.status == Status::Done);
+        let lead_time = Average::of(
+            done_tasks
+                .clone()
+                .filter_map(|task| Some(task.completed_at? - task.created_at?)),
+        );
+        let cycle_time =
+            Average::of(done_tasks.filter_map(|task| Some(task.completed_at? - task.started_at?)));
+
+        let mut doing: Vec<Aging> = tasks
+            .iter()
+            .filter(|task| task.status == Status::Doing)
+            .map(|task| Aging {
+                id: task.id,
+                name: task.name.clone(),
+                started_at: task.started_at,
+                days: task.started_at.map(|date| days(now - date)),
+            })
+            .collect();
+        // Tasks of unknown age are listed last
+        doing.sort_by(|a, b| b.days.unwrap_or(-1.0).total_cmp(&a.days.unwrap_or(-1.0)));
+
+        Self {
+            tasks: tasks.len(),
+            open: tasks.len() - done,
+            done,
+            overdue: tasks
+                .iter()
+                .filter(|task| SmartView::Overdue.matches(task, today))
+                .count(),
+            sections,
+            tags,
+            completed_per_day,
+            completed_per_week,
+            lead_time,
+            cycle_time,
+            doing,
+            burndown: Burndown::new(tasks, today),
+        }
+    }
+
+    pub fn render_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)? + "\n")
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut text = format!(
+            "Tasks: {} ({} open, {} done, {} overdue)\n",
+            self.tasks, self.open, self.done, self.overdue
+        );
+        render_counts(&mut text, "Section", &self.sections);
+        render_counts(&mut text, "Tag", &self.tags);
+
+        text.push_str(&format!("\nCompleted per day, last {} days\n", STATS_DAYS));
+        for day in &self.completed_per_day {
+            let line = format!(
+                "  {} {} {:>3} {}",
+                day.date.format(DATE_FORMAT),
+                day.date.format("%a"),
+                day.tasks,
+                bar(day.tasks)
+            );
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text.push_str(&format!(
+            "\nCompleted per week, last {} weeks\n",
+            STATS_WEEKS
+        ));
+        for week in &self.completed_per_week {
+            let line = format!(
+                "  week of {} {:>3} {}",
+                week.date.format(DATE_FORMAT),
+                week.tasks,
+                bar(week.tasks)
+            );
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+
+        text.push('\n');
+        for (label, average, from) in [
+            ("Lead time ", &self.lead_time, "created"),
+            ("Cycle time", &self.cycle_time, "started"),
+        ] {
+            match average {
+                Some(average) => text.push_str(&format!(
+                    "{}  {:.1} days on average, from {} to done ({} tasks)\n",
+                    label, average.days, from, average.tasks
+                )),
+                None => text.push_str(&format!("{}  no done task with a {} date\n", label, from)),
+            }
+        }
+
+        if !self.doing.is_empty() {
+            text.push_str("\nIn progress\n");
+            let width = self
+                .doing
+                .iter()
+                .map(|aging| aging.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            for aging in &self.doing {
+                let age = match aging.days {
+                    Some(days) => format!("{:.1} days", days),
+                    None => "started before start dates were recorded".to_string(),
+                };
+                text.push_str(&format!(
+                    "  {:>4}  {:<width$}  {}\n",
+                    aging.id, aging.name, age
+                ));
+            }
+        }
+        text
+    }
+}
+
+impl Count {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            open: 0,
+            done: 0,
+        }
+    }
+
+    // The count named `name`, added at the end when missing
+    fn find(counts: &mut Vec<Count>, name: String) -> &mut Count {
+        let i = match counts.iter().position(|count| count.name == name) {
+            Some(i) => i,
+            None => {
+                counts.push(Count::new(name));
+                counts.len() - 1
+            }
+        };
+        &mut counts[i]
+    }
+
+    fn add(&mut self, task: &Task) {
+        if task.status == Status::Done {
+            self.done += 1;
+        } else {
+            self.open += 1;
+        }
+    }
+}
+
+impl Burndown {
+    fn new(tasks: &[Task], today: NaiveDate) -> Self {
+        let dated: Vec<&Task> = tasks
+            .iter()
+            .filter(|task| task.due_date.is_some())
+            .collect();
+        let done = dated
+            .iter()
+            .filter(|task| task.status == Status::Done)
+            .count();
+        // A task counts from the day it was created until the day it was done,
+        // done tasks without a date never count
+        let open_on = |task: &Task, date: NaiveDate| {
+            task.created_at.is_none_or(|created| created.date() <= date)
+                && match (task.status, task.completed_at) {
+                    (Status::Done, Some(completed)) => completed.date() > date,
+                    (Status::Done, None) => false,
+                    _ => true,
+                }
+        };
+        let remaining = (0..STATS_DAYS)
+            .rev()
+            .map(|days| today - Duration::days(days))
+            .map(|date| DayCount {
+                date,
+                tasks: dated.iter().filter(|task| open_on(task, date)).count(),
+            })
+            .collect();
+        Self {
+            open: dated.len() - done,
+            done,
+            remaining,
+        }
+    }
+}
+
+impl Average {
+    fn of(durations: impl Iterator<Item = Duration>) -> Option<Self> {
+        let durations: Vec<Duration> = durations.collect();
+        if durations.is_empty() {
+            return None;
+        }
+        let total: f64 = durations.iter().map(|duration| days(*duration)).sum();
+        Some(Self {
+            days: round(total / durations.len() as f64),
+            tasks: durations.len(),
+        })
+    }
+}
+
+fn days(duration: Duration) -> f64 {
+    round(duration.num_minutes() as f64 / (24.0 * 60.0))
+}
+
+// Two decimals are enough for days
+fn round(days: f64) -> f64 {
+    (days * 100.0).round() / 100.0
+}
+
+fn bar(count: usize) -> String {
+    "█".repeat(count.min(MAX_BAR))
+}
+
+// A table of counts, skipped when there is nothing to count
+fn render_counts(text: &mut String, title: &str, counts: &[Count]) {
+    if counts.is_empty() {
+        return;
+    }
+    let width = counts
+        .iter()
+        .map(|count| count.name.chars().count())
+        .chain([title.len()])
+        .max()
+        .unwrap_or(0);
+    text.push_str(&format!("\n{:<width$}  Open  Done\n", title));
+    for count in counts {
+        text.push_str(&format!(
+            "{:<width$}  {:>4}  {:>4}\n",
+            count.name, count.open, count.done
+        ));
+    }
+}
@@ -0,0 +1,152 @@
+use color_eyre::{eyre::WrapErr, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::checklist::{detect_dialect, parse_checklist, render_checklist, Layout};
+use crate::convert::Format;
+use crate::markdown::{
+    default_sections_order, parse_todo_list_checked, render_todo_list, Diagnostic,
+};
+use crate::sqlite::SqliteStorage;
+use crate::task::Task;
+
+// Change made to a list since it was last saved
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    // The task was added, or its content or position changed
+    Upsert { position: usize, task: Box<Task> },
+    // The task with this id was removed
+    Remove(u16),
+}
+
+// Where a task list is loaded from and saved to. Lists can be loaded in the
+// background, on another thread.
+pub trait Storage: Send {
+    // Read the list, along with the problems found in it
+    fn load(&mut self) -> Result<(Vec<Task>, Vec<Diagnostic>)>;
+
+    // Write the whole list
+    fn save(&mut self, tasks: &[Task]) -> Result<()>;
+
+    // Write only what changed since the last save. Formats that can't be
+    // updated in place just write the whole list.
+    fn save_changes(&mut self, _changes: &[Change], tasks: &[Task]) -> Result<()> {
+        self.save(tasks)
+    }
+}
+
+// Pick the storage for a file from its extension: `.json` files are stored as
+// JSON, `.txt` files as todo.txt, `.ics` files as iCalendar, `.db`, `.sqlite`
+// and `.sqlite3` files as SQLite databases, anything else as markdown
+pub fn storage_for(file_path: &Path) -> Box<dyn Storage> {
+    let extension = file_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("db" | "sqlite" | "sqlite3") => Box::new(SqliteStorage::new(file_path)),
+        _ => match Format::from_path(file_path) {
+            Some(format @ (Format::Json | Format::TodoTxt | Format::ICalendar | Format::Org)) => {
+                Box::new(FileStorage::new(file_path, format))
+            }
+            _ => Box::new(MarkdownStorage::new(file_path)),
+        },
+    }
+}
+
+// Compute the changes that turn `base` into `tasks`, matching tasks by id
+pub fn diff_todo_lists(base: &[Task], tasks: &[Task]) -> Vec<Change> {
+    let mut changes: Vec<Change> = base
+        .iter()
+        .filter(|old| !tasks.iter().any(|task| task.id == old.id))
+        .map(|old| Change::Remove(old.id))
+        .collect();
+
+    for (position, task) in tasks.iter().enumerate() {
+        let unchanged = base.get(position).is_some_and(|old| old == task);
+        if !unchanged {
+            changes.push(Change::Upsert {
+                position,
+                task: Box::new(task.clone()),
+            });
+        }
+    }
+
+    changes
+}
+
+// The list as a markdown file. Files where tasks are checklist items, GFM
+// task lists or Obsidian Kanban boards, are written back in the same dialect.
+pub struct MarkdownStorage {
+    file_path: PathBuf,
+    // Set when the file was read as a checklist
+    layout: Option<Layout>,
+}
+
+impl MarkdownStorage {
+    pub fn new(file_path: &Path) -> Self {
+        Self {
+            file_path: file_path.to_path_buf(),
+            layout: None,
+        }
+    }
+}
+
+impl Storage for MarkdownStorage {
+    fn load(&mut self) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+        let contents = fs::read_to_string(&self.file_path)
+            .wrap_err_with(|| format!("Unable to read {}", self.file_path.display()))?;
+        match detect_dialect(&contents) {
+            Some(dialect) => {
+                let (tasks, layout, diagnostics) = parse_checklist(&contents, dialect);
+                self.layout = Some(layout);
+                Ok((tasks, diagnostics))
+            }
+            None => {
+                self.layout = None;
+                Ok(parse_todo_list_checked(&contents))
+            }
+        }
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> Result<()> {
+        let data = match &self.layout {
+            Some(layout) => render_checklist(tasks, layout),
+            None => render_todo_list(tasks, &default_sections_order()),
+        };
+        fs::write(&self.file_path, data)
+            .wrap_err_with(|| format!("Unable to write {}", self.file_path.display()))
+    }
+}
+
+// The list as a file in one of the formats horme can convert to, written
+// whole on every save
+pub struct FileStorage {
+    file_path: PathBuf,
+    format: Format,
+}
+
+impl FileStorage {
+    pub fn new(file_path: &Path, format: Format) -> Self {
+        Self {
+            file_path: file_path.to_path_buf(),
+            format,
+        }
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&mut self) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+        let contents = fs::read_to_string(&self.file_path)
+            .wrap_err_with(|| format!("Unable to read {}", self.file_path.display()))?;
+        self.format
+            .parse(&contents)
+            .wrap_err_with(|| format!("Invalid {}", self.file_path.display()))
+    }
+
+    fn save(&mut self, tasks: &[Task]) -> Result<()> {
+        fs::write(&self.file_path, self.format.render(tasks)?)
+            .wrap_err_with(|| format!("Unable to write {}", self.file_path.display()))
+    }
+}
@@ -0,0 +1,439 @@
+use color_eyre::{eyre::bail, Result};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use ratatui::widgets::ListState;
+
+use crate::app::TodoList;
+use crate::archive::{archive_tasks, is_archivable, load_archive, save_archive};
+use crate::editor::ChangeSet;
+use crate::events::LoadedList;
+use crate::lock::{FileLock, Heartbeat, LockOwner, LockStatus};
+use crate::markdown::{Diagnostic, Severity};
+use crate::merge::merge_todo_lists;
+use crate::storage::{diff_todo_lists, storage_for, Storage};
+use crate::task::{now, Status, Task};
+use crate::watch::FileWatcher;
+
+// A task list opened in the app, together with the state of its file on disk
+pub struct Tab {
+    pub todo_list: TodoList,
+    pub file_path: PathBuf,
+    pub storage: Box<dyn Storage>,
+    // The list as it was last loaded from or saved to disk
+    pub base_list: Vec<Task>,
+    // The list found on disk when it changed while there were local edits
+    pub remote_list: Option<Vec<Task>>,
+    // The storage and the file the remote list was read with in the
+    // background, taken in once the conflict is solved
+    pub remote_load: Option<(Box<dyn Storage>, FileWatcher)>,
+    pub file_watcher: FileWatcher,
+    // Held while this session is editing the file
+    pub lock: Option<FileLock>,
+    // Set when another instance holds the lock, the list is read-only meanwhile
+    pub locked_by: Option<LockOwner>,
+    // Problems found in the file the last time it was read
+    pub diagnostics: Vec<Diagnostic>,
+    // Changes applied to the list in one go, the last one is undone first
+    pub undo_stack: Vec<ChangeSet>,
+    // Number of times the list was saved, so that a list read from the file
+    // in the background before a save is told apart from a change made on disk
+    pub saves: u64,
+    // Number of times the file was read after it changed on disk, so that a
+    // read in the background is dropped once the file was read again meanwhile
+    pub loads: u64,
+    // A read of the file is running in the background
+    pub loading: bool,
+}
+
+// What was found when looking at the file on disk
+#[derive(Debug, PartialEq)]
+pub enum FileChange {
+    Unchanged,
+    // The file changed and there were no local edits, the list was reloaded
+    Reloaded,
+    // The file changed while there were local edits, see `remote_list`
+    Conflict,
+}
+
+impl Tab {
+    pub fn new(todo_list: Vec<Task>, file_path: &Path) -> Self {
+        Self {
+            base_list: todo_list.clone(),
+            todo_list: TodoList {
+                state: ListState::default(),
+                items: todo_list,
+            },
+            file_path: file_path.to_path_buf(),
+            storage: storage_for(file_path),
+            remote_list: None,
+            remote_load: None,
+            file_watcher: FileWatcher::new(file_path),
+            lock: None,
+            locked_by: FileLock::owner(file_path),
+            diagnostics: vec![],
+            undo_stack: vec![],
+            saves: 0,
+            loads: 0,
+            loading: false,
+        }
+    }
+
+    // Load the list stored in `file_path`. A missing file is reported and
+    // gives an empty list, the file is created on save.
+    pub fn open(file_path: &Path) -> Result<Self> {
+        let mut storage = storage_for(file_path);
+        let (todo_list, diagnostics) = if file_path.exists() {
+            storage.load()?
+        } else {
+            (
+                vec![],
+                vec![Diagnostic {
+                    line: 0,
+                    column: 0,
+                    severity: Severity::Warning,
+                    message: format!(
+                        "{} not found, it will be created once you add a task",
+                        file_path.display()
+                    ),
+                }],
+            )
+        };
+        let mut tab = Self::new(todo_list, file_path);
+        tab.storage = storage;
+        tab.diagnostics = diagnostics;
+        Ok(tab)
+    }
+
+    // Name shown in the tab bar, marked when there is something to save
+    pub fn title(&self) -> String {
+        let name = self
+            .file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.file_path.display().to_string());
+        if self.has_unsaved_changes() {
+            format!("{}*", name)
+        } else {
+            name
+        }
+    }
+
+    // Write the changes made since the list was last loaded or saved
+    pub fn save(&mut self) -> Result<()> {
+        // Another instance may have taken the lock over while this one was
+        // busy, never write over the changes it made meanwhile
+        if self.lock.as_ref().is_some_and(|lock| !lock.is_held()) {
+            self.lock = None;
+            if !self.lock()? {
+                bail!(
+                    "{} is being edited by another instance, the changes were not saved",
+                    self.file_path.display()
+                );
+            }
+        }
+        // Changes made on disk have to be taken in first, see
+        // `check_external_changes`
+        if self.file_watcher.has_changed() {
+            bail!(
+                "{} was changed on disk since it was read, the changes were not saved",
+                self.file_path.display()
+            );
+        }
+        let changes = diff_todo_lists(&self.base_list, &self.todo_list.items);
+        self.storage.save_changes(&changes, &self.todo_list.items)?;
+        self.base_list = self.todo_list.items.clone();
+        self.file_watcher.mark_seen();
+        self.saves += 1;
+        Ok(())
+    }
+
+    // Check if the list was changed since it was last loaded or saved
+    pub fn has_unsaved_changes(&self) -> bool {
+        !same_tasks(&self.todo_list.items, &self.base_list)
+    }
+
+    // Look for changes made to the file by other programs. Without local edits
+    // the list is simply reloaded, otherwise the caller has to ask what to do.
+    pub fn check_external_changes(&mut self) -> FileChange {
+        if self.remote_list.is_some() || !self.file_watcher.has_changed() {
+            return FileChange::Unchanged;
+        }
+        // A read running in the background is older than this one
+        self.loading = false;
+        self.loads += 1;
+        self.file_watcher.mark_seen();
+        match self.storage.load() {
+            Ok((remote_list, diagnostics)) => self.use_loaded_list(remote_list, diagnostics),
+            // The file was removed or can't be read, it will be written again on save
+            Err(_) => FileChange::Unchanged,
+        }
+    }
+
+    // Check if the file changed on disk since it was last seen, and has to be
+    // read again in the background. Returns the number of the read to start.
+    // The file is only seen once what was read is taken in, and nothing is read
+    // while a conflict is pending or another read is running.
+    pub fn poll_file(&mut self) -> Option<u64> {
+        if self.remote_list.is_some() || self.loading || !self.file_watcher.has_changed() {
+            return None;
+        }
+        self.loading = true;
+        self.loads += 1;
+        Some(self.loads)
+    }
+
+    // Take the list read in the background by read number `load`, `seen`
+    // being the file that was read and `saves` the number of saves when the
+    // read started
+    pub fn use_background_load(
+        &mut self,
+        load: u64,
+        saves: u64,
+        seen: FileWatcher,
+        loaded: Result<LoadedList>,
+    ) -> FileChange {
+        // The file was read again meanwhile
+        if load != self.loads {
+            return FileChange::Unchanged;
+        }
+        self.loading = false;
+        // The file stays unseen while a conflict is pending, it is read again
+        // once the conflict is solved
+        if self.remote_list.is_some() {
+            return FileChange::Unchanged;
+        }
+        let Ok((storage, remote_list, diagnostics)) = loaded else {
+            // The file was removed or can't be read, it will be written again on save
+            self.file_watcher = seen;
+            return FileChange::Unchanged;
+        };
+        if self.saves != saves {
+            // Read before the list was saved, it is a change made on disk
+            // unless it is the list that was saved
+            if same_tasks(&remote_list, &self.base_list) {
+                return FileChange::Unchanged;
+            }
+            self.diagnostics = diagnostics;
+            self.remote_list = Some(remote_list);
+            self.remote_load = Some((storage, seen));
+            return FileChange::Conflict;
+        }
+        self.file_watcher = seen;
+        self.storage = storage;
+        self.use_loaded_list(remote_list, diagnostics)
+    }
+
+    // Take the list read from the file after it changed on disk
+    pub fn use_loaded_list(
+        &mut self,
+        remote_list: Vec<Task>,
+        diagnostics: Vec<Diagnostic>,
+    ) -> FileChange {
+        self.diagnostics = diagnostics;
+
+        if self.has_unsaved_changes() {
+            self.remote_list = Some(remote_list);
+            FileChange::Conflict
+        } else {
+            self.replace_todo_list(remote_list.clone(), remote_list);
+            FileChange::Reloaded
+        }
+    }
+
+    // Replace the tasks shown with `items`, `base_list` being what is on disk
+    pub fn replace_todo_list(&mut self, items: Vec<Task>, base_list: Vec<Task>) {
+        self.todo_list.items = items;
+        self.base_list = base_list;
+        // The ids of the tasks are given again when they are read
+        self.undo_stack.clear();
+        self.clamp_selection();
+    }
+
+    // Apply changes made to the list in one go, so that they can be undone
+    pub fn apply_change_set(&mut self, change_set: ChangeSet) {
+        if change_set.is_empty() {
+            return;
+        }
+        self.todo_list.items = change_set.after.clone();
+        self.undo_stack.push(change_set);
+        self.clamp_selection();
+    }
+
+    // Put the list back as it was before the last change set, as long as it
+    // wasn't changed since
+    pub fn undo(&mut self) -> Result<ChangeSet, String> {
+        let Some(change_set) = self.undo_stack.pop() else {
+            return Err("Nothing to undo".to_string());
+        };
+        let unchanged = self.todo_list.items.len() == change_set.after.len()
+            && self
+                .todo_list
+                .items
+                .iter()
+                .zip(&change_set.after)
+                .all(|(task, after)| task == after);
+        if !unchanged {
+            self.undo_stack.clear();
+            return Err("The list was changed since, it can't be undone".to_string());
+        }
+        self.todo_list.items = change_set.before.clone();
+        self.clamp_selection();
+        Ok(change_set)
+    }
+
+    // Keep the selection inside the list after tasks were removed from it
+    pub fn clamp_selection(&mut self) {
+        match self.todo_list.state.selected() {
+            Some(_) if self.todo_list.items.is_empty() => self.todo_list.state.select(None),
+            Some(i) if i >= self.todo_list.items.len() => self
+                .todo_list
+                .state
+                .select(Some(self.todo_list.items.len() - 1)),
+            _ => {}
+        }
+    }
+
+    // Discard the local edits and use the list found on disk
+    pub fn reload_remote_list(&mut self) {
+        if let Some(remote_list) = self.remote_list.take() {
+            self.take_remote_load();
+            self.replace_todo_list(remote_list.clone(), remote_list);
+        }
+    }
+
+    // Keep the local edits, the file on disk will be overwritten on save
+    pub fn keep_local_list(&mut self) {
+        if let Some(remote_list) = self.remote_list.take() {
+            self.take_remote_load();
+            self.base_list = remote_list;
+        }
+    }
+
+    // The remote list is now the base list, the file it was read from is seen
+    // so that it can be saved over
+    fn take_remote_load(&mut self) {
+        if let Some((storage, seen)) = self.remote_load.take() {
+            self.storage = storage;
+            self.file_watcher = seen;
+        }
+    }
+
+    // Combine the local edits with the changes made on disk, returning the
+    // names of the tasks changed on both sides
+    pub fn merge_remote_list(&mut self) -> Vec<String> {
+        let Some(remote_list) = self.remote_list.take() else {
+            return vec![];
+        };
+        self.take_remote_load();
+        let merged = merge_todo_lists(&self.base_list, &self.todo_list.items, &remote_list);
+        self.replace_todo_list(merged.tasks, remote_list);
+        merged.conflicts
+    }
+
+    // Take the lock on the file before changing the list. Returns false when
+    // another instance is editing it, in which case the list stays read-only.
+    pub fn lock(&mut self) -> io::Result<bool> {
+        if self.lock.is_some() {
+            return Ok(true);
+        }
+        match FileLock::acquire(&self.file_path)? {
+            LockStatus::Acquired(lock) => {
+                self.lock = Some(lock);
+                self.locked_by = None;
+                Ok(true)
+            }
+            LockStatus::HeldBy(owner) => {
+                self.locked_by = Some(owner);
+                Ok(false)
+            }
+        }
+    }
+
+    // Keep the lock alive while this instance is blocked, in $EDITOR for instance
+    pub fn keep_lock_alive(&self) -> Option<Heartbeat> {
+        self.lock.as_ref().map(FileLock::keep_alive)
+    }
+
+    // Keep the lock only while there is something to save, and notice when
+    // another instance releases it. Returns true when the list became editable.
+    pub fn sync_lock(&mut self, editing: bool) -> bool {
+        let needs_lock = editing || self.has_unsaved_changes();
+        if let Some(lock) = &mut self.lock {
+            if !needs_lock {
+                self.lock = None;
+            } else if !lock.refresh() {
+                // Another instance took the lock over, stop editing
+                self.lock = None;
+                self.locked_by = FileLock::owner(&self.file_path);
+            }
+        } else if self.locked_by.is_some() {
+            self.locked_by = FileLock::owner(&self.file_path);
+            return self.locked_by.is_none();
+        }
+        false
+    }
+
+    // Move the done tasks to the archive file, only those done for at least
+    // `older_than_days` days when given, and save the list without them.
+    // Returns the number of tasks archived.
+    pub fn archive_done_tasks(&mut self, older_than_days: Option<u32>) -> Result<usize> {
+        let now = now();
+        let archived: Vec<Task> = self
+            .todo_list
+            .items
+            .iter()
+            .filter(|task| is_archivable(task, older_than_days, now))
+            .cloned()
+            .collect();
+        let count = archived.len();
+        if count == 0 {
+            return Ok(0);
+        }
+
+        // Only remove the tasks from the list once they are safely archived
+        archive_tasks(&self.file_path, archived)?;
+        self.todo_list
+            .items
+            .retain(|task| !is_archivable(task, older_than_days, now));
+        self.clamp_selection();
+        // Left unsaved, the tasks would be both in the list and the archive
+        self.save()?;
+        Ok(count)
+    }
+
+    // Done tasks read from a file written before completion dates were
+    // recorded are considered done from now on
+    pub fn date_done_tasks(&mut self) {
+        let now = now();
+        for task in &mut self.todo_list.items {
+            if task.status == Status::Done && task.completed_at.is_none() {
+                task.completed_at = Some(now);
+            }
+        }
+    }
+
+    // Take a task out of the archive and put it back in the list, saving both
+    pub fn restore_archived_task(&mut self, index: usize) -> Result<Option<Task>> {
+        let mut archive = load_archive(&self.file_path)?;
+        if index >= archive.len() {
+            return Ok(None);
+        }
+        let task = archive.remove(index);
+        save_archive(&self.file_path, &archive)?;
+        self.todo_list.items.push(task.clone());
+        self.save()?;
+        Ok(Some(task))
+    }
+}
+
+// Whether two lists hold the same tasks in the same order, ignoring their ids
+fn same_tasks(tasks: &[Task], others: &[Task]) -> bool {
+    tasks.len() == others.len()
+        && tasks
+            .iter()
+            .zip(others)
+            .all(|(task, other)| task.same_content(other))
+}
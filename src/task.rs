@@ -1,26 +1,74 @@
 // Atomic U16 is used to generate unique IDs
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::atomic::{AtomicU16, AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::recurrence::Recurrence;
 static UNIQUE_ID: AtomicU16 = AtomicU16::new(0);
 
+// Highest id a task read from a file can have, the ids above are left for the
+// tasks created while horme runs so that they never run out and wrap around
+pub const MAX_FILE_ID: u16 = u16::MAX / 2;
+
+// Format used to write dates and times to the task files
+pub const DATE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
 // Task struct
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Task {
     pub name: String,
+    #[serde(default)]
     pub description: String,
     pub status: Status,
     pub id: u16,
-    pub due_date: String,
+    // Identifier of the task in the programs it is exported to, like the UID
+    // of an iCalendar VTODO. Unlike `id`, it is kept across runs.
+    #[serde(default)]
+    pub uid: Option<String>,
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+    // Time of the day the task is due, along with the due date
+    #[serde(default)]
+    pub due_time: Option<NaiveTime>,
+    // Day work on the task is planned to start, Org's SCHEDULED
+    #[serde(default)]
+    pub scheduled: Option<NaiveDate>,
+    #[serde(default)]
     pub subtasks: Vec<Subtask>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    // From 'A' (highest) to 'Z', as in todo.txt
+    #[serde(default)]
+    pub priority: Option<char>,
+    #[serde(default)]
+    pub created_at: Option<NaiveDateTime>,
+    // Section the task is listed in, when it doesn't follow from its status
+    // (a column of a Kanban board for instance)
+    #[serde(default)]
+    pub section: Option<String>,
+    // When work on the task started, it was last moved out of Todo
+    #[serde(default)]
+    pub started_at: Option<NaiveDateTime>,
+    // When the task was last marked as done
+    #[serde(default)]
+    pub completed_at: Option<NaiveDateTime>,
+    // How often the task comes back once it is done
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct Subtask {
     pub name: String,
+    #[serde(rename = "done")]
     pub status: bool,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Status {
     Todo,
     Doing,
@@ -43,26 +91,56 @@ impl Task {
     pub fn new(name: String, description: String, status: Status, id: Option<u16>) -> Self {
         // If id is provided, use that id, otherwise generate a new id
         let new_id = match id {
-            Some(id) => id,
-            None => UNIQUE_ID.fetch_add(1, Ordering::SeqCst),
+            Some(id) => {
+                reserve_id(id);
+                id
+            }
+            None => next_id(),
         };
         Self {
             name,
             description,
             status,
             id: new_id,
-            due_date: "".to_string(),
+            uid: None,
+            due_date: None,
+            due_time: None,
+            scheduled: None,
             subtasks: vec![],
+            tags: vec![],
+            priority: None,
+            created_at: None,
+            section: None,
+            started_at: None,
+            completed_at: None,
+            recurrence: None,
         }
     }
 
     // update the task status
     pub fn update_status(&mut self) {
-        match self.status {
-            Status::Todo => self.status = Status::Doing,
-            Status::Doing => self.status = Status::Done,
-            Status::Done => self.status = Status::Todo,
+        self.set_status(match self.status {
+            Status::Todo => Status::Doing,
+            Status::Doing => Status::Done,
+            Status::Done => Status::Todo,
+        });
+    }
+
+    // move the task to a status, noting when work on it starts and when it
+    // gets done. A task done straight from Todo was never worked on as far
+    // as we know and has no start, rather than one at the time it was done.
+    pub fn set_status(&mut self, status: Status) {
+        if status == Status::Todo {
+            self.started_at = None;
+        } else if self.status == Status::Todo && status == Status::Doing {
+            self.started_at = Some(now());
+        }
+        if status != Status::Done {
+            self.completed_at = None;
+        } else if self.status != Status::Done {
+            self.completed_at = Some(now());
         }
+        self.status = status;
     }
 
     pub fn add_description(&mut self, description: &str) {
@@ -100,6 +178,82 @@ impl Task {
     pub fn get_status(&self) -> Status {
         self.status
     }
+
+    // copy of the task with a newly generated id, and uid if it has one
+    pub fn duplicate(&self) -> Self {
+        Self {
+            id: next_id(),
+            uid: self.uid.as_ref().map(|_| new_uid()),
+            ..self.clone()
+        }
+    }
+
+    // the next occurrence of a recurring task, to do again with its subtasks
+    // unchecked. It is due on the first date of the recurrence after today.
+    // The recurrence moves to the new task, so that reopening and completing
+    // this one again doesn't add another occurrence.
+    pub fn next_occurrence(&mut self, today: NaiveDate) -> Option<Self> {
+        let recurrence = self.recurrence.take()?;
+        let mut due_date = recurrence.next_date(self.due_date.unwrap_or(today));
+        while due_date <= today {
+            due_date = recurrence.next_date(due_date);
+        }
+        let mut task = self.duplicate();
+        task.recurrence = Some(recurrence);
+        task.status = Status::Todo;
+        task.due_date = Some(due_date);
+        task.created_at = Some(now());
+        task.uid = Some(new_uid());
+        task.started_at = None;
+        task.completed_at = None;
+        for subtask in &mut task.subtasks {
+            subtask.status = false;
+        }
+        Some(task)
+    }
+
+    // compare two tasks ignoring their ids
+    pub fn same_content(&self, other: &Task) -> bool {
+        let task = Task {
+            id: other.id,
+            uid: other.uid.clone(),
+            ..self.clone()
+        };
+        task == *other
+    }
+}
+
+// a new id, not used by any other task of this run
+pub fn next_id() -> u16 {
+    UNIQUE_ID.fetch_add(1, Ordering::SeqCst)
+}
+
+// a uid for a new task, unique across lists and runs
+pub fn new_uid() -> String {
+    static UIDS: AtomicU32 = AtomicU32::new(0);
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "{:x}-{:x}-{:x}-{:x}@horme",
+        time.as_secs(),
+        time.subsec_nanos(),
+        std::process::id(),
+        UIDS.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+// make sure the ids generated from now on don't clash with an id read from a file
+pub fn reserve_id(id: u16) {
+    UNIQUE_ID.fetch_max(id.saturating_add(1), Ordering::SeqCst);
+}
+
+// current local time, to the minute as it is written to the task files
+pub fn now() -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    now.with_second(0)
+        .and_then(|now| now.with_nanosecond(0))
+        .unwrap_or(now)
 }
 
 impl Subtask {
@@ -0,0 +1,96 @@
+use color_eyre::Result;
+use serde::Deserialize;
+
+use crate::ical::parse_date_time;
+use crate::markdown::{Diagnostic, Severity};
+use crate::task::{Status, Task};
+
+// A task of `task export`, only the fields horme has a place for
+#[derive(Debug, Deserialize)]
+struct TaskwarriorTask {
+    #[serde(default)]
+    uuid: Option<String>,
+    description: String,
+    status: String,
+    #[serde(default)]
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    entry: Option<String>,
+    #[serde(default)]
+    start: Option<String>,
+    #[serde(default)]
+    end: Option<String>,
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Annotation {
+    description: String,
+}
+
+// Read the JSON array written by Taskwarrior's `task export`. Projects become
+// sections, started tasks are in progress, annotations make up the
+// description, and deleted tasks and recurring templates are left out.
+pub fn parse_taskwarrior(contents: &str) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+    let exported: Vec<TaskwarriorTask> = serde_json::from_str(contents)?;
+
+    let mut todo_list = vec![];
+    let mut skipped = 0;
+    for exported in exported {
+        let status = match exported.status.as_str() {
+            "completed" => Status::Done,
+            "pending" | "waiting" if exported.start.is_some() => Status::Doing,
+            "pending" | "waiting" => Status::Todo,
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let description = exported
+            .annotations
+            .iter()
+            .map(|annotation| annotation.description.as_str())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut task = Task::new(exported.description, description, status, None);
+        task.uid = exported.uuid;
+        task.section = exported.project;
+        task.tags = exported.tags;
+        task.priority = match exported.priority.as_deref() {
+            Some("H") => Some('A'),
+            Some("M") => Some('B'),
+            Some("L") => Some('C'),
+            _ => None,
+        };
+        task.due_date = exported
+            .due
+            .as_deref()
+            .and_then(parse_date_time)
+            .map(|date| date.date());
+        task.created_at = exported.entry.as_deref().and_then(parse_date_time);
+        task.started_at = exported.start.as_deref().and_then(parse_date_time);
+        if status == Status::Done {
+            task.completed_at = exported.end.as_deref().and_then(parse_date_time);
+        }
+        todo_list.push(task);
+    }
+
+    let mut diagnostics = vec![];
+    if skipped > 0 {
+        diagnostics.push(Diagnostic::new(
+            0,
+            0,
+            Severity::Warning,
+            format!("{} deleted or recurring tasks are skipped", skipped),
+        ));
+    }
+    Ok((todo_list, diagnostics))
+}
@@ -0,0 +1,111 @@
+use chrono::NaiveDate;
+use color_eyre::{eyre::eyre, Result};
+
+use crate::markdown::{Diagnostic, Severity};
+use crate::task::{Status, Subtask, Task, DATE_FORMAT};
+
+// Read a project from a Todoist CSV backup. Its sections become horme
+// sections, `@labels` in the content become tags, and tasks indented under
+// another one become its subtasks. Todoist priorities go from 4 (p1, the
+// highest) to 1 (no priority).
+pub fn parse_todoist(contents: &str) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(contents.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or_else(|| eyre!("missing the {} column", name))
+    };
+    let (kind, content, description) = (
+        column("TYPE")?,
+        column("CONTENT")?,
+        column("DESCRIPTION").ok(),
+    );
+    let (priority, indent, date) = (
+        column("PRIORITY").ok(),
+        column("INDENT").ok(),
+        column("DATE").ok(),
+    );
+
+    let mut todo_list: Vec<Task> = vec![];
+    let mut diagnostics = vec![];
+    let mut section = None;
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        // The header is the first line
+        let line_number = i + 2;
+        let field = |index: Option<usize>| index.and_then(|index| record.get(index)).unwrap_or("");
+
+        match field(Some(kind)) {
+            "section" => {
+                section = Some(field(Some(content)).to_string()).filter(|name| !name.is_empty());
+                continue;
+            }
+            "task" => {}
+            // Notes and empty lines
+            _ => continue,
+        }
+
+        let (name, tags) = split_labels(field(Some(content)));
+        if name.is_empty() {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                1,
+                Severity::Error,
+                "task without a name".to_string(),
+            ));
+            continue;
+        }
+
+        // Nested tasks are subtasks of the last top-level task
+        let level = field(indent).parse::<u32>().unwrap_or(1);
+        if level > 1 {
+            if let Some(parent) = todo_list.last_mut() {
+                parent.subtasks.push(Subtask::new(name));
+                continue;
+            }
+        }
+
+        let mut task = Task::new(name, field(description).to_string(), Status::Todo, None);
+        task.tags = tags;
+        task.section = section.clone();
+        task.priority = match field(priority) {
+            "4" => Some('A'),
+            "3" => Some('B'),
+            "2" => Some('C'),
+            _ => None,
+        };
+        // Dates are written as the user typed them, only plain dates are kept
+        let date = field(date);
+        if !date.is_empty() {
+            match NaiveDate::parse_from_str(date.get(..10).unwrap_or(date), DATE_FORMAT) {
+                Ok(date) => task.due_date = Some(date),
+                Err(_) => diagnostics.push(Diagnostic::new(
+                    line_number,
+                    1,
+                    Severity::Warning,
+                    format!("due date \"{}\" is not a date and is skipped", date),
+                )),
+            }
+        }
+        todo_list.push(task);
+    }
+
+    Ok((todo_list, diagnostics))
+}
+
+// Take the `@labels` out of the content of a task
+fn split_labels(content: &str) -> (String, Vec<String>) {
+    let mut tags = vec![];
+    let mut words = vec![];
+    for word in content.split_whitespace() {
+        match word.strip_prefix('@') {
+            Some(label) if !label.is_empty() => tags.push(label.to_string()),
+            _ => words.push(word),
+        }
+    }
+    (words.join(" "), tags)
+}
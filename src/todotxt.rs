@@ -0,0 +1,313 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::markdown::{Diagnostic, Severity};
+use crate::recurrence::{Recurrence, Unit};
+use crate::task::{Status, Subtask, Task, DATE_FORMAT};
+
+// Format of the times written in `key:value` pairs, which can't contain spaces
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+
+// Read a list in the todo.txt format (https://github.com/todotxt/todo.txt):
+//
+//     x 2026-10-02 2026-09-30 Call the plumber +house @phone due:2026-10-01
+//     (A) 2026-09-30 Fix the build +horme status:doing due:2026-10-01T09:30
+//
+// `+project` and `@context` become tags (contexts keep their `@`), `due:` the
+// due date and time, `t:` (the threshold date) the scheduled date and `rec:`
+// the recurrence, as `2w` or `monday,thursday`. `status:doing` marks a task in
+// progress, which todo.txt has no notion of, and `started:` when it started.
+// `note:` holds the description, `sub:` and `subdone:` the subtasks and
+// `section:` the section, percent-encoded as values can't contain spaces.
+// `created:` is the creation date of done tasks without a completion date,
+// which can't be written before the name. Other `key:value` pairs are kept in
+// the name.
+pub fn parse_todo_txt(contents: &str) -> (Vec<Task>, Vec<Diagnostic>) {
+    let mut todo_list = vec![];
+    let mut diagnostics = vec![];
+
+    for (i, line) in contents.lines().enumerate() {
+        let line_number = i + 1;
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+
+        let mut task = Task::new(String::new(), String::new(), Status::Todo, None);
+        if words.next_if_eq(&"x").is_some() {
+            task.status = Status::Done;
+            task.completed_at = words
+                .next_if(|word| parse_date(word).is_some())
+                .and_then(parse_date);
+        } else if let Some(priority) = words.next_if(|word| parse_priority(word).is_some()) {
+            task.priority = parse_priority(priority);
+        }
+        task.created_at = words
+            .next_if(|word| parse_date(word).is_some())
+            .and_then(parse_date);
+
+        let mut name = vec![];
+        for word in words {
+            match word.split_once(':') {
+                _ if word.len() > 1 && word.starts_with('+') => {
+                    task.tags.push(word[1..].to_string())
+                }
+                _ if word.len() > 1 && word.starts_with('@') => task.tags.push(word.to_string()),
+                Some((key @ ("due" | "t" | "started" | "created" | "rec"), value)) => {
+                    if let Err(message) = parse_field(&mut task, key, value) {
+                        diagnostics.push(Diagnostic::new(
+                            line_number,
+                            column_of(line, word),
+                            Severity::Warning,
+                            message,
+                        ));
+                        name.push(word);
+                    }
+                }
+                // Done tasks keep their priority as `pri:A`
+                Some(("pri", value)) if parse_priority(&format!("({})", value)).is_some() => {
+                    task.priority = value.chars().next();
+                }
+                Some(("status", "doing")) if task.status == Status::Todo => {
+                    task.status = Status::Doing;
+                }
+                Some(("note", value)) if !value.is_empty() => {
+                    if !task.description.is_empty() {
+                        task.description.push('\n');
+                    }
+                    task.description.push_str(&decode(value));
+                }
+                Some((key @ ("sub" | "subdone"), value)) if !value.is_empty() => {
+                    task.subtasks.push(Subtask {
+                        name: decode(value),
+                        status: key == "subdone",
+                    });
+                }
+                Some(("section", value)) if !value.is_empty() => {
+                    task.section = Some(decode(value));
+                }
+                _ => name.push(word),
+            }
+        }
+
+        if name.is_empty() {
+            diagnostics.push(Diagnostic::new(
+                line_number,
+                1,
+                Severity::Error,
+                "task without a name".to_string(),
+            ));
+            continue;
+        }
+        task.name = name.join(" ");
+        todo_list.push(task);
+    }
+
+    (todo_list, diagnostics)
+}
+
+// Write the tasks in the todo.txt format
+pub fn render_todo_txt(todo_list: &[Task]) -> String {
+    let mut data = String::new();
+    for task in todo_list {
+        let mut words = vec![];
+        if task.status == Status::Done {
+            words.push("x".to_string());
+            // The creation date can only be written after a completion date,
+            // it is written with the other fields without one
+            if let Some(date) = task.completed_at {
+                words.push(date.format(DATE_FORMAT).to_string());
+                words.extend(
+                    task.created_at
+                        .map(|date| date.format(DATE_FORMAT).to_string()),
+                );
+            }
+        } else {
+            words.extend(task.priority.map(|priority| format!("({})", priority)));
+            words.extend(
+                task.created_at
+                    .map(|date| date.format(DATE_FORMAT).to_string()),
+            );
+        }
+        words.push(task.name.clone());
+        for tag in &task.tags {
+            if tag.starts_with('@') {
+                words.push(tag.clone());
+            } else {
+                words.push(format!("+{}", tag));
+            }
+        }
+        match (task.due_date, task.due_time) {
+            (Some(date), Some(time)) => words.push(format!(
+                "due:{}",
+                date.and_time(time).format(DATE_TIME_FORMAT)
+            )),
+            (Some(date), None) => words.push(format!("due:{}", date.format(DATE_FORMAT))),
+            _ => {}
+        }
+        words.extend(
+            task.scheduled
+                .map(|date| format!("t:{}", date.format(DATE_FORMAT))),
+        );
+        words.extend(
+            task.recurrence
+                .as_ref()
+                .map(|recurrence| format!("rec:{}", format_recurrence(recurrence))),
+        );
+        if task.status == Status::Doing {
+            words.push("status:doing".to_string());
+        }
+        words.extend(
+            task.started_at
+                .map(|date| format!("started:{}", date.format(DATE_TIME_FORMAT))),
+        );
+        if task.status == Status::Done && task.completed_at.is_none() {
+            words.extend(
+                task.created_at
+                    .map(|date| format!("created:{}", date.format(DATE_FORMAT))),
+            );
+        }
+        if task.status == Status::Done {
+            words.extend(task.priority.map(|priority| format!("pri:{}", priority)));
+        }
+        if !task.description.is_empty() {
+            words.push(format!("note:{}", encode(&task.description)));
+        }
+        for subtask in &task.subtasks {
+            let key = if subtask.status { "subdone" } else { "sub" };
+            words.push(format!("{}:{}", key, encode(&subtask.name)));
+        }
+        if let Some(section) = &task.section {
+            words.push(format!("section:{}", encode(section)));
+        }
+
+        data.push_str(&words.join(" "));
+        data.push('\n');
+    }
+    data
+}
+
+// Set the task field of a `key:value` pair that holds a date or a recurrence
+fn parse_field(task: &mut Task, key: &str, value: &str) -> Result<(), String> {
+    let date_time = || {
+        NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT)
+            .ok()
+            .or_else(|| parse_date(value))
+            .ok_or_else(|| {
+                format!(
+                    "invalid date \"{}\" in {}:, expected YYYY-MM-DD[THH:MM]",
+                    value, key
+                )
+            })
+    };
+    match key {
+        "due" => {
+            let date = date_time()?;
+            task.due_date = Some(date.date());
+            task.due_time = value.contains('T').then(|| date.time());
+        }
+        "t" => task.scheduled = Some(date_time()?.date()),
+        "started" => task.started_at = Some(date_time()?),
+        "created" => task.created_at = Some(date_time()?),
+        _ => task.recurrence = Some(parse_recurrence(value)?),
+    }
+    Ok(())
+}
+
+// A recurrence as a `rec:` value: `1d`, `2w`, `3m` and `1y` as in the other
+// programs that read it, or the days of the week, `monday,thursday`
+fn format_recurrence(recurrence: &Recurrence) -> String {
+    match recurrence {
+        Recurrence::Every(count, unit) => {
+            let unit = match unit {
+                Unit::Day => 'd',
+                Unit::Week => 'w',
+                Unit::Month => 'm',
+                Unit::Year => 'y',
+            };
+            format!("{}{}", count, unit)
+        }
+        Recurrence::On(_) => recurrence
+            .to_string()
+            .trim_start_matches("every ")
+            .replace(", ", ","),
+    }
+}
+
+fn parse_recurrence(value: &str) -> Result<Recurrence, String> {
+    // A leading `+` counts from the due date rather than the completion date
+    // in other programs, horme always counts from the due date
+    let interval = value.strip_prefix('+').unwrap_or(value);
+    let (count, unit) = interval.split_at(interval.len().saturating_sub(1));
+    let unit = match unit {
+        "d" => Unit::Day,
+        "w" => Unit::Week,
+        "m" => Unit::Month,
+        "y" => Unit::Year,
+        _ => return format!("every {}", value).parse(),
+    };
+    match count.parse::<u32>() {
+        Ok(count) if count > 0 => Ok(Recurrence::Every(count, unit)),
+        _ => format!("every {}", value).parse(),
+    }
+}
+
+// Percent-encode the characters that would end a value: whitespace, and `%`
+// itself
+fn encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for c in text.chars() {
+        if c == '%' || c.is_whitespace() {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+fn decode(value: &str) -> String {
+    let mut bytes = vec![];
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match hex {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+// A date at the start of a todo.txt line, read as midnight
+fn parse_date(word: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT)
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+// A priority written as `(A)`
+fn parse_priority(word: &str) -> Option<char> {
+    let priority = word.strip_prefix('(')?.strip_suffix(')')?;
+    let mut chars = priority.chars();
+    match (chars.next(), chars.next()) {
+        (Some(priority), None) if priority.is_ascii_uppercase() => Some(priority),
+        _ => None,
+    }
+}
+
+// Column of a word of the line, counted from 1
+fn column_of(line: &str, word: &str) -> usize {
+    line.find(word).map_or(1, |i| i + 1)
+}
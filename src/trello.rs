@@ -0,0 +1,140 @@
+use chrono::{DateTime, Local};
+use color_eyre::Result;
+use serde::Deserialize;
+
+use crate::checklist::lane_status;
+use crate::markdown::{Diagnostic, Severity};
+use crate::task::{Status, Subtask, Task};
+
+// The parts of a Trello board export (Menu > Print, export and share > JSON)
+// that horme has a place for
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Board {
+    lists: Vec<List>,
+    cards: Vec<Card>,
+    #[serde(default)]
+    checklists: Vec<Checklist>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct List {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    id_list: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    labels: Vec<Label>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checklist {
+    id_card: String,
+    #[serde(default)]
+    check_items: Vec<CheckItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckItem {
+    name: String,
+    state: String,
+    #[serde(default)]
+    pos: f64,
+}
+
+// Read the cards of a Trello board. Lists named after a status (Todo,
+// Doing, Done...) set the status of their cards, the others become sections.
+// Labels become tags, checklists subtasks, and archived cards and lists are
+// left out.
+pub fn parse_trello(contents: &str) -> Result<(Vec<Task>, Vec<Diagnostic>)> {
+    let board: Board = serde_json::from_str(contents)?;
+
+    let mut todo_list = vec![];
+    let mut skipped = 0;
+    for card in &board.cards {
+        let Some(list) = board.lists.iter().find(|list| list.id == card.id_list) else {
+            skipped += 1;
+            continue;
+        };
+        if card.closed || list.closed {
+            skipped += 1;
+            continue;
+        }
+
+        let list_status = lane_status(&list.name);
+        let status = match list_status {
+            _ if card.due_complete => Status::Done,
+            Some(status) => status,
+            None => Status::Todo,
+        };
+        let mut task = Task::new(card.name.clone(), card.desc.clone(), status, None);
+        if list_status.is_none() {
+            task.section = Some(list.name.clone());
+        }
+        task.tags = card
+            .labels
+            .iter()
+            .filter_map(|label| match label.name.as_str() {
+                // Labels without a name are only a colour
+                "" => label.color.clone(),
+                name => Some(name.replace(' ', "-")),
+            })
+            .collect();
+        task.due_date = card
+            .due
+            .as_deref()
+            .and_then(|due| DateTime::parse_from_rfc3339(due).ok())
+            .map(|due| due.with_timezone(&Local).date_naive());
+
+        for checklist in board
+            .checklists
+            .iter()
+            .filter(|checklist| checklist.id_card == card.id)
+        {
+            let mut items: Vec<&CheckItem> = checklist.check_items.iter().collect();
+            items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+            task.subtasks.extend(items.into_iter().map(|item| Subtask {
+                name: item.name.clone(),
+                status: item.state == "complete",
+            }));
+        }
+        todo_list.push(task);
+    }
+
+    let mut diagnostics = vec![];
+    if skipped > 0 {
+        diagnostics.push(Diagnostic::new(
+            0,
+            0,
+            Severity::Warning,
+            format!("{} archived cards are skipped", skipped),
+        ));
+    }
+    Ok((todo_list, diagnostics))
+}
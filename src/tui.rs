@@ -4,13 +4,13 @@ use color_eyre::config::HookBuilder;
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
+        event::{DisableFocusChange, EnableFocusChange},
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
         ExecutableCommand,
     },
     terminal::Terminal,
 };
 
-#[allow(dead_code)]
 pub fn init_error_hooks() -> color_eyre::Result<()> {
     let (panic, error) = HookBuilder::default().into_hooks();
     let panic = panic.into_panic_hook();
@@ -26,14 +26,16 @@ pub fn init_error_hooks() -> color_eyre::Result<()> {
     Ok(())
 }
 
-#[allow(dead_code)]
 pub fn init_terminal() -> io::Result<Terminal<impl Backend>> {
     stdout().execute(EnterAlternateScreen)?;
+    // Lists are looked at again when the terminal gets the focus back
+    stdout().execute(EnableFocusChange)?;
     enable_raw_mode()?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 pub fn restore_terminal() -> io::Result<()> {
+    stdout().execute(DisableFocusChange)?;
     stdout().execute(LeaveAlternateScreen)?;
     disable_raw_mode()
 }
@@ -1,126 +1,249 @@
-use crate::task::Task;
-use crate::utils;
-use crate::Status;
+use std::io::{self, IsTerminal, Write};
+
+use crate::bulk::{change_status, select_tasks, Selection, TagEdit};
+use crate::editor::{edit_list_in_editor, edit_task_in_editor, editor_command};
+use crate::output::{print_tasks, OutputFormat};
+use crate::query::Query;
+use crate::quickadd::parse_quick_add;
+use crate::task::{new_uid, now, Task, DATE_FORMAT};
 
 // Add a new task
 pub fn add_task(args: Vec<&str>, todo_list: &mut Vec<Task>) {
     if let Some(name) = args.get(1) {
-        if let Some(description) = args.get(2) {
-            let task = Task::new(
-                name.to_string(),
-                description.to_string(),
-                Status::Todo,
-                None,
-            );
-            todo_list.push(task);
-        } else {
-            let task = Task::new(name.to_string(), "".to_string(), Status::Todo, None);
-            todo_list.push(task);
+        // The name can hold the due date, tags, priority and recurrence
+        let description = args.get(2).unwrap_or(&"");
+        let quick_add = parse_quick_add(name, now().date());
+        // Tell what was taken out of the name, so that no word goes missing
+        let fields: Vec<String> = quick_add
+            .describe()
+            .into_iter()
+            .map(|(field, value)| format!("{}: {}", field.to_lowercase(), value))
+            .collect();
+        if !fields.is_empty() {
+            println!("Added \"{}\", {}", quick_add.name, fields.join(", "));
         }
+        let mut task = quick_add.into_task(description.to_string());
+        task.created_at = Some(now());
+        task.uid = Some(new_uid());
+        todo_list.push(task);
     } else {
         println!("Please provide a task name and description");
     }
 }
 
-// Print tasks in a nice table
-pub fn list_tasks(args: Vec<&str>, todo_list: &Vec<Task>) {
-    if todo_list.is_empty() {
-        println!("No tasks found");
-        return;
+// Print the tasks matching a query, like `list status:todo tag:infra`. The
+// flags of the older versions are read as queries.
+pub fn list_tasks(args: Vec<&str>, todo_list: &[Task]) {
+    let mut terms = vec![];
+    let mut format = OutputFormat::default();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg {
+            "--format" => match args.next().and_then(OutputFormat::from_name) {
+                Some(name) => format = name,
+                None => {
+                    eprintln!(
+                        "Please provide a format after --format, one of: {}",
+                        OutputFormat::NAMES.join(", ")
+                    );
+                    return;
+                }
+            },
+            "--all" | "-a" => {}
+            "--completed" | "-c" => terms.push("status:done".to_string()),
+            "--incomplete" | "-i" => terms.push("-status:done".to_string()),
+            "--due" | "-d" => terms.push(format!("due:{}", args.next().unwrap_or_default())),
+            arg => terms.push(arg.to_string()),
+        }
     }
-
-    for task in todo_list {
-        if args.len() == 1 || args.contains(&"--all") || args.contains(&"-a") {
-            utils::print_task(task);
-        } else if args.contains(&"--completed")
-            || args.contains(&"-c") && task.status == Status::Done
-        {
-            utils::print_task(task);
-        } else if args.contains(&"--incomplete")
-            || args.contains(&"-i") && task.status != Status::Done
-        {
-            utils::print_task(task);
-        } else if args.contains(&"--due") || args.contains(&"-d") && task.due_date == args[2] {
-            utils::print_task(task);
+    let input = terms.join(" ");
+    let query = match Query::parse(&input) {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("{}", e.pointing_at(&input));
+            return;
         }
+    };
+
+    let today = now().date();
+    let tasks: Vec<Task> = todo_list
+        .iter()
+        .filter(|task| query.matches(task, today))
+        .cloned()
+        .collect();
+    if let Err(e) = print_tasks(&tasks, format) {
+        eprintln!("Unable to print the tasks: {}", e);
     }
 }
 
-// Delete a tasks
+// Delete tasks: delete 3 5 8-12, delete --yes status:done tag:old. The tasks
+// picked by a query are only deleted after a confirmation or with --yes.
 pub fn delete_task(args: Vec<&str>, todo_list: &mut Vec<Task>) {
-    // Check if id is present in todo_list
-    if let Some(id) = args.get(1) {
-        if let Ok(id) = id.parse::<u16>() {
-            if utils::check_id(id, todo_list) {
-                todo_list.retain(|task| task.id != id);
-            } else {
-                println!("Please provide a valid task id (run <list> to see task ids)");
+    let yes = args.iter().any(|arg| matches!(*arg, "--yes" | "-y"));
+    let args: Vec<&str> = args
+        .into_iter()
+        .skip(1)
+        .filter(|arg| !matches!(*arg, "--yes" | "-y"))
+        .collect();
+    let ids = match select_tasks(&args, todo_list, now().date()) {
+        Ok(selection) if selection.ids().is_empty() => {
+            println!("No tasks found");
+            return;
+        }
+        Ok(Selection::Query(ids)) if !yes => {
+            if !confirm(&format!("Delete {} tasks matching the query?", ids.len())) {
+                println!("Nothing was deleted, pass --yes to delete without asking");
+                return;
             }
-        } else {
-            println!("Please provide a valid task id (run <list> to see task ids)");
+            ids
         }
-        // TODO: Add reordering of ids
-    } else {
-        println!("Please provide a valid task id (run <list> to see task ids)");
+        Ok(selection) => selection.ids().to_vec(),
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    todo_list.retain(|task| !ids.contains(&task.id));
+    if ids.len() > 1 {
+        println!("Deleted {} tasks", ids.len());
     }
 }
 
-// Edit a task
-pub fn edit_task(args: Vec<&str>, todo_list: &mut Vec<Task>) {
-    let mut task_found = false;
-    for task in todo_list {
-        if task.id == args[1].parse::<u16>().unwrap() {
-            task_found = true;
-            match args[2] {
-                "--name" | "-n" => {
-                    task.name = args[3].to_string();
-                }
-                "--description" | "-d" => {
-                    task.description = args[3].to_string();
-                }
-                _ => {
-                    println!("Please provide a valid task id (run <list> to see task ids)");
-                }
-            }
-            break;
-        }
+// Ask a yes or no question on the terminal, no when stdin isn't one
+fn confirm(question: &str) -> bool {
+    if !io::stdin().is_terminal() {
+        return false;
     }
-    if !task_found {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+// Edit a task
+pub fn edit_task(args: Vec<&str>, todo_list: &mut [Task]) {
+    let id = args.get(1).and_then(|id| id.parse::<u16>().ok());
+    let Some(task) = todo_list.iter_mut().find(|task| Some(task.id) == id) else {
         println!("Please provide a valid task id (run <list> to see task ids)");
+        return;
+    };
+    match (args.get(2), args.get(3)) {
+        (Some(&"--name" | &"-n"), Some(name)) => {
+            task.name = name.to_string();
+        }
+        (Some(&"--description" | &"-d"), Some(description)) => {
+            task.description = description.to_string();
+        }
+        (Some(&"--editor" | &"-e"), None) => match edit_task_in_editor(task, &editor_command()) {
+            Ok(edited) => *task = edited,
+            Err(e) => println!("{}", e),
+        },
+        _ => {
+            println!(
+                "Please provide --name or --description followed by the new value, or --editor"
+            );
+        }
     }
 }
 
-// Complete a task
-pub fn complete_task(args: Vec<&str>, todo_list: &mut Vec<Task>) {
-    // Check if id is present in todo_list and update the status
-    if let Some(id) = args.get(1) {
-        if let Ok(id) = id.parse::<u16>() {
-            for task in todo_list.iter_mut() {
-                if task.id == id {
-                    task.update_status();
-                }
+// Edit the whole list in $EDITOR, or some tasks: edit-list, edit-list tag:infra
+pub fn edit_list(args: Vec<&str>, todo_list: &mut Vec<Task>) {
+    let ids = if args.len() > 1 {
+        match select_tasks(&args[1..], todo_list, now().date()) {
+            Ok(selection) => selection.ids().to_vec(),
+            Err(e) => {
+                println!("{}", e);
+                return;
             }
         }
     } else {
-        println!("Please provide a task id");
+        todo_list.iter().map(|task| task.id).collect()
+    };
+    match edit_list_in_editor(todo_list, &ids, &editor_command()) {
+        Ok(change_set) if change_set.is_empty() => println!("The list wasn't changed"),
+        Ok(change_set) => {
+            println!("Applied the changes: {}", change_set);
+            *todo_list = change_set.after;
+        }
+        Err(e) => println!("{}", e),
+    }
+}
+
+// Move tasks to their next status: complete 3 5 8-12, complete status:doing
+pub fn complete_task(args: Vec<&str>, todo_list: &mut Vec<Task>) {
+    let today = now().date();
+    let ids = match select_tasks(&args[1..], todo_list, today) {
+        Ok(selection) if selection.ids().is_empty() => {
+            println!("No tasks found");
+            return;
+        }
+        Ok(selection) => selection.ids().to_vec(),
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    // A recurring task comes back once it is done
+    for task in change_status(todo_list, &ids, None, today) {
+        println!(
+            "\"{}\" is due again on {}",
+            task.name,
+            task.due_date.unwrap_or_default().format(DATE_FORMAT)
+        );
     }
 }
 
+// Add and remove tags: tag +infra -old 3 5, tag +infra status:todo
+pub fn tag_tasks(args: Vec<&str>, todo_list: &mut [Task]) {
+    let (edit, read) = TagEdit::parse(&args[1..]);
+    if edit.is_empty() {
+        println!("Please provide tags to add with +tag or remove with -tag, then the tasks");
+        return;
+    }
+    let ids = match select_tasks(&args[1 + read..], todo_list, now().date()) {
+        Ok(selection) => selection.ids().to_vec(),
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let mut changed = 0;
+    for task in todo_list.iter_mut().filter(|task| ids.contains(&task.id)) {
+        if edit.apply(task) {
+            changed += 1;
+        }
+    }
+    println!("Changed the tags of {} of {} tasks", changed, ids.len());
+}
+
 pub fn help_message() {
     // Show the list of commands and their usage
     println!("Commands:");
     println!("  add <name> <description>                            - Add a new task");
-    println!("  list, ls <OPTION>                                   - List all tasks");
-    println!("      -c  --completed                                 - List all completed tasks");
-    println!("      -i  --incomplete                                - List all incomplete tasks");
+    println!("      e.g. add \"Deploy nginx tomorrow 9am #infra !high every monday\"");
     println!(
-        "      -d  --due <date>                                - List all tasks due on <date>"
+        "  list, ls <query>                                    - List the tasks matching <query>"
     );
+    println!("      e.g. list status:todo tag:infra due<7d -tag:someday \"text\"");
+    println!("      status:<todo|doing|done>  tag:<tag>  priority:<A-Z|none>  section:<name>");
+    println!("      due|created|done <:|<|<=|>|>=> <YYYY-MM-DD|today|7d|-2w|any|none>");
+    println!("      id:<id>  is:<today|upcoming|overdue|someday>  -<term> to exclude");
+    println!("      --format <table|plain|json|markdown|csv>        - How to print the tasks");
     println!("  edit <id> <OPTION> <new_value>                      - Edit a task");
     println!("      -n  --name <new_name>                           - Edit task name");
     println!("      -d  --description <new_description>             - Edit task description");
-    println!("  delete <id>                                         - Delete a task");
-    println!("  complete <id>                                       - Complete a task");
+    println!("      -e  --editor                                    - Edit the task in $EDITOR");
+    println!(
+        "  edit-list [tasks]                                   - Edit the tasks in $EDITOR at once"
+    );
+    println!("  delete [--yes] <tasks>                              - Delete tasks");
+    println!("      --yes to delete the tasks picked by a query without asking");
+    println!(
+        "  complete <tasks>                                    - Move tasks to their next status"
+    );
+    println!("  tag <+tag|-tag>... <tasks>                          - Add or remove tags");
+    println!("      <tasks> is a list of ids and ranges like 3 5 8-12, or a query");
     println!("  exit                                                - Exit the program");
     println!("  help                                                - Show this help message");
 }
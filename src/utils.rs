@@ -1,20 +1,13 @@
 use crate::task::Task;
 
 // Check if id is present in todo_list
-pub fn check_id(id: u16, todo_list: &Vec<Task>) -> bool {
+#[allow(dead_code)]
+pub fn check_id(id: u16, todo_list: &[Task]) -> bool {
     todo_list.iter().any(|task| task.id == id)
 }
 
-// Print task
-pub fn print_task(task: &Task) {
-    println!(
-        "ID: {} - Name: {}, Description: {}, Due Date: {}",
-        task.id, task.name, task.description, task.due_date
-    );
-}
-
 // get task
 #[allow(dead_code)]
-pub fn get_task(id: u16, todo_list: &Vec<Task>) -> Option<&Task> {
+pub fn get_task(id: u16, todo_list: &[Task]) -> Option<&Task> {
     todo_list.iter().find(|task| task.id == id)
 }
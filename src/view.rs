@@ -0,0 +1,134 @@
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+
+use crate::query::Query;
+use crate::task::{Status, Task};
+
+// Days after today covered by the Upcoming view
+const UPCOMING_DAYS: u64 = 7;
+
+// Views computed from the due date and status of the tasks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmartView {
+    // Every task of the list
+    All,
+    // Tasks due today or earlier
+    Today,
+    // Tasks due in the coming week
+    Upcoming,
+    // Tasks due before today
+    Overdue,
+    // Tasks without a due date
+    Someday,
+}
+
+// A view defined in the configuration file by a query:
+//
+//     [[views]]
+//     name = "Infra"
+//     query = "tag:infra is:upcoming"
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SavedView {
+    pub name: String,
+    pub query: Query,
+}
+
+// A subset of a list, shown as a tab of the header
+#[derive(Debug, Clone, PartialEq)]
+pub enum View {
+    Smart(SmartView),
+    Saved(SavedView),
+}
+
+impl SmartView {
+    pub const ALL: [SmartView; 5] = [
+        SmartView::All,
+        SmartView::Today,
+        SmartView::Upcoming,
+        SmartView::Overdue,
+        SmartView::Someday,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        SmartView::ALL
+            .into_iter()
+            .find(|view| view.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SmartView::All => "All",
+            SmartView::Today => "Today",
+            SmartView::Upcoming => "Upcoming",
+            SmartView::Overdue => "Overdue",
+            SmartView::Someday => "Someday",
+        }
+    }
+
+    pub fn matches(self, task: &Task, today: NaiveDate) -> bool {
+        // Only the tasks left to do have somewhere to be, except in All
+        if self != SmartView::All && task.status == Status::Done {
+            return false;
+        }
+        match (self, task.due_date) {
+            (SmartView::All, _) => true,
+            (SmartView::Today, Some(date)) => date <= today,
+            (SmartView::Upcoming, Some(date)) => {
+                date > today && date <= today + Days::new(UPCOMING_DAYS)
+            }
+            (SmartView::Overdue, Some(date)) => date < today,
+            (SmartView::Someday, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl View {
+    // The smart views followed by the saved ones
+    pub fn all(saved: &[SavedView]) -> Vec<View> {
+        SmartView::ALL
+            .into_iter()
+            .map(View::Smart)
+            .chain(saved.iter().cloned().map(View::Saved))
+            .collect()
+    }
+
+    // Find a view by its name, ignoring case
+    pub fn from_name(name: &str, saved: &[SavedView]) -> Option<View> {
+        View::all(saved)
+            .into_iter()
+            .find(|view| view.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            View::Smart(view) => view.name(),
+            View::Saved(view) => &view.name,
+        }
+    }
+
+    pub fn matches(&self, task: &Task, today: NaiveDate) -> bool {
+        match self {
+            View::Smart(view) => view.matches(task, today),
+            View::Saved(view) => view.query.matches(task, today),
+        }
+    }
+
+    // Indices of the tasks in the view. Smart views of dated tasks list them
+    // by due date, the others keep the order of the list.
+    pub fn select(&self, todo_list: &[Task], today: NaiveDate) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..todo_list.len())
+            .filter(|i| self.matches(&todo_list[*i], today))
+            .collect();
+        if matches!(
+            self,
+            View::Smart(SmartView::Today | SmartView::Upcoming | SmartView::Overdue)
+        ) {
+            // Sorting is stable, tasks due at the same time keep their order
+            indices.sort_by_key(|i| (todo_list[*i].due_date, todo_list[*i].due_time));
+        }
+        indices
+    }
+}
@@ -0,0 +1,52 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+// Keeps track of the last known state of a file on disk, so that changes made
+// by other programs (an editor, git, ...) can be noticed
+#[derive(Debug, Clone)]
+pub struct FileWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+    last_len: Option<u64>,
+}
+
+impl FileWatcher {
+    pub fn new(path: &Path) -> Self {
+        let mut watcher = Self {
+            path: path.to_path_buf(),
+            last_modified: None,
+            last_len: None,
+        };
+        watcher.mark_seen();
+        watcher
+    }
+
+    // Remember the current state of the file as the one we know about
+    pub fn mark_seen(&mut self) {
+        let (modified, len) = Self::stat(&self.path);
+        self.last_modified = modified;
+        self.last_len = len;
+    }
+
+    // Forget the state of the file, so that it is seen as changed
+    pub fn forget(&mut self) {
+        self.last_modified = None;
+        self.last_len = None;
+    }
+
+    // Check whether the file changed since the last time it was seen
+    pub fn has_changed(&self) -> bool {
+        let (modified, len) = Self::stat(&self.path);
+        modified != self.last_modified || len != self.last_len
+    }
+
+    fn stat(path: &Path) -> (Option<SystemTime>, Option<u64>) {
+        match fs::metadata(path) {
+            Ok(metadata) => (metadata.modified().ok(), Some(metadata.len())),
+            Err(_) => (None, None),
+        }
+    }
+}